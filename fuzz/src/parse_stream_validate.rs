@@ -17,7 +17,7 @@ compile_error!("Fuzz targets need cfg=dnssec_prover_fuzzing");
 
 extern crate dnssec_prover;
 use dnssec_prover::ser::parse_rr_stream;
-use dnssec_prover::validation::verify_rr_stream;
+use dnssec_prover::validation::{minimize_proof, verify_rr_stream};
 
 #[cfg(feature = "afl")]
 #[macro_use] extern crate afl;
@@ -26,6 +26,7 @@ fn main() {
 	fuzz!(|data| {
 		let _ = parse_rr_stream(data).as_ref()
 			.map(|rrs| verify_rr_stream(rrs));
+		let _ = minimize_proof(data);
 	});
 }
 
@@ -37,6 +38,7 @@ fn main() {
 		fuzz!(|data| {
 			let _ = parse_rr_stream(data).as_ref()
 				.map(|rrs| verify_rr_stream(rrs));
+			let _ = minimize_proof(data);
 		});
 	}
 }
@@ -47,6 +49,7 @@ fn main() {
 fuzz_target!(|data: &[u8]| {
 	let _ = parse_rr_stream(data).as_ref()
 		.map(|rrs| verify_rr_stream(rrs));
+	let _ = minimize_proof(data);
 });
 
 #[cfg(feature = "stdin_fuzz")]
@@ -57,6 +60,7 @@ fn main() {
 	std::io::stdin().read_to_end(&mut data).unwrap();
 	let _ = parse_rr_stream(&data).as_ref()
 		.map(|rrs| verify_rr_stream(rrs));
+	let _ = minimize_proof(&data);
 }
 
 #[test]
@@ -72,6 +76,7 @@ fn run_test_cases() {
 
 			let _ = parse_rr_stream(&data).as_ref()
 				.map(|rrs| verify_rr_stream(rrs));
+			let _ = minimize_proof(&data);
 		}
 	}
 }
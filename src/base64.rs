@@ -0,0 +1,76 @@
+//! Standard (RFC 4648 section 4) base64 encoding and decoding, as used in the presentation format
+//! of DNSSEC keys and signatures.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn decode_char(c: u8) -> Result<u32, ()> {
+	match c {
+		b'A'..=b'Z' => Ok((c - b'A') as u32),
+		b'a'..=b'z' => Ok((c - b'a') as u32 + 26),
+		b'0'..=b'9' => Ok((c - b'0') as u32 + 52),
+		b'+' => Ok(62),
+		b'/' => Ok(63),
+		_ => Err(()),
+	}
+}
+
+/// Encodes the given bytes as padded base64.
+pub(crate) fn encode(data: &[u8]) -> String {
+	let mut res = String::with_capacity((data.len() + 2) / 3 * 4);
+	for chunk in data.chunks(3) {
+		let mut buf = [0u8; 3];
+		buf[..chunk.len()].copy_from_slice(chunk);
+		let val = (buf[0] as u32) << 16 | (buf[1] as u32) << 8 | buf[2] as u32;
+		for i in 0..4 {
+			if i <= chunk.len() {
+				res.push(ALPHABET[(val >> (18 - 6 * i)) as usize & 0x3f] as char);
+			} else {
+				res.push('=');
+			}
+		}
+	}
+	res
+}
+
+/// Decodes padded base64, rejecting non-canonical encodings.
+pub(crate) fn decode(data: &str) -> Result<Vec<u8>, ()> {
+	let data = data.as_bytes();
+	if data.len() % 4 != 0 { return Err(()); }
+	let mut res = Vec::with_capacity(data.len() / 4 * 3);
+	for (idx, chunk) in data.chunks(4).enumerate() {
+		let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+		if padding > 2 || (padding != 0 && idx != data.len() / 4 - 1) { return Err(()); }
+		let mut val = 0;
+		for c in chunk[..4 - padding].iter() {
+			val = (val << 6) | decode_char(*c)?;
+		}
+		val <<= 6 * padding;
+		let bytes = [(val >> 16) as u8, (val >> 8) as u8, val as u8];
+		// Any bits beyond the last byte must be zero
+		if bytes[3 - padding..].iter().any(|b| *b != 0) { return Err(()); }
+		res.extend_from_slice(&bytes[..3 - padding]);
+	}
+	Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rfc4648_vectors() {
+		let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="),
+			("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+		for (raw, encoded) in vectors {
+			assert_eq!(encode(raw.as_bytes()), encoded);
+			assert_eq!(decode(encoded).unwrap(), raw.as_bytes());
+		}
+		assert!(decode("Zg=").is_err());
+		assert!(decode("Zh==").is_err());
+		assert!(decode("Zg==Zg==").is_err());
+		assert!(decode("Z===").is_err());
+	}
+}
@@ -1,13 +1,13 @@
 //! Simple variable-time big integer implementation
 
+#![allow(clippy::needless_lifetimes)] // lifetimes improve readability
+#![allow(clippy::needless_borrow)] // borrows indicate read-only/non-move
+#![allow(clippy::too_many_arguments)] // sometimes we don't have an option
+#![allow(clippy::identity_op)] // sometimes identities improve readability for repeated actions
+
 use alloc::vec::Vec;
 use core::marker::PhantomData;
 
-#[allow(clippy::needless_lifetimes)] // lifetimes improve readability
-#[allow(clippy::needless_borrow)] // borrows indicate read-only/non-move
-#[allow(clippy::too_many_arguments)] // sometimes we don't have an option
-#[allow(clippy::identity_op)] // sometimes identities improve readability for repeated actions
-
 // **************************************
 // * Implementations of math primitives *
 // **************************************
@@ -62,6 +62,7 @@ const fn const_subarr<'a, const N: usize, T>(a: &'a [T], start: usize) -> &'a [T
 	// a pointer to an array, basically just a pointer to the start or end. While its possible Rust
 	// could use a pointer to the end, this would be very surprising (and probably less efficient
 	// in most cases), so we just assume array references are always just pointers to the start.
+	#[allow(clippy::transmute_ptr_to_ref)]
 	unsafe { core::mem::transmute(startptr) }
 }
 
@@ -866,10 +867,6 @@ impl U4096 {
 				(64, 12, mul_64 as mul_ty, sqr_64 as sqr_ty, add as add_double_ty, sub as sub_ty)
 			};
 
-		// r is always the even value with one bit set above the word count we're using.
-		let mut r = [0; WORD_COUNT_4096 * 2];
-		r[WORD_COUNT_4096 * 2 - word_count - 1] = 1;
-
 		let mut m_inv_pos = [0; WORD_COUNT_4096];
 		m_inv_pos[WORD_COUNT_4096 - 1] = 1;
 		let mut two = [0; WORD_COUNT_4096];
@@ -79,13 +79,13 @@ pub(super) trait Curve : Copy {
 
 #[derive(Clone, PartialEq, Eq)]
 /// A Point, stored in Jacobian coordinates
-pub(super) struct Point<C: Curve + ?Sized> {
+pub(super) struct Point<C: Curve> {
 	x: C::CurveField,
 	y: C::CurveField,
 	z: C::CurveField,
 }
 
-impl<C: Curve + ?Sized> Point<C> {
+impl<C: Curve> Point<C> {
 	fn check_curve_conditions() {
 		debug_assert!(C::ScalarModulus::PRIME < C::CurveModulus::PRIME, "N is < P");
 	}
@@ -0,0 +1,11 @@
+//! Hex encoding and decoding, as used in the presentation format of various binary record fields
+//! and in our JSON encoding of records.
+
+use alloc::vec::Vec;
+
+/// Decodes the given (case-insensitive) hex string.
+pub(crate) fn decode(hex: &str) -> Result<Vec<u8>, ()> {
+	if hex.len() % 2 != 0 { return Err(()); }
+	let digit = |c: u8| (c as char).to_digit(16).ok_or(());
+	hex.as_bytes().chunks(2).map(|pair| Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8)).collect()
+}
@@ -18,12 +18,18 @@ extern crate alloc;
 // Note that this is duplicated exactly in src/lib.rs
 pub const MAX_PROOF_STEPS: usize = 20;
 
+mod idna;
+mod json;
+#[cfg(feature = "serde")]
+mod serde_util;
 pub mod rr;
 pub mod ser;
 pub mod query;
+pub mod zonefile;
 
-#[cfg(feature = "validation")]
 mod base32;
+mod base64;
+mod hex;
 #[cfg(feature = "validation")]
 mod crypto;
 #[cfg(feature = "validation")]
@@ -55,7 +61,7 @@ fn main() { panic!("You need to enable the `build_server` feature to use the bui
 mod imp {
 	use super::*;
 
-	use rr::Name;
+	use rr::{Name, RR};
 	use query::*;
 
 	use std::net::SocketAddr;
@@ -68,6 +74,7 @@ mod imp {
 			let (mut socket, _) = listener.accept().await.expect("Failed to accept new TCP connection");
 			tokio::spawn(async move {
 				let mut response = ("400 Bad Request", "Bad Request");
+				#[allow(clippy::never_loop)]
 				'ret_err: loop { // goto label
 					let mut buf = [0; 4096];
 					let mut buf_pos = 0;
@@ -107,7 +114,7 @@ mod imp {
 					if verb != "GET" { break; }
 					if http_vers != "HTTP/1.1" && http_vers != "HTTP/1.0" { break 'ret_err; }
 
-					const PATH_PFX: &'static str = "/dnssecproof?";
+					const PATH_PFX: &str = "/dnssecproof?";
 					if !path.starts_with(PATH_PFX) {
 						response = ("404 Not Found", "Not Found");
 						break 'ret_err;
@@ -124,7 +131,7 @@ mod imp {
 						} else { break 'ret_err; }
 					}
 
-					if d == "" || t == "" {
+					if d.is_empty() || t.is_empty() {
 						response.1 = "Missing d or t URI parameters";
 						break 'ret_err;
 					}
@@ -132,13 +139,14 @@ mod imp {
 						response.1 = "Failed to parse domain, make sure it ends with .";
 						break 'ret_err;
 					};
-					let proof_res = match t.to_ascii_uppercase().as_str() {
-						"TXT" => build_txt_proof_async(resolver_sockaddr, &query_name).await,
-						"TLSA" => build_tlsa_proof_async(resolver_sockaddr, &query_name).await,
-						"A" => build_a_proof_async(resolver_sockaddr, &query_name).await,
-						"AAAA" => build_aaaa_proof_async(resolver_sockaddr, &query_name).await,
-						_ => break 'ret_err,
-					};
+					let mut types = Vec::new();
+					for ty_name in t.split(",") {
+						if let Some(ty) = RR::ty_from_name(ty_name) { types.push(ty); } else {
+							response.1 = "Unsupported record type";
+							break 'ret_err;
+						}
+					}
+					let proof_res = build_proof_for_types_async(resolver_sockaddr, &query_name, &types).await;
 					let (proof, cache_ttl) = if let Ok(proof) = proof_res { proof } else {
 						response = ("404 Not Found", "Failed to generate proof for given domain");
 						break 'ret_err;
@@ -167,10 +175,9 @@ mod test {
 	use super::*;
 
 	use crate::ser::parse_rr_stream;
+	use crate::rr::RR;
 	use crate::validation::verify_rr_stream;
 
-	use minreq;
-
 	#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 	async fn test_lookup() {
 		let ns = "8.8.8.8:53".parse().unwrap();
@@ -200,7 +207,31 @@ mod test {
 		assert_eq!(resp.status_code, 200);
 		let rrs = parse_rr_stream(resp.as_bytes()).unwrap();
 		let verified_rrs = verify_rr_stream(&rrs).unwrap();
-		assert!(verified_rrs.verified_rrs.len() >= 1);
+		assert!(!verified_rrs.verified_rrs.is_empty());
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+	async fn test_lookup_multiple_types() {
+		let ns = "8.8.8.8:53".parse().unwrap();
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await
+			.expect("Failed to bind to socket");
+		let addr = listener.local_addr().unwrap();
+		tokio::spawn(imp::run_server(listener, ns));
+		let resp = minreq::get(
+			format!("http://{}/dnssecproof?d=cloudflare.com.&t=A,aaaa", addr)
+		).send().unwrap();
+
+		assert_eq!(resp.status_code, 200);
+		let rrs = parse_rr_stream(resp.as_bytes()).unwrap();
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		let resolved_rrs = verified_rrs.resolve_name(&"cloudflare.com.".try_into().unwrap());
+		assert!(resolved_rrs.iter().any(|rr| matches!(rr, RR::A(_))));
+		assert!(resolved_rrs.iter().any(|rr| matches!(rr, RR::AAAA(_))));
+
+		let resp = minreq::get(
+			format!("http://{}/dnssecproof?d=cloudflare.com.&t=BOGUS", addr)
+		).send().unwrap();
+		assert_eq!(resp.status_code, 400);
 	}
 
 	#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
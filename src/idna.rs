@@ -0,0 +1,235 @@
+//! Conversion between internationalized domain names and their ASCII (punycode) form.
+//!
+//! This implements RFC 3492 punycode and the parts of UTS #46 processing which don't require
+//! large Unicode data tables - names are lowercased (using the full Unicode lowercase mapping),
+//! full-width characters are mapped to their ASCII equivalents, alternative full stops are mapped
+//! to `.`, a few always-ignored characters are dropped and control and whitespace characters
+//! are rejected. Nontransitional processing is used (i.e. `ß` is kept rather than mapped to
+//! `ss`) and ASCII characters outside of letters, digits and `-` are allowed, as is required for
+//! names like `_443._tcp.example.com`.
+//!
+//! We do not carry Unicode normalization or bidi tables, so input is expected to already be in
+//! Normalization Form C, as nearly all text input is.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const BASE: u32 = 36;
+const TMIN: u32 = 1;
+const TMAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+	delta /= if first_time { DAMP } else { 2 };
+	delta += delta / num_points;
+	let mut k = 0;
+	while delta > ((BASE - TMIN) * TMAX) / 2 {
+		delta /= BASE - TMIN;
+		k += BASE;
+	}
+	k + (BASE - TMIN + 1) * delta / (delta + SKEW)
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+	if k <= bias { TMIN } else if k >= bias + TMAX { TMAX } else { k - bias }
+}
+
+fn encode_digit(d: u32) -> char {
+	debug_assert!(d < BASE);
+	if d < 26 { (b'a' + d as u8) as char } else { (b'0' + (d - 26) as u8) as char }
+}
+
+fn decode_digit(c: u8) -> Option<u32> {
+	match c {
+		b'a'..=b'z' => Some((c - b'a') as u32),
+		b'A'..=b'Z' => Some((c - b'A') as u32),
+		b'0'..=b'9' => Some((c - b'0') as u32 + 26),
+		_ => None,
+	}
+}
+
+/// Encodes the given characters using punycode (without the `xn--` prefix).
+fn punycode_encode(input: &[char]) -> Option<String> {
+	let mut output: String = input.iter().filter(|c| c.is_ascii()).collect();
+	let basic_count = output.len() as u32;
+	let mut handled = basic_count;
+	if basic_count > 0 { output.push('-'); }
+
+	let (mut n, mut delta, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+	while (handled as usize) < input.len() {
+		let m = input.iter().map(|c| *c as u32).filter(|c| *c >= n).min()?;
+		delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+		n = m;
+		for c in input.iter().map(|c| *c as u32) {
+			if c < n { delta = delta.checked_add(1)?; }
+			if c == n {
+				let mut q = delta;
+				let mut k = BASE;
+				loop {
+					let t = threshold(k, bias);
+					if q < t { break; }
+					output.push(encode_digit(t + (q - t) % (BASE - t)));
+					q = (q - t) / (BASE - t);
+					k += BASE;
+				}
+				output.push(encode_digit(q));
+				bias = adapt(delta, handled + 1, handled == basic_count);
+				delta = 0;
+				handled += 1;
+			}
+		}
+		delta = delta.checked_add(1)?;
+		n = n.checked_add(1)?;
+	}
+	Some(output)
+}
+
+/// Decodes the given punycode (without the `xn--` prefix).
+fn punycode_decode(input: &[u8]) -> Option<Vec<char>> {
+	let (basic, encoded) = match input.iter().rposition(|c| *c == b'-') {
+		Some(pos) => (&input[..pos], &input[pos + 1..]),
+		None => (&[][..], input),
+	};
+	if !basic.is_ascii() { return None; }
+	let mut output: Vec<char> = basic.iter().map(|c| *c as char).collect();
+
+	let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+	let mut encoded = encoded.iter();
+	while encoded.len() != 0 {
+		let old_i = i;
+		let mut w = 1u32;
+		let mut k = BASE;
+		loop {
+			let digit = decode_digit(*encoded.next()?)?;
+			i = i.checked_add(digit.checked_mul(w)?)?;
+			let t = threshold(k, bias);
+			if digit < t { break; }
+			w = w.checked_mul(BASE - t)?;
+			k += BASE;
+		}
+		let len = output.len() as u32 + 1;
+		bias = adapt(i - old_i, len, old_i == 0);
+		n = n.checked_add(i / len)?;
+		i %= len;
+		let c = char::from_u32(n)?;
+		// Basic code points must only appear in the basic section
+		if c.is_ascii() { return None; }
+		output.insert(i as usize, c);
+		i += 1;
+	}
+	Some(output)
+}
+
+/// Maps a single character per our subset of the UTS #46 mapping table, appending the result to
+/// `out`.
+fn map_char(c: char, out: &mut String) -> Result<(), ()> {
+	match c {
+		// Ideographic and full-width full stops
+		'\u{3002}' | '\u{ff0e}' | '\u{ff61}' => out.push('.'),
+		// Full-width ASCII
+		'\u{ff01}'..='\u{ff5e}' => {
+			let ascii = char::from_u32(c as u32 - 0xff01 + 0x21).ok_or(())?;
+			map_char(ascii, out)?;
+		},
+		// Soft hyphen, zero-width space, word joiner, variation selectors and the BOM
+		'\u{ad}' | '\u{200b}' | '\u{2060}' | '\u{fe00}'..='\u{fe0f}' | '\u{feff}' => {},
+		_ if c.is_control() || c.is_whitespace() => return Err(()),
+		_ => out.extend(c.to_lowercase()),
+	}
+	Ok(())
+}
+
+/// Converts a (possibly internationalized) domain name to its wire encoding, punycode-encoding
+/// any labels which contain non-ASCII characters.
+///
+/// A trailing `.` is optional.
+pub(crate) fn unicode_to_wire(name: &str) -> Result<Vec<u8>, ()> {
+	let mut mapped = String::with_capacity(name.len());
+	for c in name.chars() { map_char(c, &mut mapped)?; }
+	if mapped == "." { return Ok(Vec::from([0])); }
+	let mapped = mapped.strip_suffix('.').unwrap_or(&mapped);
+	if mapped.is_empty() { return Err(()); }
+
+	let mut wire = Vec::with_capacity(mapped.len() + 2);
+	for label in mapped.split('.') {
+		let label_start = wire.len();
+		wire.push(0);
+		if label.is_ascii() {
+			// Any A-labels which were provided directly must be valid
+			if label.starts_with("xn--") && label_to_unicode(label.as_bytes()).is_none() {
+				return Err(());
+			}
+			wire.extend_from_slice(label.as_bytes());
+		} else {
+			let chars: Vec<char> = label.chars().collect();
+			wire.extend_from_slice(b"xn--");
+			wire.extend_from_slice(punycode_encode(&chars).ok_or(())?.as_bytes());
+		}
+		let label_len = wire.len() - label_start - 1;
+		if label_len == 0 || label_len > 63 { return Err(()); }
+		wire[label_start] = label_len as u8;
+	}
+	wire.push(0);
+	Ok(wire)
+}
+
+/// Decodes a single (lowercase) `xn--` label into its Unicode form, returning `None` if the label
+/// is not a valid A-label.
+pub(crate) fn label_to_unicode(label: &[u8]) -> Option<String> {
+	let encoded = label.strip_prefix(b"xn--")?;
+	let decoded = punycode_decode(encoded)?;
+	if decoded.iter().all(|c| c.is_ascii()) { return None; }
+	let mut res = String::with_capacity(label.len() * 2);
+	for c in decoded.iter() {
+		// Only accept characters which map to themselves, so that the Unicode form is canonical
+		// and maps back to the same A-label.
+		let mut mapped = String::new();
+		map_char(*c, &mut mapped).ok()?;
+		if mapped.chars().ne(core::iter::once(*c)) || *c == '.' || *c == '\\' { return None; }
+		res.push(*c);
+	}
+	if punycode_encode(&decoded)?.as_bytes() != encoded { return None; }
+	Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rfc3492_vectors() {
+		// Sample strings from RFC 3492 section 7.1
+		let vectors: [(&str, &str); 4] = [
+			("\u{4ed6}\u{4eec}\u{4e3a}\u{4ec0}\u{4e48}\u{4e0d}\u{8bf4}\u{4e2d}\u{6587}", "ihqwcrb4cv8a8dqg056pqjye"),
+			("3\u{5e74}B\u{7d44}\u{91d1}\u{516b}\u{5148}\u{751f}", "3B-ww4c5e180e575a65lsy2b"),
+			("\u{305d}\u{306e}\u{30b9}\u{30d4}\u{30fc}\u{30c9}\u{3067}", "d9juau41awczczp"),
+			("m\u{fc}nchen", "mnchen-3ya"),
+		];
+		for (unicode, encoded) in vectors {
+			let chars: Vec<char> = unicode.chars().collect();
+			assert_eq!(punycode_encode(&chars).unwrap(), encoded);
+			assert_eq!(punycode_decode(encoded.as_bytes()).unwrap(), chars);
+		}
+		assert!(punycode_decode(b"a-b").is_none());
+		assert!(punycode_decode(b"99999999999").is_none());
+	}
+
+	#[test]
+	fn uts46_mapping() {
+		assert_eq!(unicode_to_wire("M\u{dc}nchen.DE").unwrap(), b"\x0exn--mnchen-3ya\x02de\x00");
+		assert_eq!(unicode_to_wire("\u{ff2d}\u{fc}nchen\u{3002}de.").unwrap(), b"\x0exn--mnchen-3ya\x02de\x00");
+		assert_eq!(unicode_to_wire("stra\u{df}e.de").unwrap(), b"\x0dxn--strae-oqa\x02de\x00");
+		assert_eq!(unicode_to_wire("_443._tcp.ex\u{ad}ample.").unwrap(), b"\x04_443\x04_tcp\x07example\x00");
+		assert!(unicode_to_wire("a b.de").is_err());
+		assert!(unicode_to_wire("a..de").is_err());
+		assert!(unicode_to_wire("xn--a-b.de").is_err());
+		assert!(unicode_to_wire("").is_err());
+
+		assert_eq!(label_to_unicode(b"xn--mnchen-3ya").unwrap(), "m\u{fc}nchen");
+		assert!(label_to_unicode(b"mnchen-3ya").is_none());
+		assert!(label_to_unicode(b"xn--abc-").is_none());
+	}
+}
@@ -0,0 +1,421 @@
+//! A minimal JSON reader, used to map the output of [`RR::json`] back into [`RR`]s.
+//!
+//! Only the subset of JSON which we emit is fully supported - notably numbers must be
+//! non-negative integers which fit in a `u64`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::rr::*;
+use crate::ser::parse_rdata;
+
+/// The maximum nesting depth of arrays and objects we'll parse. Our schema never nests more than
+/// twice.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum JsonValue {
+	Null,
+	Bool(bool),
+	Number(u64),
+	String(String),
+	Array(Vec<JsonValue>),
+	Object(Vec<(String, JsonValue)>),
+}
+
+struct Reader<'a> {
+	input: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn skip_whitespace(&mut self) {
+		while matches!(self.input.get(self.pos), Some(b' '|b'\t'|b'\r'|b'\n')) { self.pos += 1; }
+	}
+
+	fn peek(&mut self) -> Result<u8, ()> {
+		self.skip_whitespace();
+		self.input.get(self.pos).copied().ok_or(())
+	}
+
+	fn expect(&mut self, c: u8) -> Result<(), ()> {
+		if self.peek()? != c { return Err(()); }
+		self.pos += 1;
+		Ok(())
+	}
+
+	fn expect_literal(&mut self, literal: &[u8]) -> Result<(), ()> {
+		if !self.input[self.pos..].starts_with(literal) { return Err(()); }
+		self.pos += literal.len();
+		Ok(())
+	}
+
+	fn read_hex4(&mut self) -> Result<u32, ()> {
+		let digits = self.input.get(self.pos..self.pos + 4).ok_or(())?;
+		self.pos += 4;
+		let mut res = 0;
+		for d in digits {
+			res = res << 4 | (*d as char).to_digit(16).ok_or(())?;
+		}
+		Ok(res)
+	}
+
+	fn read_string(&mut self) -> Result<String, ()> {
+		self.expect(b'"')?;
+		let mut res = Vec::new();
+		loop {
+			let c = *self.input.get(self.pos).ok_or(())?;
+			self.pos += 1;
+			match c {
+				b'"' => break,
+				b'\\' => {
+					let escape = *self.input.get(self.pos).ok_or(())?;
+					self.pos += 1;
+					let c = match escape {
+						b'"' => '"', b'\\' => '\\', b'/' => '/', b'b' => '\u{8}', b'f' => '\u{c}',
+						b'n' => '\n', b'r' => '\r', b't' => '\t',
+						b'u' => {
+							let mut code = self.read_hex4()?;
+							if (0xd800..0xdc00).contains(&code) {
+								// A high surrogate, which must be followed by a low surrogate
+								self.expect_literal(b"\\u")?;
+								let low = self.read_hex4()?;
+								if !(0xdc00..0xe000).contains(&low) { return Err(()); }
+								code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+							}
+							char::from_u32(code).ok_or(())?
+						},
+						_ => return Err(()),
+					};
+					let mut buf = [0; 4];
+					res.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+				},
+				0..=0x1f => return Err(()),
+				_ => res.push(c),
+			}
+		}
+		String::from_utf8(res).map_err(|_| ())
+	}
+
+	fn read_value(&mut self, depth: usize) -> Result<JsonValue, ()> {
+		if depth > MAX_DEPTH { return Err(()); }
+		match self.peek()? {
+			b'n' => { self.expect_literal(b"null")?; Ok(JsonValue::Null) },
+			b't' => { self.expect_literal(b"true")?; Ok(JsonValue::Bool(true)) },
+			b'f' => { self.expect_literal(b"false")?; Ok(JsonValue::Bool(false)) },
+			b'"' => Ok(JsonValue::String(self.read_string()?)),
+			b'0'..=b'9' => {
+				let start = self.pos;
+				while matches!(self.input.get(self.pos), Some(b'0'..=b'9')) { self.pos += 1; }
+				let digits = &self.input[start..self.pos];
+				if digits.len() > 1 && digits[0] == b'0' { return Err(()); }
+				if matches!(self.input.get(self.pos), Some(b'.'|b'e'|b'E')) { return Err(()); }
+				let digits = core::str::from_utf8(digits).map_err(|_| ())?;
+				Ok(JsonValue::Number(digits.parse().map_err(|_| ())?))
+			},
+			b'[' => {
+				self.pos += 1;
+				let mut res = Vec::new();
+				if self.peek()? == b']' {
+					self.pos += 1;
+					return Ok(JsonValue::Array(res));
+				}
+				loop {
+					res.push(self.read_value(depth + 1)?);
+					match self.peek()? {
+						b',' => self.pos += 1,
+						b']' => { self.pos += 1; break; },
+						_ => return Err(()),
+					}
+				}
+				Ok(JsonValue::Array(res))
+			},
+			b'{' => {
+				self.pos += 1;
+				let mut res = Vec::new();
+				if self.peek()? == b'}' {
+					self.pos += 1;
+					return Ok(JsonValue::Object(res));
+				}
+				loop {
+					self.skip_whitespace();
+					let key = self.read_string()?;
+					self.expect(b':')?;
+					res.push((key, self.read_value(depth + 1)?));
+					match self.peek()? {
+						b',' => self.pos += 1,
+						b'}' => { self.pos += 1; break; },
+						_ => return Err(()),
+					}
+				}
+				Ok(JsonValue::Object(res))
+			},
+			_ => Err(()),
+		}
+	}
+}
+
+/// Parses a single JSON value, failing if there is anything but whitespace after it.
+pub(crate) fn parse(json: &str) -> Result<JsonValue, ()> {
+	let mut reader = Reader { input: json.as_bytes(), pos: 0 };
+	let res = reader.read_value(0)?;
+	reader.skip_whitespace();
+	if reader.pos != reader.input.len() { return Err(()); }
+	Ok(res)
+}
+
+/// The fields of a JSON object, which are looked up by key.
+struct Fields<'a>(&'a [(String, JsonValue)]);
+
+impl<'a> Fields<'a> {
+	fn get(&self, key: &str) -> Result<&'a JsonValue, ()> {
+		let mut matching = self.0.iter().filter(|(k, _)| k == key);
+		let res = matching.next().ok_or(())?;
+		if matching.next().is_some() { return Err(()); }
+		Ok(&res.1)
+	}
+
+	fn int<T: TryFrom<u64>>(&self, key: &str) -> Result<T, ()> {
+		match self.get(key)? {
+			JsonValue::Number(n) => T::try_from(*n).map_err(|_| ()),
+			_ => Err(()),
+		}
+	}
+
+	fn str(&self, key: &str) -> Result<&'a str, ()> {
+		match self.get(key)? {
+			JsonValue::String(s) => Ok(s),
+			_ => Err(()),
+		}
+	}
+
+	fn name(&self, key: &str) -> Result<Name, ()> {
+		Name::try_from(self.str(key)?)
+	}
+
+	fn hex(&self, key: &str) -> Result<Vec<u8>, ()> {
+		crate::hex::decode(self.str(key)?)
+	}
+
+	/// Reads bytes written as either a string (of printable ASCII) or an array of byte values.
+	fn bytes(&self, key: &str) -> Result<Vec<u8>, ()> {
+		match self.get(key)? {
+			JsonValue::String(s) => Ok(s.as_bytes().to_vec()),
+			JsonValue::Array(vals) => vals.iter().map(|v| match v {
+				JsonValue::Number(n) => (*n).try_into().map_err(|_| ()),
+				_ => Err(()),
+			}).collect(),
+			_ => Err(()),
+		}
+	}
+
+	fn types(&self, key: &str) -> Result<NSecTypeMask, ()> {
+		let vals = match self.get(key)? {
+			JsonValue::Array(vals) => vals,
+			_ => return Err(()),
+		};
+		let mut types = Vec::with_capacity(vals.len());
+		for val in vals.iter() {
+			types.push(match val {
+				JsonValue::Number(n) => (*n).try_into().map_err(|_| ())?,
+				JsonValue::String(s) => type_from_mnemonic(s).ok_or(())?,
+				_ => return Err(()),
+			});
+		}
+		Ok(NSecTypeMask::from_types(&types))
+	}
+}
+
+/// Parses a record from the JSON format written by [`RR::json`].
+pub(crate) fn parse_rr(json: &str) -> Result<RR, ()> {
+	let value = parse(json)?;
+	let fields = match &value {
+		JsonValue::Object(fields) => Fields(fields),
+		_ => return Err(()),
+	};
+	let name = fields.name("name")?;
+	if let Ok(unicode_name) = fields.str("unicode_name") {
+		if name.to_unicode() != unicode_name { return Err(()); }
+	}
+	let rr: RR = match fields.str("type")? {
+		"a" => {
+			let address = fields.bytes("address")?.try_into().map_err(|_| ())?;
+			A { name, address }.into()
+		},
+		"aaaa" => {
+			let address = fields.bytes("address")?.try_into().map_err(|_| ())?;
+			AAAA { name, address }.into()
+		},
+		"ns" => NS { name, name_server: fields.name("ns")? }.into(),
+		"soa" => SOA {
+			name, primary_ns: fields.name("primary_ns")?, mailbox: fields.name("mailbox")?,
+			serial: fields.int("serial")?, refresh: fields.int("refresh")?,
+			retry: fields.int("retry")?, expire: fields.int("expire")?,
+			minimum: fields.int("minimum")?,
+		}.into(),
+		"txt" => Txt { name, data: TxtBytes::new(&fields.bytes("contents")?)? }.into(),
+		"cname" => CName { name, canonical_name: fields.name("canonical_name")? }.into(),
+		"dname" => DName { name, delegation_name: fields.name("delegation_name")? }.into(),
+		"ptr" => PTR { name, ptr_name: fields.name("ptr_name")? }.into(),
+		"tlsa" => TLSA {
+			name, cert_usage: fields.int("usage")?, selector: fields.int("selector")?,
+			data_ty: fields.int("data_ty")?, data: fields.hex("data")?,
+		}.into(),
+		"smimea" => SMIMEA {
+			name, cert_usage: fields.int("usage")?, selector: fields.int("selector")?,
+			data_ty: fields.int("data_ty")?, data: fields.hex("data")?,
+		}.into(),
+		"openpgpkey" => OpenPGPKey { name, pubkey: fields.hex("pubkey")? }.into(),
+		"uri" => {
+			let target = String::from_utf8(fields.bytes("target")?).map_err(|_| ())?;
+			URI { name, priority: fields.int("priority")?, weight: fields.int("weight")?, target }.into()
+		},
+		"naptr" => NAPTR {
+			name, order: fields.int("order")?, preference: fields.int("preference")?,
+			flags: fields.bytes("flags")?, services: fields.bytes("services")?,
+			regexp: fields.bytes("regexp")?, replacement: fields.name("replacement")?,
+		}.into(),
+		"cert" => CERT {
+			name, cert_type: fields.int("cert_type")?, key_tag: fields.int("key_tag")?,
+			alg: fields.int("alg")?, cert: fields.hex("cert")?,
+		}.into(),
+		"dnskey" => DnsKey {
+			name, flags: fields.int("flags")?, protocol: fields.int("protocol")?,
+			alg: fields.int("alg")?, pubkey: fields.hex("pubkey")?,
+		}.into(),
+		"cdnskey" => CDnsKey {
+			name, flags: fields.int("flags")?, protocol: fields.int("protocol")?,
+			alg: fields.int("alg")?, pubkey: fields.hex("pubkey")?,
+		}.into(),
+		"ds" => DS {
+			name, key_tag: fields.int("key_tag")?, alg: fields.int("alg")?,
+			digest_type: fields.int("digest_type")?, digest: fields.hex("digest")?,
+		}.into(),
+		"cds" => CDS {
+			name, key_tag: fields.int("key_tag")?, alg: fields.int("alg")?,
+			digest_type: fields.int("digest_type")?, digest: fields.hex("digest")?,
+		}.into(),
+		"zonemd" => ZoneMD {
+			name, serial: fields.int("serial")?, scheme: fields.int("scheme")?,
+			hash_alg: fields.int("hash_alg")?, digest: fields.hex("digest")?,
+		}.into(),
+		"rrsig" => RRSig {
+			name, ty: fields.int("signed_record_type")?, alg: fields.int("alg")?,
+			labels: fields.int("signed_labels")?, orig_ttl: fields.int("orig_ttl")?,
+			expiration: fields.int("expiration")?, inception: fields.int("inception")?,
+			key_tag: fields.int("key_tag")?, key_name: fields.name("key_name")?,
+			signature: fields.hex("signature")?,
+		}.into(),
+		"nsec" => NSec { name, next_name: fields.name("next_name")?, types: fields.types("types")? }.into(),
+		"nsec3" => NSec3 {
+			name, hash_algo: fields.int("hash_algo")?, flags: fields.int("flags")?,
+			hash_iterations: fields.int("hash_iterations")?, salt: fields.bytes("salt")?,
+			next_name_hash: fields.bytes("next_name_hash")?, types: fields.types("types")?,
+		}.into(),
+		_ => return Err(()),
+	};
+
+	// Round-trip the record through its wire encoding, applying the same checks as we do when
+	// parsing records off the wire (and catching any fields which are too long to be encoded).
+	let mut data = Vec::new();
+	Record::write_u16_len_prefixed_data(&rr, &mut data);
+	if u16::from_be_bytes([data[0], data[1]]) as usize != data.len() - 2 { return Err(()); }
+	let parsed = parse_rdata(rr.name().clone(), Record::ty(&rr), &data[2..], &data[2..])?;
+	if parsed != rr { return Err(()); }
+	Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use alloc::vec;
+
+	#[test]
+	fn json_values() {
+		assert_eq!(parse(" { \"a\" : [1, true, null, \"\\u00fc\\ud83d\\ude00\\n\"], \"b\": {} } ").unwrap(),
+			JsonValue::Object(vec![
+				("a".into(), JsonValue::Array(vec![JsonValue::Number(1), JsonValue::Bool(true), JsonValue::Null,
+					JsonValue::String("\u{fc}\u{1f600}\n".into())])),
+				("b".into(), JsonValue::Object(Vec::new())),
+			]));
+		for invalid in ["", "[1,]", "{\"a\" 1}", "01", "1.5", "-1", "18446744073709551616", "\"\\ud83d\"",
+			"\"\n\"", "[[[[[[[[[[]]]]]]]]]]", "1 2", "{\"a\":1,}", "\"\\x\""]
+		{
+			assert!(parse(invalid).is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn json_records() {
+		assert_eq!(parse_rr("{\"type\":\"a\",\"name\":\"example.com.\",\"address\":[192, 0, 2, 1]}").unwrap(),
+			A { name: "example.com.".try_into().unwrap(), address: [192, 0, 2, 1] }.into());
+		// Fields may appear in any order and unicode_name, if present, must match name.
+		assert_eq!(parse_rr("{\"name\":\"xn--mnchen-3ya.de.\",\"unicode_name\":\"m\u{fc}nchen.de.\",\"ns\":\"a.de.\",\"type\":\"ns\"}").unwrap(),
+			NS { name: "xn--mnchen-3ya.de.".try_into().unwrap(), name_server: "a.de.".try_into().unwrap() }.into());
+		for invalid in [
+			"{\"type\":\"a\",\"name\":\"example.com.\",\"address\":[192, 0, 2]}",
+			"{\"type\":\"a\",\"name\":\"example.com.\",\"address\":[192, 0, 2, 256]}",
+			"{\"type\":\"a\",\"name\":\"example.com.\",\"name\":\"example.com.\",\"address\":[192, 0, 2, 1]}",
+			"{\"type\":\"ns\",\"name\":\"xn--mnchen-3ya.de.\",\"unicode_name\":\"m\u{fc}nchen.com.\",\"ns\":\"a.de.\"}",
+			"{\"type\":\"uri\",\"name\":\"a.\",\"priority\":0,\"weight\":0,\"target\":\"\"}",
+			"{\"type\":\"zonemd\",\"name\":\"a.\",\"serial\":0,\"scheme\":1,\"hash_alg\":1,\"digest\":\"00\"}",
+			"{\"type\":\"ds\",\"name\":\"a.\",\"key_tag\":0,\"alg\":1,\"digest_type\":1,\"digest\":\"0\"}",
+			"{\"type\":\"mx\",\"name\":\"a.\"}",
+		] {
+			assert!(parse_rr(invalid).is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn json_round_trip() {
+		let rrs: Vec<RR> = vec![
+			AAAA { name: "xn--mnchen-3ya.de.".try_into().unwrap(), address: [0x20, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1] }.into(),
+			SOA {
+				name: "a.".try_into().unwrap(), primary_ns: "ns.a.".try_into().unwrap(),
+				mailbox: "host\\.master.a.".try_into().unwrap(), serial: u32::MAX, refresh: 1, retry: 2,
+				expire: 3, minimum: 4,
+			}.into(),
+			Txt { name: "a\\\"b.".try_into().unwrap(), data: TxtBytes::new(b"a \"quoted\\ string").unwrap() }.into(),
+			Txt { name: "a.".try_into().unwrap(), data: TxtBytes::new(&[0xff; 300]).unwrap() }.into(),
+			CName { name: "a.".try_into().unwrap(), canonical_name: "b.".try_into().unwrap() }.into(),
+			DName { name: "a.".try_into().unwrap(), delegation_name: "b.".try_into().unwrap() }.into(),
+			PTR { name: "a.".try_into().unwrap(), ptr_name: "b.".try_into().unwrap() }.into(),
+			TLSA { name: "a.".try_into().unwrap(), cert_usage: 3, selector: 1, data_ty: 1, data: vec![0xab; 32] }.into(),
+			SMIMEA { name: "a.".try_into().unwrap(), cert_usage: 3, selector: 1, data_ty: 1, data: vec![0xcd; 32] }.into(),
+			OpenPGPKey { name: "a.".try_into().unwrap(), pubkey: vec![0x99; 10] }.into(),
+			URI { name: "a.".try_into().unwrap(), priority: 1, weight: 2, target: "https://a/\"".into() }.into(),
+			NAPTR {
+				name: "a.".try_into().unwrap(), order: 1, preference: 2, flags: b"u".to_vec(),
+				services: vec![0, 1, 2], regexp: b"!^.*$!sip:a@b!".to_vec(), replacement: ".".try_into().unwrap(),
+			}.into(),
+			CERT { name: "a.".try_into().unwrap(), cert_type: 1, key_tag: 2, alg: 3, cert: vec![4; 20] }.into(),
+			DnsKey { name: ".".try_into().unwrap(), flags: 257, protocol: 3, alg: 8, pubkey: vec![1, 2, 3, 4] }.into(),
+			CDnsKey { name: "a.".try_into().unwrap(), flags: 257, protocol: 3, alg: 13, pubkey: vec![5; 64] }.into(),
+			DS { name: "com.".try_into().unwrap(), key_tag: 19718, alg: 13, digest_type: 2, digest: vec![0xab; 32] }.into(),
+			CDS { name: "a.".try_into().unwrap(), key_tag: 1, alg: 13, digest_type: 2, digest: vec![0x12; 32] }.into(),
+			ZoneMD { name: "a.".try_into().unwrap(), serial: 1, scheme: 1, hash_alg: 1, digest: vec![0x11; 48] }.into(),
+			RRSig {
+				name: "a.".try_into().unwrap(), ty: Txt::TYPE, alg: 13, labels: 1, orig_ttl: 3600,
+				expiration: 1710201600, inception: 0, key_tag: 1234, key_name: "a.".try_into().unwrap(),
+				signature: vec![42; 64],
+			}.into(),
+			NSec {
+				name: "a.".try_into().unwrap(), next_name: "b.a.".try_into().unwrap(),
+				types: NSecTypeMask::from_types(&[A::TYPE, 15, RRSig::TYPE, NSec::TYPE, 1234]),
+			}.into(),
+			NSec3 {
+				name: "2vptu5timamqttgl4luu9kg21e0aor3s.a.".try_into().unwrap(), hash_algo: 1, flags: 0,
+				hash_iterations: 0, salt: vec![1, 2], next_name_hash: vec![0x42; 20],
+				types: NSecTypeMask::from_types(&[Txt::TYPE, RRSig::TYPE]),
+			}.into(),
+		];
+		for rr in rrs {
+			let json = rr.json();
+			assert!(parse(&json).is_ok(), "{}", json);
+			let parsed = RR::from_json(&json).unwrap();
+			assert_eq!(parsed.json(), json);
+			assert_eq!(parsed, rr);
+		}
+	}
+}
@@ -18,13 +18,27 @@
 //!  * By default, the `validation` feature is set, allowing to validate DNSSEC signatures and
 //!    proofs using the [`validation`] module.
 //!  * The `std` feature enables the [`query`] module, allowing for the building of proofs by
-//!    querying a recursive resolver over TCP.
+//!    querying a recursive resolver over TCP or UDP (or any other transport, by implementing
+//!    `DnsTransport` or `AsyncDnsTransport`), or by iteratively querying authoritative servers
+//!    starting from the root.
 //!  * The `tokio` feature further enables async versions of the [`query`] methods, doing the same
 //!    querying async using `tokio`'s TCP streams.
+//!  * The `doh` feature adds DNS-over-HTTPS drivers to the [`query`] module (async versions of
+//!    which are also enabled by setting both the `tokio` and `tokio-rustls` features), using
+//!    `rustls` and the Mozilla root certificates from `webpki-roots`.
+//!  * The `dot` feature similarly adds DNS-over-TLS drivers to the [`query`] module, which can
+//!    authenticate the resolver by name or by pinning its public key.
+//!
+//!    Note that, as `rustls` requires a much newer compiler, the `doh` and `dot` features are not
+//!    covered by the crate's MSRV.
 //!  * Finally, the crate can be built as a binary using the `build_server` feature, responding to
 //!    queries over HTTP GET calls to `/dnssecproof?d=domain.name.&t=RecordType` with DNSSEC
 //!    proofs.
 //!
+//! The `serde` feature implements `serde`'s `Serialize` and `Deserialize` for [`rr::Name`], all
+//! the records in [`rr`], and the result and error types of the [`validation`] and [`query`]
+//! modules. Names are encoded in presentation form and binary data as uppercase hex strings.
+//!
 //! The `slower_smaller_binary` feature slows proof validation down by 50%+ for a very marginal
 //! reduction in binary size, but those who are extremely binary size constrained may still find it
 //! useful.
@@ -50,8 +64,9 @@ extern crate alloc;
 // Note that this is duplicated exactly in src/http.rs
 pub const MAX_PROOF_STEPS: usize = 20;
 
-#[cfg(feature = "validation")]
 mod base32;
+mod base64;
+mod hex;
 
 #[cfg(feature = "validation")]
 pub(crate) mod unhex;
@@ -61,9 +76,15 @@ pub mod crypto;
 #[cfg(all(feature = "validation", not(any(dnssec_prover_fuzzing, dnssec_validate_bench))))]
 mod crypto;
 
+mod idna;
+mod json;
+#[cfg(feature = "serde")]
+mod serde_util;
+
 pub mod rr;
 pub mod ser;
 pub mod query;
+pub mod zonefile;
 
 #[cfg(feature = "validation")]
 pub mod validation;
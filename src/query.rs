@@ -3,17 +3,29 @@
 
 use core::{cmp, ops};
 use alloc::vec;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 
 #[cfg(feature = "std")]
-use std::net::{SocketAddr, TcpStream};
+use core::future::Future;
+#[cfg(feature = "std")]
+use core::pin::Pin;
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+
+#[cfg(feature = "std")]
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
 #[cfg(feature = "std")]
 use std::io::{Read, Write, Error, ErrorKind};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant, SystemTime};
 
 #[cfg(feature = "tokio")]
-use tokio_crate::net::TcpStream as TokioTcpStream;
+use tokio_crate::net::{TcpStream as TokioTcpStream, UdpSocket as TokioUdpSocket};
 #[cfg(feature = "tokio")]
-use tokio_crate::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_crate::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::rr::*;
 use crate::ser::*;
@@ -96,18 +108,28 @@ impl ops::DerefMut for QueryBuf {
 
 // We don't care about transaction IDs as we're only going to accept signed data.
 // Further, if we're querying over DoH, the RFC says we SHOULD use a transaction ID of 0 here.
+// When querying over UDP we do use random transaction IDs (see `query_udp`), but they're replaced
+// with this before responses reach the `ProofBuilder`.
 const TXID: u16 = 0;
 
+/// The UDP payload size we advertise via EDNS. This is the size recommended by DNS Flag Day 2020
+/// as it avoids IP fragmentation on nearly all paths.
+const EDNS_UDP_PAYLOAD_SIZE: u16 = 1232;
+
 fn build_query(domain: &Name, ty: u16) -> QueryBuf {
+	build_query_with_flags(domain, ty, 0x0120) // Flags: Recursive, Authenticated Data
+}
+
+fn build_query_with_flags(domain: &Name, ty: u16, flags: u16) -> QueryBuf {
 	let mut query = QueryBuf::new_zeroed(0);
 	query.extend_from_slice(&TXID.to_be_bytes());
-	query.extend_from_slice(&[0x01, 0x20]); // Flags: Recursive, Authenticated Data
+	query.extend_from_slice(&flags.to_be_bytes());
 	query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 1]); // One question, One additional
 	write_name(&mut query, domain);
 	query.extend_from_slice(&ty.to_be_bytes());
 	query.extend_from_slice(&1u16.to_be_bytes()); // INternet class
 	query.extend_from_slice(&[0, 0, 0x29]); // . OPT
-	query.extend_from_slice(&0u16.to_be_bytes()); // 0 UDP payload size
+	query.extend_from_slice(&EDNS_UDP_PAYLOAD_SIZE.to_be_bytes()); // UDP payload size
 	query.extend_from_slice(&[0, 0]); // EDNS version 0
 	query.extend_from_slice(&0x8000u16.to_be_bytes()); // Accept DNSSEC RRs
 	query.extend_from_slice(&0u16.to_be_bytes()); // No additional data
@@ -118,6 +140,7 @@ fn build_query(domain: &Name, ty: u16) -> QueryBuf {
 /// handful of common ones are captured in the variants here.
 // Note that this is also duplicated in uniffi in the udl
 #[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ProofBuildingError {
 	/// The server provided an invalid response.
 	///
@@ -184,14 +207,48 @@ impl std::error::Error for ProofBuildingError {
 /// Read some input and parse it as if it came from a server, for fuzzing.
 pub fn fuzz_response(response: &[u8]) {
 	let (mut proof, mut names) = (Vec::new(), Vec::new());
-	let _ = handle_response(response, &mut proof, &mut names);
+	if handle_response(response, &mut proof, &mut names, true).is_ok() {
+		// Any records we write into a proof must be readable by the proof parser.
+		parse_rr_stream(&proof).expect("Records we wrote should always be parseable");
+	}
+}
+
+/// Removes any records which appear more than once from the given proof (e.g. because they were
+/// included in responses to several queries).
+fn dedup_proof(proof: Vec<u8>) -> Vec<u8> {
+	let mut rrs = BTreeSet::new();
+	let mut deduped = Vec::with_capacity(proof.len());
+	let mut read = &proof[..];
+	while !read.is_empty() {
+		let rr_start = read;
+		let rr = match parse_rr(&mut read) {
+			Ok(rr) => rr,
+			Err(()) => return proof,
+		};
+		if rrs.insert(rr) {
+			deduped.extend_from_slice(&rr_start[..rr_start.len() - read.len()]);
+		}
+	}
+	deduped
+}
+
+/// Reads the name and type of the (single) question in a response.
+fn response_question(resp: &[u8]) -> Result<(Name, u16), ()> {
+	let mut read = resp.get(12..).ok_or(())?;
+	let name = read_wire_packet_name(&mut read, resp)?;
+	Ok((name, read_u16(&mut read)?))
 }
 
 /// Handle a response, returning the minimum TTL of any answer.
 ///
+/// If `require_authenticated` is set, the response must have the AD bit set, i.e. have been
+/// validated by the (recursive) resolver which sent it.
+///
 /// Note that the caller must map errors of [`ProofBuildingError::MissingRecord`] to
 /// [`ProofBuildingError::NoSuchName`] if this was the first query!
-fn handle_response(resp: &[u8], proof: &mut Vec<u8>, rrsig_key_names: &mut Vec<Name>) -> Result<u32, ProofBuildingError> {
+fn handle_response(
+	resp: &[u8], proof: &mut Vec<u8>, rrsig_key_names: &mut Vec<Name>, require_authenticated: bool,
+) -> Result<u32, ProofBuildingError> {
 	let mut read: &[u8] = resp;
 	let resp_txid = read_u16(&mut read).map_err(|()| ProofBuildingError::InvalidResponse)?;
 	if resp_txid != TXID { return Err(ProofBuildingError::InvalidResponse); }
@@ -212,7 +269,7 @@ fn handle_response(resp: &[u8], proof: &mut Vec<u8>, rrsig_key_names: &mut Vec<N
 	if flags & 0b0111_1010_0000_1111 != 0 {
 		return Err(ProofBuildingError::InvalidResponse);
 	}
-	if flags & 0b10_0000 == 0 {
+	if require_authenticated && flags & 0b10_0000 == 0 {
 		// The AD bit was unset
 		return Err(ProofBuildingError::Unauthenticated);
 	}
@@ -240,27 +297,42 @@ fn handle_response(resp: &[u8], proof: &mut Vec<u8>, rrsig_key_names: &mut Vec<N
 	}
 
 	for _ in 0..authorities {
-		// Only include records from the authority section if they are NSEC/3 (or signatures
-		// thereover). We don't care about NS records here.
 		let (rr, ttl) = parse_wire_packet_rr(&mut read, resp)
 			.map_err(|()| ProofBuildingError::InvalidResponse)?;
-		match &rr {
-			RR::RRSig(rrsig) => {
-				if rrsig.ty != NSec::TYPE && rrsig.ty != NSec3::TYPE {
-					continue;
-				}
-			},
-			RR::NSec(_)|RR::NSec3(_) => {},
-			_ => continue,
+		if let Some(ttl) = handle_authority_rr(rr, ttl, proof, rrsig_key_names) {
+			min_ttl = cmp::min(min_ttl, ttl);
 		}
-		write_rr(&rr, ttl, proof);
-		min_ttl = cmp::min(min_ttl, ttl);
-		if let RR::RRSig(rrsig) = rr { rrsig_key_names.push(rrsig.key_name); }
 	}
 
 	Ok(min_ttl)
 }
 
+/// Adds a record from the authority section of a response to the `proof` if it is an NSEC/3 or
+/// SOA record (or a signature thereover), returning the TTL to cache the proof for if it was
+/// added.
+///
+/// We don't care about NS records here, but the SOA carries the negative caching TTL for any
+/// denial of existence.
+fn handle_authority_rr(rr: RR, ttl: u32, proof: &mut Vec<u8>, rrsig_key_names: &mut Vec<Name>) -> Option<u32> {
+	let mut min_ttl = ttl;
+	match &rr {
+		RR::RRSig(rrsig) => {
+			if rrsig.ty != NSec::TYPE && rrsig.ty != NSec3::TYPE && rrsig.ty != SOA::TYPE {
+				return None;
+			}
+		},
+		RR::NSec(_)|RR::NSec3(_) => {},
+		RR::SOA(soa) => {
+			// Per RFC 2308, negative answers may be cached no longer than the SOA minimum.
+			min_ttl = cmp::min(min_ttl, soa.minimum);
+		},
+		_ => return None,
+	}
+	write_rr(&rr, ttl, proof);
+	if let RR::RRSig(rrsig) = rr { rrsig_key_names.push(rrsig.key_name); }
+	Some(min_ttl)
+}
+
 #[cfg(dnssec_prover_fuzzing)]
 /// Read a stream of responses and handle them it as if they came from a server, for fuzzing.
 pub fn fuzz_proof_builder(mut response_stream: &[u8]) {
@@ -289,7 +361,11 @@ pub fn fuzz_proof_builder(mut response_stream: &[u8]) {
 /// To build a DNSSEC proof using a DoH server, take each [`QueryBuf`], encode it as base64url, and
 /// make a query to `https://doh-server/endpoint?dns=base64url_encoded_query` with an `Accept`
 /// header of `application/dns-message`. Each response, in raw binary, can be fed directly into
-/// [`ProofBuilder::process_response`].
+/// [`ProofBuilder::process_response`]. With the `doh` feature, `build_proof_doh` does exactly this.
+///
+/// With the `std` feature, a [`ProofBuilder`] can instead be constructed with
+/// `ProofBuilder::new_iterative`, in which case it queries authoritative servers directly
+/// (starting from the root) rather than relying on a recursive resolver.
 #[derive(Clone)]
 pub struct ProofBuilder {
 	proof: Vec<u8>,
@@ -297,6 +373,9 @@ pub struct ProofBuilder {
 	dnskeys_requested: Vec<Name>,
 	pending_queries: usize,
 	queries_made: usize,
+	questions: usize,
+	#[cfg(feature = "std")]
+	iterative: Option<iterative::IterativeState>,
 }
 
 impl ProofBuilder {
@@ -309,14 +388,48 @@ impl ProofBuilder {
 	///
 	/// You can find constants for supported standard types in the [`crate::rr`] module.
 	pub fn new(name: &Name, ty: u16) -> (ProofBuilder, QueryBuf) {
-		let initial_query = build_query(name, ty);
+		let (builder, mut initial_queries) = ProofBuilder::new_for_questions(&[(name.clone(), ty)]);
+		(builder, initial_queries.pop().expect("We build one query per question"))
+	}
+
+	/// Constructs a new [`ProofBuilder`] which builds a single proof for records of each of the
+	/// given types at the given names, and the initial queries to send to the recursive resolver to
+	/// begin the proof building process.
+	///
+	/// The DNSKEY and DS records needed for several of the questions are only queried for once,
+	/// and records which appear in several responses are only included in the proof once, making
+	/// the proof much smaller than separate proofs for each question would be. Up to
+	/// [`MAX_PROOF_STEPS`] queries are allowed per question.
+	pub fn new_for_questions(questions: &[(Name, u16)]) -> (ProofBuilder, Vec<QueryBuf>) {
+		let initial_queries = questions.iter().map(|(name, ty)| build_query(name, *ty)).collect();
 		(ProofBuilder {
 			proof: Vec::new(),
 			min_ttl: u32::MAX,
 			dnskeys_requested: Vec::with_capacity(MAX_PROOF_STEPS),
-			pending_queries: 1,
-			queries_made: 1,
-		}, initial_query)
+			pending_queries: questions.len(),
+			queries_made: questions.len(),
+			questions: questions.len(),
+			#[cfg(feature = "std")]
+			iterative: None,
+		}, initial_queries)
+	}
+
+	/// Returns true if this builder is building a proof by querying authoritative servers
+	/// directly (see [`Self::new_iterative`]).
+	#[cfg(feature = "std")]
+	fn is_iterative(&self) -> bool {
+		self.iterative.is_some()
+	}
+	#[cfg(not(feature = "std"))]
+	fn is_iterative(&self) -> bool {
+		false
+	}
+
+	/// The maximum number of queries we'll make to build a proof.
+	fn query_limit(&self) -> usize {
+		#[cfg(feature = "std")]
+		if self.is_iterative() { return iterative::MAX_ITERATIVE_QUERIES * self.questions; }
+		MAX_PROOF_STEPS * self.questions
 	}
 
 	/// Returns true as long as further responses are expected from the resolver.
@@ -325,16 +438,35 @@ impl ProofBuilder {
 	/// [`Self::process_response`]. Once this returns false, [`Self::finish_proof`] should be used
 	/// to (possibly) get the final proof.
 	pub fn awaiting_responses(&self) -> bool {
-		self.pending_queries > 0 && self.queries_made <= MAX_PROOF_STEPS
+		self.pending_queries > 0 && self.queries_made <= self.query_limit()
 	}
 
 	/// Processes a query response from the recursive resolver, returning a list of new queries to
 	/// send to the resolver.
 	pub fn process_response(&mut self, resp: &QueryBuf) -> Result<Vec<QueryBuf>, ProofBuildingError> {
-		if self.pending_queries == 0 { return Err(ProofBuildingError::NoResponseExpected); }
+		self.process_response_inner(resp, None)
+	}
+
+	/// Processes a query response from the recursive resolver as [`Self::process_response`] does,
+	/// but consulting the given `cache` before querying for any DNSKEY or DS records.
+	///
+	/// Any DNSKEY or DS records in the response are added to the cache, and records for keys
+	/// which are already in the cache are spliced into the proof rather than being queried for.
+	/// `now` is the current time as a UNIX timestamp, in seconds.
+	pub fn process_response_with_cache(&mut self, resp: &QueryBuf, cache: &mut ProofCache, now: u64)
+	-> Result<Vec<QueryBuf>, ProofBuildingError> {
+		self.process_response_inner(resp, Some((cache, now)))
+	}
+
+	fn process_response_inner(&mut self, resp: &QueryBuf, mut cache: Option<(&mut ProofCache, u64)>)
+	-> Result<Vec<QueryBuf>, ProofBuildingError> {
+		if self.pending_queries == 0 || self.is_iterative() {
+			return Err(ProofBuildingError::NoResponseExpected);
+		}
 
+		let proof_start = self.proof.len();
 		let mut rrsig_key_names = Vec::new();
-		let min_ttl = match handle_response(resp, &mut self.proof, &mut rrsig_key_names) {
+		let min_ttl = match handle_response(resp, &mut self.proof, &mut rrsig_key_names, true) {
 			Ok(min_ttl) => min_ttl,
 			Err(err) => {
 				if self.proof.is_empty() && err == ProofBuildingError::MissingRecord {
@@ -347,61 +479,104 @@ impl ProofBuilder {
 		self.min_ttl = cmp::min(self.min_ttl, min_ttl);
 		self.pending_queries -= 1;
 
-		rrsig_key_names.sort_unstable();
-		rrsig_key_names.dedup();
-
-		let mut new_queries = Vec::with_capacity(2);
-		for key_name in rrsig_key_names.drain(..) {
-			if !self.dnskeys_requested.contains(&key_name) {
-				new_queries.push(build_query(&key_name, DnsKey::TYPE));
-				self.pending_queries += 1;
-				self.queries_made += 1;
-				self.dnskeys_requested.push(key_name.clone());
-
-				if key_name.as_str() != "." {
-					new_queries.push(build_query(&key_name, DS::TYPE));
-					self.pending_queries += 1;
-					self.queries_made += 1;
+		if let Some((cache, now)) = cache.as_mut() {
+			if let Ok((name, ty)) = response_question(resp) {
+				if ty == DnsKey::TYPE || ty == DS::TYPE {
+					let records = parse_rr_stream(&self.proof[proof_start..])
+						.expect("We only ever write parseable records");
+					cache.insert(name, ty, records, rrsig_key_names.clone(), min_ttl, *now);
 				}
 			}
 		}
-		if self.queries_made <= MAX_PROOF_STEPS {
+
+		let cache = cache.as_ref().map(|(cache, now)| (&**cache, *now));
+		let new_queries = self.key_queries(rrsig_key_names, cache).iter()
+			.map(|(name, ty)| build_query(name, *ty))
+			.collect();
+		if self.queries_made <= self.query_limit() {
 			Ok(new_queries)
 		} else {
 			Ok(Vec::new())
 		}
 	}
 
+	/// Gets the DNSKEY and DS queries we need to make to fetch the keys which made the RRSIGs with
+	/// the given signer names, skipping any we've already requested and counting the rest as
+	/// pending.
+	///
+	/// Records found in the given `cache` (at the given time) are added to the proof directly,
+	/// rather than being queried for.
+	fn key_queries(&mut self, mut rrsig_key_names: Vec<Name>, cache: Option<(&ProofCache, u64)>)
+	-> Vec<(Name, u16)> {
+		let mut new_queries = Vec::with_capacity(2);
+		// Cached records may be signed by further keys, which we then need as well.
+		while !rrsig_key_names.is_empty() {
+			rrsig_key_names.sort_unstable();
+			rrsig_key_names.dedup();
+
+			for key_name in core::mem::take(&mut rrsig_key_names) {
+				if self.dnskeys_requested.contains(&key_name) { continue; }
+				self.dnskeys_requested.push(key_name.clone());
+
+				let types: &[u16] = if key_name.as_str() == "." { &[DnsKey::TYPE] } else { &[DnsKey::TYPE, DS::TYPE] };
+				for ty in types {
+					if let Some((records, signers, ttl)) = cache.and_then(|(cache, now)| cache.get(&key_name, *ty, now)) {
+						for rr in records { write_rr(rr, ttl, &mut self.proof); }
+						self.min_ttl = cmp::min(self.min_ttl, ttl);
+						rrsig_key_names.extend_from_slice(signers);
+					} else {
+						new_queries.push((key_name.clone(), *ty));
+						self.pending_queries += 1;
+						self.queries_made += 1;
+					}
+				}
+			}
+		}
+		new_queries
+	}
+
 	/// Finalizes the proof, if one is available, and returns it as well as the TTL that should be
 	/// used to cache the proof (i.e. the lowest TTL of all records which were used to build the
 	/// proof).
 	///
 	/// Only fails if too many queries have been made or there are still some pending queries.
 	pub fn finish_proof(self) -> Result<(Vec<u8>, u32), ()> {
-		if self.pending_queries > 0 || self.queries_made > MAX_PROOF_STEPS {
+		if self.pending_queries > 0 || self.queries_made > self.query_limit() {
 			Err(())
 		} else {
-			Ok((self.proof, self.min_ttl))
+			Ok((dedup_proof(self.proof), self.min_ttl))
 		}
 	}
+
+	/// Finalizes the proof as in [`Self::finish_proof`], wrapping it in an RFC 9102
+	/// [`DnssecChainExtension`] with the given
+	/// [`ext_support_lifetime`](DnssecChainExtension::ext_support_lifetime), ready to be sent in
+	/// a TLS `dnssec_chain` extension.
+	///
+	/// Also fails if no records were included in the proof.
+	pub fn finish_dnssec_chain_extension(self, ext_support_lifetime: u16)
+	-> Result<(DnssecChainExtension, u32), ()> {
+		let (proof, ttl) = self.finish_proof()?;
+		Ok((DnssecChainExtension::new(ext_support_lifetime, proof)?, ttl))
+	}
 }
 
 #[cfg(feature = "std")]
-fn send_query(stream: &mut TcpStream, query: &[u8]) -> Result<(), Error> {
+fn send_query<S: Write>(stream: &mut S, query: &[u8]) -> Result<(), Error> {
 	stream.write_all(&(query.len() as u16).to_be_bytes())?;
 	stream.write_all(&query)?;
-	Ok(())
+	stream.flush()
 }
 
 #[cfg(feature = "tokio")]
-async fn send_query_async(stream: &mut TokioTcpStream, query: &[u8]) -> Result<(), Error> {
+async fn send_query_async<S: AsyncWrite + Unpin>(stream: &mut S, query: &[u8]) -> Result<(), Error> {
 	stream.write_all(&(query.len() as u16).to_be_bytes()).await?;
 	stream.write_all(&query).await?;
-	Ok(())
+	stream.flush().await
 }
 
 #[cfg(feature = "std")]
-fn read_response(stream: &mut TcpStream) -> Result<QueryBuf, Error> {
+fn read_response<S: Read>(stream: &mut S) -> Result<QueryBuf, Error> {
 	let mut len_bytes = [0; 2];
 	stream.read_exact(&mut len_bytes)?;
 	let mut buf = QueryBuf::new_zeroed(u16::from_be_bytes(len_bytes));
@@ -410,7 +585,7 @@ fn read_response(stream: &mut TcpStream) -> Result<QueryBuf, Error> {
 }
 
 #[cfg(feature = "tokio")]
-async fn read_response_async(stream: &mut TokioTcpStream) -> Result<QueryBuf, Error> {
+async fn read_response_async<S: AsyncRead + Unpin>(stream: &mut S) -> Result<QueryBuf, Error> {
 	let mut len_bytes = [0; 2];
 	stream.read_exact(&mut len_bytes).await?;
 	let mut buf = QueryBuf::new_zeroed(u16::from_be_bytes(len_bytes));
@@ -418,198 +593,1660 @@ async fn read_response_async(stream: &mut TokioTcpStream) -> Result<QueryBuf, Er
 	Ok(buf)
 }
 
+/// A transport over which DNS queries can be made to a recursive resolver, used to drive a
+/// [`ProofBuilder`] with [`build_proof_with_transport`].
+///
+/// Queries are sent with [`Self::send_query`], and their responses are then read back, in any
+/// order, with [`Self::read_response`]. Several queries may be sent before any responses are read,
+/// so implementations which cannot pipeline queries should queue them until they're read.
+///
+/// Implementing this allows proofs to be built over other networking stacks or via proxies. See
+/// [`AsyncDnsTransport`] for the async flavor.
+#[cfg(feature = "std")]
+pub trait DnsTransport {
+	/// Sends the given query to the resolver.
+	fn send_query(&mut self, query: &QueryBuf) -> Result<(), Error>;
+	/// Reads the response to a query we previously sent to the resolver.
+	fn read_response(&mut self) -> Result<QueryBuf, Error>;
+	/// Sets how long the next [`Self::read_response`] may block before failing. This is called
+	/// before each read, as the time left before the overall deadline shrinks.
+	///
+	/// Transports which cannot time out reads may ignore this, which is the default.
+	fn set_read_timeout(&mut self, _timeout: Duration) -> Result<(), Error> { Ok(()) }
+}
+
+/// The future returned by [`AsyncDnsTransport`] methods.
 #[cfg(feature = "std")]
-macro_rules! build_proof_impl {
-	($stream: ident, $send_query: ident, $read_response: ident, $domain: expr, $ty: expr $(, $async_ok: tt)?) => { {
-		// We require the initial query to have already gone out, and assume our resolver will
-		// return any CNAMEs all the way to the final record in the response. From there, we just
-		// have to take any RRSIGs in the response and walk them up to the root. We do so
-		// iteratively, sending DNSKEY and DS lookups after every response, deduplicating requests
-		// using `dnskeys_requested`.
-		let (mut builder, initial_query) = ProofBuilder::new($domain, $ty);
-		$send_query(&mut $stream, &initial_query)
-			$(.await?; $async_ok)??; // Either await?; Ok(())?, or just ?
-		while builder.awaiting_responses() {
-			let response = $read_response(&mut $stream)
-				$(.await?; $async_ok)??; // Either await?; Ok(())?, or just ?
-			let new_queries = builder.process_response(&response)
-				.map_err(|err| Error::new(ErrorKind::Other, err))?;
-			for query in new_queries {
-				$send_query(&mut $stream, &query)
-					$(.await?; $async_ok)??; // Either await?; Ok(())?, or just ?
-			}
-		}
+pub type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// An async transport over which DNS queries can be made to a recursive resolver, used to drive a
+/// [`ProofBuilder`] with [`build_proof_with_transport_async`].
+///
+/// This works exactly as [`DnsTransport`] does, but returns (boxed) futures, allowing it to be
+/// implemented for any async runtime.
+#[cfg(feature = "std")]
+pub trait AsyncDnsTransport {
+	/// Sends the given query to the resolver.
+	fn send_query<'a>(&'a mut self, query: &'a QueryBuf) -> TransportFuture<'a, ()>;
+	/// Reads the response to a query we previously sent to the resolver.
+	fn read_response(&mut self) -> TransportFuture<'_, QueryBuf>;
+}
+
+/// A [`DnsTransport`] (and, with the `tokio` feature, an [`AsyncDnsTransport`]) which makes
+/// queries over a stream using the length-prefixed framing DNS uses over TCP (and TLS).
+///
+/// All the queries needed are pipelined on the stream, so it must be able to handle several
+/// queries in flight at once.
+#[cfg(feature = "std")]
+pub struct StreamTransport<S> {
+	stream: S,
+	/// A handle to the TCP socket underlying `stream`, if we know of one, used to set read
+	/// timeouts.
+	socket: Option<TcpStream>,
+}
+
+#[cfg(feature = "std")]
+impl<S> StreamTransport<S> {
+	/// Wraps the given stream, which must already be connected to the resolver.
+	pub fn new(stream: S) -> Self {
+		Self { stream, socket: None }
+	}
+
+	/// Wraps the given stream, which runs over (a clone of) the given TCP `socket`, allowing
+	/// [`DnsTransport::set_read_timeout`] to set the socket's read timeout.
+	fn new_over_tcp(stream: S, socket: TcpStream) -> Self {
+		Self { stream, socket: Some(socket) }
+	}
 
-		builder.finish_proof()
-			.map_err(|()| Error::new(ErrorKind::Other, "Too many requests required"))
-	} }
+	/// Gets the underlying stream back.
+	pub fn into_inner(self) -> S {
+		self.stream
+	}
 }
 
 #[cfg(feature = "std")]
-fn build_proof(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
-	let mut stream = TcpStream::connect(resolver)?;
-	build_proof_impl!(stream, send_query, read_response, domain, ty)
+impl<S: Read + Write> DnsTransport for StreamTransport<S> {
+	fn send_query(&mut self, query: &QueryBuf) -> Result<(), Error> {
+		send_query(&mut self.stream, query)
+	}
+	fn read_response(&mut self) -> Result<QueryBuf, Error> {
+		read_response(&mut self.stream)
+	}
+	fn set_read_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+		match &self.socket {
+			Some(socket) => socket.set_read_timeout(Some(timeout)),
+			None => Ok(()),
+		}
+	}
 }
 
 #[cfg(feature = "tokio")]
-async fn build_proof_async(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
-	let mut stream = TokioTcpStream::connect(resolver).await?;
-	build_proof_impl!(stream, send_query_async, read_response_async, domain, ty, { Ok::<(), Error>(()) })
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncDnsTransport for StreamTransport<S> {
+	fn send_query<'a>(&'a mut self, query: &'a QueryBuf) -> TransportFuture<'a, ()> {
+		Box::pin(send_query_async(&mut self.stream, query))
+	}
+	fn read_response(&mut self) -> TransportFuture<'_, QueryBuf> {
+		Box::pin(read_response_async(&mut self.stream))
+	}
 }
 
-/// Builds a DNSSEC proof for an A record by querying a recursive resolver, returning the proof as
-/// well as the TTL for the proof provided by the recursive resolver.
+mod cache;
+pub use cache::ProofCache;
+#[cfg(any(feature = "doh", feature = "dot"))]
+mod tls;
+#[cfg(feature = "doh")]
+mod doh;
+#[cfg(feature = "doh")]
+pub use doh::{DohMethod, build_proof_doh, build_proof_for_types_doh};
+#[cfg(all(feature = "doh", feature = "tokio", feature = "tokio-rustls"))]
+pub use doh::{build_proof_doh_async, build_proof_for_types_doh_async};
+#[cfg(feature = "dot")]
+mod dot;
+#[cfg(feature = "dot")]
+pub use dot::{DotAuthentication, build_proof_dot, build_proof_for_types_dot};
+#[cfg(all(feature = "dot", feature = "tokio", feature = "tokio-rustls"))]
+pub use dot::{build_proof_dot_async, build_proof_for_types_dot_async};
+#[cfg(feature = "std")]
+mod iterative;
+#[cfg(feature = "std")]
+pub use iterative::{IterativeQuery, ROOT_HINTS, build_proof_iterative, build_proof_for_types_iterative, build_proof_for_questions_iterative_with_config};
+#[cfg(feature = "tokio")]
+pub use iterative::{build_proof_iterative_async, build_proof_for_types_iterative_async, build_proof_for_questions_iterative_with_config_async};
+
+/// Returns true if proofs can be built for records of the given type.
 ///
-/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
-/// module to validate the records contained.
+/// This is any type supported by this library (i.e. one with an [`RR`] variant) except for
+/// [`RRSig`], which cannot be proven on its own.
 #[cfg(feature = "std")]
-pub fn build_a_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof(resolver, domain, A::TYPE)
+fn is_provable_type(ty: u16) -> bool {
+	RR::ty_to_rr_name(ty).is_some() && ty != RRSig::TYPE
 }
 
-/// Builds a DNSSEC proof for an AAAA record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Checks that proofs can be built for records of all the given `types` (and that some were given).
+#[cfg(feature = "std")]
+fn check_provable_types(types: &[u16]) -> Result<(), Error> {
+	if types.is_empty() || !types.iter().all(|ty| is_provable_type(*ty)) {
+		Err(Error::new(ErrorKind::InvalidInput, "Unsupported record type"))
+	} else {
+		Ok(())
+	}
+}
+
+/// Checks that proofs can be built for all the given `questions` (and that some were given).
+#[cfg(feature = "std")]
+fn check_provable_questions(questions: &[(Name, u16)]) -> Result<(), Error> {
+	check_provable_types(&questions.iter().map(|(_, ty)| *ty).collect::<Vec<_>>())
+}
+
+/// Gets the questions we need to ask to build a proof for records of each of the given `types` at
+/// `domain`.
+#[cfg(feature = "std")]
+fn type_questions(domain: &Name, types: &[u16]) -> Vec<(Name, u16)> {
+	types.iter().map(|ty| (domain.clone(), *ty)).collect()
+}
+
+/// Finalizes the proof in the given `builder`.
+#[cfg(feature = "std")]
+fn finish(builder: ProofBuilder) -> Result<(Vec<u8>, u32), Error> {
+	builder.finish_proof().map_err(|()| Error::new(ErrorKind::Other, "Too many requests required"))
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by making queries to a recursive resolver
+/// over the given `transport`, returning the proof as well as the TTL for the proof provided by
+/// the recursive resolver.
+///
+/// This can be used to build proofs over other networking stacks, though no timeouts or retries
+/// are applied - see [`build_proof_for_questions_with_config_and_transport`] for those. Fails with
+/// [`ErrorKind::InvalidInput`] if the given `ty`pe isn't supported (see [`build_proof`]).
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
 #[cfg(feature = "std")]
-pub fn build_aaaa_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof(resolver, domain, AAAA::TYPE)
+pub fn build_proof_with_transport<T: DnsTransport + ?Sized>(transport: &mut T, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_transport(transport, domain, &[ty])
 }
 
-/// Builds a DNSSEC proof for an TXT record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but by making queries over the given `transport` (see
+/// [`build_proof_with_transport`]).
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
 #[cfg(feature = "std")]
-pub fn build_txt_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof(resolver, domain, Txt::TYPE)
+pub fn build_proof_for_types_with_transport<T: DnsTransport + ?Sized>(transport: &mut T, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_transport(transport, &type_questions(domain, types))
 }
 
-/// Builds a DNSSEC proof for an TLSA record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Builds a single DNSSEC proof for records of the given type at the given name for each of the
+/// given `questions` (see [`ProofBuilder::new_for_questions`]), by making queries over the given
+/// `transport` (see [`build_proof_with_transport`]).
+///
+/// Fails if a proof for any of the questions cannot be built, or with [`ErrorKind::InvalidInput`]
+/// if any type isn't supported or no questions were given.
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
 #[cfg(feature = "std")]
-pub fn build_tlsa_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof(resolver, domain, TLSA::TYPE)
+pub fn build_proof_for_questions_with_transport<T: DnsTransport + ?Sized>(transport: &mut T, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_questions(questions)?;
+	// We require the initial queries to have already gone out, and assume our resolver will
+	// return any CNAMEs all the way to the final record in the response. From there, we just
+	// have to take any RRSIGs in the response and walk them up to the root. We do so
+	// iteratively, sending DNSKEY and DS lookups after every response, deduplicating requests
+	// using `dnskeys_requested`.
+	let (mut builder, initial_queries) = ProofBuilder::new_for_questions(questions);
+	for query in initial_queries {
+		transport.send_query(&query)?;
+	}
+	while builder.awaiting_responses() {
+		let response = transport.read_response()?;
+		let new_queries = builder.process_response(&response)
+			.map_err(|err| Error::new(ErrorKind::Other, err))?;
+		for query in new_queries {
+			transport.send_query(&query)?;
+		}
+	}
+	finish(builder)
 }
 
-
-/// Builds a DNSSEC proof for an A record by querying a recursive resolver, returning the proof as
-/// well as the TTL for the proof provided by the recursive resolver.
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_with_transport`] does,
+/// but asynchronously over an [`AsyncDnsTransport`].
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
-#[cfg(feature = "tokio")]
-pub async fn build_a_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof_async(resolver, domain, A::TYPE).await
+#[cfg(feature = "std")]
+pub async fn build_proof_with_transport_async<T: AsyncDnsTransport + ?Sized>(transport: &mut T, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_transport_async(transport, domain, &[ty]).await
 }
 
-/// Builds a DNSSEC proof for an AAAA record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_with_transport`] does, but asynchronously over an
+/// [`AsyncDnsTransport`].
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
-#[cfg(feature = "tokio")]
-pub async fn build_aaaa_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof_async(resolver, domain, AAAA::TYPE).await
+#[cfg(feature = "std")]
+pub async fn build_proof_for_types_with_transport_async<T: AsyncDnsTransport + ?Sized>(transport: &mut T, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_transport_async(transport, &type_questions(domain, types)).await
 }
 
-/// Builds a DNSSEC proof for an TXT record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_with_transport`] does, but asynchronously.
 ///
 /// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
 /// module to validate the records contained.
-#[cfg(feature = "tokio")]
-pub async fn build_txt_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof_async(resolver, domain, Txt::TYPE).await
+#[cfg(feature = "std")]
+pub async fn build_proof_for_questions_with_transport_async<T: AsyncDnsTransport + ?Sized>(transport: &mut T, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_questions(questions)?;
+	// See `build_proof_for_questions_with_transport` for how this works.
+	let (mut builder, initial_queries) = ProofBuilder::new_for_questions(questions);
+	for query in initial_queries {
+		transport.send_query(&query).await?;
+	}
+	while builder.awaiting_responses() {
+		let response = transport.read_response().await?;
+		let new_queries = builder.process_response(&response)
+			.map_err(|err| Error::new(ErrorKind::Other, err))?;
+		for query in new_queries {
+			transport.send_query(&query).await?;
+		}
+	}
+	finish(builder)
 }
 
-/// Builds a DNSSEC proof for an TLSA record by querying a recursive resolver, returning the proof
-/// as well as the TTL for the proof provided by the recursive resolver.
+/// Configuration for building proofs by querying recursive resolvers over TCP with
+/// [`build_proof_with_config`], or over any transport with
+/// [`build_proof_for_questions_with_config_and_transport`].
 ///
-/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
-/// module to validate the records contained.
-#[cfg(feature = "tokio")]
-pub async fn build_tlsa_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
-	build_proof_async(resolver, domain, TLSA::TYPE).await
+/// Each query is retried (on the next resolver) if the resolver fails to respond in time, the
+/// connection fails, or the resolver responds with a server failure. Only the queries which failed
+/// are retried, any responses already received are kept.
+#[derive(Clone, Debug)]
+#[cfg(feature = "std")]
+pub struct ProofBuildingConfig {
+	/// The recursive resolvers to query.
+	///
+	/// Queries are made to the first resolver until a query fails, at which point we fail over to
+	/// the next resolver (wrapping around to the first after the last).
+	pub resolvers: Vec<SocketAddr>,
+	/// How long we wait for a connection to a resolver to be established or for the next response
+	/// from it before treating the outstanding queries as failed.
+	pub query_timeout: Duration,
+	/// How long we allow for building the full proof, after which we give up.
+	pub overall_timeout: Duration,
+	/// How many times each query is retried after it fails before we give up.
+	pub query_retries: usize,
+	/// A cache of DNSKEY and DS records to share across proofs (see [`ProofCache`]), or `None` to
+	/// fetch all the keys needed for each proof.
+	pub cache: Option<Arc<Mutex<ProofCache>>>,
 }
 
-#[cfg(all(feature = "validation", feature = "std", test))]
-mod tests {
-	use super::*;
-	use crate::validation::*;
+#[cfg(feature = "std")]
+impl ProofBuildingConfig {
+	/// Constructs a new config which queries the given `resolvers`, with a five second
+	/// [`query_timeout`](Self::query_timeout), a 30 second
+	/// [`overall_timeout`](Self::overall_timeout), two [`query_retries`](Self::query_retries) and
+	/// no [`cache`](Self::cache).
+	pub fn new(resolvers: Vec<SocketAddr>) -> Self {
+		Self {
+			resolvers,
+			query_timeout: Duration::from_secs(5),
+			overall_timeout: Duration::from_secs(30),
+			query_retries: 2,
+			cache: None,
+		}
+	}
+}
 
-	use rand::seq::SliceRandom;
+/// A query which we've yet to get a response to.
+#[cfg(feature = "std")]
+struct PendingQuery {
+	query: QueryBuf,
+	failures: usize,
+	sent: bool,
+	/// When building a proof iteratively, the authoritative servers to send the query to, moving
+	/// on to the next after each failure. Otherwise empty, as the query goes to the resolver.
+	servers: Vec<SocketAddr>,
+}
 
-	use std::net::ToSocketAddrs;
-	use std::time::SystemTime;
+/// Tracks the queries we're waiting on while building a proof with a [`ProofBuildingConfig`],
+/// deciding when to retry them and which resolver to query.
+#[cfg(feature = "std")]
+struct RetryingQueries<'a> {
+	config: &'a ProofBuildingConfig,
+	deadline: Instant,
+	resolver_idx: usize,
+	pending: Vec<PendingQuery>,
+}
 
-	#[test]
-	fn test_cloudflare_txt_query() {
-		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
-		let query_name = "cloudflare.com.".try_into().unwrap();
-		let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+#[cfg(feature = "std")]
+impl<'a> RetryingQueries<'a> {
+	fn new(config: &'a ProofBuildingConfig) -> Result<Self, Error> {
+		if config.resolvers.is_empty() {
+			return Err(Error::new(ErrorKind::InvalidInput, "No resolvers configured"));
+		}
+		let deadline = Instant::now() + config.overall_timeout;
+		Ok(Self { config, deadline, resolver_idx: 0, pending: Vec::new() })
+	}
 
-		let mut rrs = parse_rr_stream(&proof).unwrap();
-		rrs.shuffle(&mut rand::rngs::OsRng);
-		let verified_rrs = verify_rr_stream(&rrs).unwrap();
-		assert!(verified_rrs.verified_rrs.len() > 1);
+	/// Starts tracking the queries for a new [`ProofBuilder`].
+	fn start(&mut self, initial_queries: Vec<QueryBuf>) {
+		self.pending = Self::pending_queries(initial_queries);
+	}
 
-		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-		assert!(verified_rrs.valid_from < now);
-		assert!(verified_rrs.expires > now);
+	/// Wraps new queries to the resolver for tracking.
+	fn pending_queries(queries: Vec<QueryBuf>) -> Vec<PendingQuery> {
+		queries.into_iter()
+			.map(|query| PendingQuery { query, failures: 0, sent: false, servers: Vec::new() })
+			.collect()
 	}
 
-	#[test]
-	fn test_sha1_query() {
-		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
-		let query_name = "benthecarman.com.".try_into().unwrap();
-		let (proof, _) = build_a_proof(sockaddr, &query_name).unwrap();
+	fn resolver(&self) -> SocketAddr {
+		self.config.resolvers[self.resolver_idx]
+	}
 
-		let mut rrs = parse_rr_stream(&proof).unwrap();
-		rrs.shuffle(&mut rand::rngs::OsRng);
-		let verified_rrs = verify_rr_stream(&rrs).unwrap();
-		assert!(verified_rrs.verified_rrs.len() >= 1);
+	/// Gets how long we should wait on the resolver, failing if we've hit the overall deadline.
+	fn timeout(&self) -> Result<Duration, Error> {
+		let remaining = self.deadline.saturating_duration_since(Instant::now());
+		if remaining.is_zero() {
+			Err(Error::new(ErrorKind::TimedOut, "Timed out building proof"))
+		} else {
+			Ok(cmp::min(self.config.query_timeout, remaining))
+		}
+	}
 
-		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-		assert!(verified_rrs.valid_from < now);
-		assert!(verified_rrs.expires > now);
+	/// Moves on to the next resolver. The connection to the current resolver must be dropped, and
+	/// all pending queries will be sent again.
+	fn fail_over(&mut self) {
+		self.resolver_idx = (self.resolver_idx + 1) % self.config.resolvers.len();
+		for query in self.pending.iter_mut() { query.sent = false; }
 	}
 
-	#[test]
-	fn test_txt_query() {
-		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
-		let query_name = "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap();
-		let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+	/// Handles a failure to connect to, send to or read from the resolver, counting a failure
+	/// against the queries which were in flight on the failed connection and failing over to the
+	/// next resolver.
+	///
+	/// If we failed before sending any queries (e.g. because we couldn't connect), all pending
+	/// queries were blocked by the failure, so it is counted against each of them.
+	fn connection_failed(&mut self, err: Error) -> Result<(), Error> {
+		let any_sent = self.pending.iter().any(|query| query.sent);
+		for query in self.pending.iter_mut().filter(|query| query.sent || !any_sent) {
+			query.failures += 1;
+			if query.failures > self.config.query_retries { return Err(err); }
+		}
+		self.fail_over();
+		Ok(())
+	}
 
-		let mut rrs = parse_rr_stream(&proof).unwrap();
-		rrs.shuffle(&mut rand::rngs::OsRng);
-		let verified_rrs = verify_rr_stream(&rrs).unwrap();
-		assert_eq!(verified_rrs.verified_rrs.len(), 1);
+	/// Handles a response from the resolver, passing it to the `builder` and tracking any new
+	/// queries. Returns true if we failed over to the next resolver (after a server failure).
+	fn handle_response(&mut self, builder: &mut ProofBuilder, resp: &QueryBuf) -> Result<bool, Error> {
+		let idx = match self.pending.iter().position(|query| query.sent && response_matches(&query.query, resp)) {
+			Some(idx) => idx,
+			// A response to some query we aren't waiting on (anymore), just ignore it.
+			None => return Ok(false),
+		};
+		let res = if builder.is_iterative() {
+			builder.process_iterative_response(resp)
+				.map(|new_queries| new_queries.into_iter().map(iterative::pending_query).collect())
+		} else if let Some(cache) = &self.config.cache {
+			let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+				.map(|now| now.as_secs()).unwrap_or(0);
+			let mut cache = cache.lock().unwrap_or_else(PoisonError::into_inner);
+			builder.process_response_with_cache(resp, &mut cache, now).map(Self::pending_queries)
+		} else {
+			builder.process_response(resp).map(Self::pending_queries)
+		};
+		match res {
+			Ok(new_queries) => {
+				self.pending.swap_remove(idx);
+				self.pending.extend(new_queries);
+				Ok(false)
+			},
+			Err(ProofBuildingError::ServerFailure) => {
+				self.pending[idx].failures += 1;
+				if self.pending[idx].failures > self.config.query_retries {
+					return Err(Error::new(ErrorKind::Other, ProofBuildingError::ServerFailure));
+				}
+				self.fail_over();
+				Ok(true)
+			},
+			Err(err) => Err(Error::new(ErrorKind::Other, err)),
+		}
+	}
+}
 
-		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-		assert!(verified_rrs.valid_from < now);
-		assert!(verified_rrs.expires > now);
+/// Sends any unsent queries to the resolver (connecting with `connect` first if we aren't yet
+/// connected) and reads the next response.
+#[cfg(feature = "std")]
+fn query_step<T: DnsTransport, F: FnMut(SocketAddr, Duration) -> Result<T, Error>>(
+	transport: &mut Option<T>, connect: &mut F, queries: &mut RetryingQueries, timeout: Duration,
+) -> Result<QueryBuf, Error> {
+	if transport.is_none() {
+		*transport = Some(connect(queries.resolver(), timeout)?);
+	}
+	let transport = transport.as_mut().expect("Just set it");
+	for query in queries.pending.iter_mut().filter(|query| !query.sent) {
+		transport.send_query(&query.query)?;
+		query.sent = true;
 	}
+	transport.set_read_timeout(queries.timeout()?)?;
+	transport.read_response()
+}
 
-	#[test]
-	fn test_cname_query() {
-		for resolver in ["1.1.1.1:53", "8.8.8.8:53", "9.9.9.9:53"] {
-			let sockaddr = resolver.to_socket_addrs().unwrap().next().unwrap();
-			let query_name = "cname_test.dnssec_proof_tests.bitcoin.ninja.".try_into().unwrap();
-			let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+/// Sends any unsent queries to the resolver (connecting with `connect` first if we aren't yet
+/// connected) and reads the next response, giving up after `timeout`.
+#[cfg(feature = "tokio")]
+async fn query_step_async<T, F, Fut>(
+	transport: &mut Option<T>, connect: &mut F, queries: &mut RetryingQueries<'_>, timeout: Duration,
+) -> Result<QueryBuf, Error>
+where T: AsyncDnsTransport, F: FnMut(SocketAddr, Duration) -> Fut, Fut: Future<Output = Result<T, Error>> {
+	let step = async {
+		if transport.is_none() {
+			*transport = Some(connect(queries.resolver(), timeout).await?);
+		}
+		let transport = transport.as_mut().expect("Just set it");
+		for query in queries.pending.iter_mut().filter(|query| !query.sent) {
+			transport.send_query(&query.query).await?;
+			query.sent = true;
+		}
+		transport.read_response().await
+	};
+	match tokio_crate::time::timeout(timeout, step).await {
+		Ok(res) => res,
+		Err(_) => Err(Error::new(ErrorKind::TimedOut, "Timed out waiting for the resolver")),
+	}
+}
 
-			let mut rrs = parse_rr_stream(&proof).unwrap();
-			rrs.shuffle(&mut rand::rngs::OsRng);
-			let verified_rrs = verify_rr_stream(&rrs).unwrap();
-			assert_eq!(verified_rrs.verified_rrs.len(), 2);
+/// Connects to the given `resolver` over TCP, timing out connecting and each read or write after
+/// `timeout`. Transports built over the stream should update the read timeout as
+/// [`DnsTransport::set_read_timeout`] is called.
+#[cfg(feature = "std")]
+fn connect_tcp(resolver: SocketAddr, timeout: Duration) -> Result<TcpStream, Error> {
+	let stream = TcpStream::connect_timeout(&resolver, timeout)?;
+	stream.set_read_timeout(Some(timeout))?;
+	stream.set_write_timeout(Some(timeout))?;
+	Ok(stream)
+}
 
-			let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-			assert!(verified_rrs.valid_from < now);
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_with_config`] does, but making queries over transports returned by
+/// `connect` rather than over TCP.
+///
+/// `connect` is called with the resolver from the `config` to query whenever we need a new
+/// connection, i.e. initially and after failing over to the next resolver, and should time out
+/// connecting after the given [`Duration`]. Before each read the transport's
+/// [`DnsTransport::set_read_timeout`] is called with the time left for it, so transports which
+/// don't implement it should time out reads after the given [`Duration`] instead. This allows
+/// retries, timeouts and fail-over to be used with any [`DnsTransport`], as the TCP, UDP, DoH and
+/// DoT drivers in this module do.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_questions_with_config_and_transport<T, F>(
+	config: &ProofBuildingConfig, questions: &[(Name, u16)], mut connect: F,
+) -> Result<(Vec<u8>, u32), Error>
+where T: DnsTransport, F: FnMut(SocketAddr, Duration) -> Result<T, Error> {
+	check_provable_questions(questions)?;
+	let mut queries = RetryingQueries::new(config)?;
+	let mut transport = None;
+	let (mut builder, initial_queries) = ProofBuilder::new_for_questions(questions);
+	queries.start(initial_queries);
+	while builder.awaiting_responses() {
+		let timeout = queries.timeout()?;
+		match query_step(&mut transport, &mut connect, &mut queries, timeout) {
+			Ok(resp) => if queries.handle_response(&mut builder, &resp)? { transport = None; },
+			Err(err) => {
+				transport = None;
+				queries.connection_failed(err)?;
+			},
+		}
+	}
+	finish(builder)
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_with_config_and_transport`] does, but asynchronously using `tokio`
+/// over [`AsyncDnsTransport`]s.
+///
+/// Each step, including connecting, is also bounded by `tokio`'s timer, so `connect` may ignore
+/// the [`Duration`] it is given.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_questions_with_config_and_transport_async<T, F, Fut>(
+	config: &ProofBuildingConfig, questions: &[(Name, u16)], mut connect: F,
+) -> Result<(Vec<u8>, u32), Error>
+where T: AsyncDnsTransport, F: FnMut(SocketAddr, Duration) -> Fut, Fut: Future<Output = Result<T, Error>> {
+	check_provable_questions(questions)?;
+	let mut queries = RetryingQueries::new(config)?;
+	let mut transport = None;
+	let (mut builder, initial_queries) = ProofBuilder::new_for_questions(questions);
+	queries.start(initial_queries);
+	while builder.awaiting_responses() {
+		let timeout = queries.timeout()?;
+		match query_step_async(&mut transport, &mut connect, &mut queries, timeout).await {
+			Ok(resp) => if queries.handle_response(&mut builder, &resp)? { transport = None; },
+			Err(err) => {
+				transport = None;
+				queries.connection_failed(err)?;
+			},
+		}
+	}
+	finish(builder)
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by querying the recursive resolvers in
+/// the given `config` over TCP, retrying queries and failing over between resolvers as configured
+/// (see [`ProofBuildingConfig`]), returning the proof as well as the TTL for the proof provided by
+/// the recursive resolvers.
+///
+/// Fails with [`ErrorKind::TimedOut`] if the [`ProofBuildingConfig::overall_timeout`] is hit, or
+/// with the error from the last attempt if some query failed too many times.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_with_config(config: &ProofBuildingConfig, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_config(config, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but querying the recursive resolvers in the given `config` (see
+/// [`build_proof_with_config`]).
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_types_with_config(config: &ProofBuildingConfig, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config(config, &type_questions(domain, types))
+}
+
+/// Builds a single DNSSEC proof for records of the given type at the given name for each of the
+/// given `questions` (see [`ProofBuilder::new_for_questions`]), querying the recursive resolvers in
+/// the given `config` (see [`build_proof_with_config`]).
+///
+/// Fails if a proof for any of the questions cannot be built, or with [`ErrorKind::InvalidInput`]
+/// if any type isn't supported or no questions were given.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_questions_with_config(config: &ProofBuildingConfig, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config_and_transport(config, questions,
+		|resolver, timeout| {
+			let stream = connect_tcp(resolver, timeout)?;
+			let socket = stream.try_clone()?;
+			Ok(StreamTransport::new_over_tcp(stream, socket))
+		})
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_with_config`] does, but
+/// asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_with_config_async(config: &ProofBuildingConfig, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_config_async(config, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_with_config`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_types_with_config_async(config: &ProofBuildingConfig, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config_async(config, &type_questions(domain, types)).await
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_with_config`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_questions_with_config_async(config: &ProofBuildingConfig, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config_and_transport_async(config, questions,
+		|resolver, _| async move { TokioTcpStream::connect(resolver).await.map(StreamTransport::new) }).await
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by querying a recursive resolver,
+/// returning the proof as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Constants for supported types can be found in the [`crate::rr`] module, or a type can be looked
+/// up by its mnemonic using [`RR::ty_from_name`]. Fails with [`ErrorKind::InvalidInput`] if the
+/// given `ty`pe isn't supported.
+///
+/// Queries time out and are retried as [`ProofBuildingConfig::new`] describes. Use
+/// [`build_proof_with_config`] to configure this or to fail over between several resolvers.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types(resolver, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` by querying a recursive
+/// resolver, returning the proof as well as the TTL for the proof provided by the recursive
+/// resolver.
+///
+/// Records which are needed for more than one type (e.g. the DNSKEYs and DSs from the zone up to
+/// the root) are only included once. Fails if a proof for any of the given types cannot be built,
+/// or with [`ErrorKind::InvalidInput`] if any type isn't supported (see [`build_proof`]) or no
+/// types were given.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_types(resolver: SocketAddr, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_config(&ProofBuildingConfig::new(vec![resolver]), domain, types)
+}
+
+/// Builds a single DNSSEC proof for records of the given type at the given name for each of the
+/// given `questions` (e.g. the TXT records for a BIP 353 name alongside the TLSA records for a
+/// related host) by querying a recursive resolver, returning the proof as well as the TTL for the
+/// proof provided by the recursive resolver.
+///
+/// Records which are needed for more than one question are only included once. Fails if a proof
+/// for any of the questions cannot be built, or with [`ErrorKind::InvalidInput`] if any type isn't
+/// supported (see [`build_proof`]) or no questions were given.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_questions(resolver: SocketAddr, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config(&ProofBuildingConfig::new(vec![resolver]), questions)
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by querying a recursive resolver,
+/// returning the proof as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Constants for supported types can be found in the [`crate::rr`] module, or a type can be looked
+/// up by its mnemonic using [`RR::ty_from_name`]. Fails with [`ErrorKind::InvalidInput`] if the
+/// given `ty`pe isn't supported.
+///
+/// Queries time out and are retried as [`ProofBuildingConfig::new`] describes. Use
+/// [`build_proof_with_config_async`] to configure this or to fail over between several resolvers.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_async(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_async(resolver, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` by querying a recursive
+/// resolver, returning the proof as well as the TTL for the proof provided by the recursive
+/// resolver.
+///
+/// Records which are needed for more than one type (e.g. the DNSKEYs and DSs from the zone up to
+/// the root) are only included once. Fails if a proof for any of the given types cannot be built,
+/// or with [`ErrorKind::InvalidInput`] if any type isn't supported (see [`build_proof_async`]) or
+/// no types were given.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_types_async(resolver: SocketAddr, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_with_config_async(&ProofBuildingConfig::new(vec![resolver]), domain, types).await
+}
+
+/// Builds a single DNSSEC proof for records of the given type at the given name for each of the
+/// given `questions` as [`build_proof_for_questions`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_questions_async(resolver: SocketAddr, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_with_config_async(&ProofBuildingConfig::new(vec![resolver]), questions).await
+}
+
+/// Builds a DNSSEC proof for an A record by querying a recursive resolver, returning the proof as
+/// well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_a_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof(resolver, domain, A::TYPE)
+}
+
+/// Builds a DNSSEC proof for an AAAA record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_aaaa_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof(resolver, domain, AAAA::TYPE)
+}
+
+/// Builds a DNSSEC proof for an TXT record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_txt_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof(resolver, domain, Txt::TYPE)
+}
+
+/// Builds a DNSSEC proof for an TLSA record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_tlsa_proof(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof(resolver, domain, TLSA::TYPE)
+}
+
+/// Builds a DNSSEC proof for the PTR record (i.e. reverse lookup) for the given IP address by
+/// querying a recursive resolver, returning the proof as well as the TTL for the proof provided by
+/// the recursive resolver.
+///
+/// The name queried for is derived from the IP address using [`PTR::name_for_ip`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_ptr_proof(resolver: SocketAddr, ip: IpAddr) -> Result<(Vec<u8>, u32), Error> {
+	build_proof(resolver, &PTR::name_for_ip(ip), PTR::TYPE)
+}
+
+/// Builds a DNSSEC proof for the OPENPGPKEY record for the given email address by querying a
+/// recursive resolver, returning the proof as well as the TTL for the proof provided by the
+/// recursive resolver.
+///
+/// The name queried for is derived from the email address using [`OpenPGPKey::name_for_email`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "std", feature = "validation"))]
+pub fn build_openpgpkey_proof(resolver: SocketAddr, email: &str) -> Result<(Vec<u8>, u32), Error> {
+	let domain = OpenPGPKey::name_for_email(email)
+		.map_err(|()| Error::new(ErrorKind::InvalidInput, "Invalid email address"))?;
+	build_proof(resolver, &domain, OpenPGPKey::TYPE)
+}
+
+/// Builds a DNSSEC proof for the SMIMEA record for the given email address by querying a
+/// recursive resolver, returning the proof as well as the TTL for the proof provided by the
+/// recursive resolver.
+///
+/// The name queried for is derived from the email address using [`SMIMEA::name_for_email`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "std", feature = "validation"))]
+pub fn build_smimea_proof(resolver: SocketAddr, email: &str) -> Result<(Vec<u8>, u32), Error> {
+	let domain = SMIMEA::name_for_email(email)
+		.map_err(|()| Error::new(ErrorKind::InvalidInput, "Invalid email address"))?;
+	build_proof(resolver, &domain, SMIMEA::TYPE)
+}
+
+
+/// Builds a DNSSEC proof for an A record by querying a recursive resolver, returning the proof as
+/// well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_a_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_async(resolver, domain, A::TYPE).await
+}
+
+/// Builds a DNSSEC proof for an AAAA record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_aaaa_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_async(resolver, domain, AAAA::TYPE).await
+}
+
+/// Builds a DNSSEC proof for an TXT record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_txt_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_async(resolver, domain, Txt::TYPE).await
+}
+
+/// Builds a DNSSEC proof for an TLSA record by querying a recursive resolver, returning the proof
+/// as well as the TTL for the proof provided by the recursive resolver.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_tlsa_proof_async(resolver: SocketAddr, domain: &Name) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_async(resolver, domain, TLSA::TYPE).await
+}
+
+/// Builds a DNSSEC proof for the PTR record (i.e. reverse lookup) for the given IP address by
+/// querying a recursive resolver, returning the proof as well as the TTL for the proof provided by
+/// the recursive resolver.
+///
+/// The name queried for is derived from the IP address using [`PTR::name_for_ip`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_ptr_proof_async(resolver: SocketAddr, ip: IpAddr) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_async(resolver, &PTR::name_for_ip(ip), PTR::TYPE).await
+}
+
+/// Builds a DNSSEC proof for the OPENPGPKEY record for the given email address by querying a
+/// recursive resolver, returning the proof as well as the TTL for the proof provided by the
+/// recursive resolver.
+///
+/// The name queried for is derived from the email address using [`OpenPGPKey::name_for_email`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "validation"))]
+pub async fn build_openpgpkey_proof_async(resolver: SocketAddr, email: &str) -> Result<(Vec<u8>, u32), Error> {
+	let domain = OpenPGPKey::name_for_email(email)
+		.map_err(|()| Error::new(ErrorKind::InvalidInput, "Invalid email address"))?;
+	build_proof_async(resolver, &domain, OpenPGPKey::TYPE).await
+}
+
+/// Builds a DNSSEC proof for the SMIMEA record for the given email address by querying a
+/// recursive resolver, returning the proof as well as the TTL for the proof provided by the
+/// recursive resolver.
+///
+/// The name queried for is derived from the email address using [`SMIMEA::name_for_email`].
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "validation"))]
+pub async fn build_smimea_proof_async(resolver: SocketAddr, email: &str) -> Result<(Vec<u8>, u32), Error> {
+	let domain = SMIMEA::name_for_email(email)
+		.map_err(|()| Error::new(ErrorKind::InvalidInput, "Invalid email address"))?;
+	build_proof_async(resolver, &domain, SMIMEA::TYPE).await
+}
+
+/// How long we wait for a response to a UDP query before sending it again.
+#[cfg(feature = "std")]
+const UDP_RETRANSMIT_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many times we send a UDP query before giving up on it.
+#[cfg(feature = "std")]
+const UDP_QUERY_ATTEMPTS: usize = 3;
+
+/// Replaces the transaction ID in the given query with a random one, returning it.
+#[cfg(feature = "std")]
+fn randomize_txid(query: &mut [u8]) -> Result<u16, Error> {
+	let mut txid = [0; 2];
+	getrandom::getrandom(&mut txid)
+		.map_err(|_| Error::new(ErrorKind::Other, "Failed to fetch randomness from the OS"))?;
+	query[..2].copy_from_slice(&txid);
+	Ok(u16::from_be_bytes(txid))
+}
+
+/// Checks that the given `resp`onse is a response to our `query`, i.e. that it has the QR bit set,
+/// carries the same transaction ID and echoes the same question.
+#[cfg(feature = "std")]
+fn response_matches(query: &[u8], resp: &[u8]) -> bool {
+	// Queries built by `build_query` consist of the 12-byte header, the question, and an 11-byte
+	// OPT record.
+	let question = &query[12..query.len() - 11];
+	// The name may have its case changed, but the type and class must match exactly.
+	let (name, ty_class) = question.split_at(question.len() - 4);
+	if resp.len() < 12 + question.len() || resp[2] & 0x80 == 0 { return false; }
+	let (resp_name, resp_ty_class) = resp[12..12 + question.len()].split_at(name.len());
+	resp[..2] == query[..2] && resp[4..6] == [0, 1]
+		&& resp_name.eq_ignore_ascii_case(name) && resp_ty_class == ty_class
+}
+
+/// Checks if the given response has the TC (truncation) bit set.
+#[cfg(feature = "std")]
+fn is_truncated(resp: &[u8]) -> bool {
+	resp[2] & 0b10 != 0
+}
+
+/// Makes a UDP query and returns the response, or, if the response was truncated, makes the query
+/// again over TCP (opening the `tcp_stream`, which times out after `tcp_timeout`, if we haven't
+/// yet).
+#[cfg(feature = "std")]
+fn query_udp(
+	socket: &UdpSocket, tcp_stream: &mut Option<TcpStream>, resolver: SocketAddr, tcp_timeout: Duration,
+	recv_buf: &mut [u8], mut query: QueryBuf,
+) -> Result<QueryBuf, Error> {
+	randomize_txid(&mut query)?;
+	for _ in 0..UDP_QUERY_ATTEMPTS {
+		socket.send(&query)?;
+		loop {
+			let len = match socket.recv(recv_buf) {
+				Ok(len) => len,
+				Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => break,
+				Err(e) => return Err(e),
+			};
+			let resp = &recv_buf[..len];
+			// Ignore anything which isn't a response to this query, e.g. late responses to a
+			// previous attempt or a spoofing attempt.
+			if !response_matches(&query, resp) { continue; }
+			if is_truncated(resp) {
+				query[..2].copy_from_slice(&TXID.to_be_bytes());
+				if tcp_stream.is_none() { *tcp_stream = Some(connect_tcp(resolver, tcp_timeout)?); }
+				let stream = tcp_stream.as_mut().expect("Just set it");
+				send_query(stream, &query)?;
+				return read_response(stream);
+			}
+			let mut resp_buf = QueryBuf::new_zeroed(len as u16);
+			resp_buf.copy_from_slice(resp);
+			resp_buf[..2].copy_from_slice(&TXID.to_be_bytes());
+			return Ok(resp_buf);
+		}
+	}
+	Err(Error::new(ErrorKind::TimedOut, "Resolver did not respond to UDP query"))
+}
+
+/// Makes a UDP query and returns the response, or, if the response was truncated, makes the query
+/// again over TCP (opening the `tcp_stream` if we haven't yet).
+#[cfg(feature = "tokio")]
+async fn query_udp_async(
+	socket: &TokioUdpSocket, tcp_stream: &mut Option<TokioTcpStream>, resolver: SocketAddr,
+	recv_buf: &mut [u8], mut query: QueryBuf,
+) -> Result<QueryBuf, Error> {
+	randomize_txid(&mut query)?;
+	for _ in 0..UDP_QUERY_ATTEMPTS {
+		socket.send(&query).await?;
+		loop {
+			let len = match tokio_crate::time::timeout(UDP_RETRANSMIT_TIMEOUT, socket.recv(recv_buf)).await {
+				Ok(len_res) => len_res?,
+				Err(_) => break,
+			};
+			let resp = &recv_buf[..len];
+			// Ignore anything which isn't a response to this query, e.g. late responses to a
+			// previous attempt or a spoofing attempt.
+			if !response_matches(&query, resp) { continue; }
+			if is_truncated(resp) {
+				query[..2].copy_from_slice(&TXID.to_be_bytes());
+				if tcp_stream.is_none() { *tcp_stream = Some(TokioTcpStream::connect(resolver).await?); }
+				let stream = tcp_stream.as_mut().expect("Just set it");
+				send_query_async(stream, &query).await?;
+				return read_response_async(stream).await;
+			}
+			let mut resp_buf = QueryBuf::new_zeroed(len as u16);
+			resp_buf.copy_from_slice(resp);
+			resp_buf[..2].copy_from_slice(&TXID.to_be_bytes());
+			return Ok(resp_buf);
+		}
+	}
+	Err(Error::new(ErrorKind::TimedOut, "Resolver did not respond to UDP query"))
+}
+
+/// Gets the local address to bind a UDP socket to in order to query the given `resolver`.
+#[cfg(feature = "std")]
+fn udp_bind_addr(resolver: SocketAddr) -> SocketAddr {
+	if resolver.is_ipv4() {
+		(std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+	} else {
+		(std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+	}
+}
+
+/// A transport which makes queries over UDP, falling back to TCP for truncated responses.
+///
+/// UDP queries are made one at a time, so queries are simply kept on a stack until a response is
+/// read.
+#[cfg(feature = "std")]
+struct UdpTransport<U, T> {
+	socket: U,
+	tcp_stream: Option<T>,
+	resolver: SocketAddr,
+	tcp_timeout: Duration,
+	recv_buf: Vec<u8>,
+	queries: Vec<QueryBuf>,
+}
+
+#[cfg(feature = "std")]
+impl<U, T> UdpTransport<U, T> {
+	fn new(socket: U, resolver: SocketAddr, tcp_timeout: Duration) -> Self {
+		Self {
+			socket, tcp_stream: None, resolver, tcp_timeout, recv_buf: vec![0; u16::MAX as usize],
+			queries: Vec::new(),
+		}
+	}
+
+	fn next_query(&mut self) -> Result<QueryBuf, Error> {
+		self.queries.pop().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "No queries pending"))
+	}
+}
+
+#[cfg(feature = "std")]
+impl DnsTransport for UdpTransport<UdpSocket, TcpStream> {
+	fn send_query(&mut self, query: &QueryBuf) -> Result<(), Error> {
+		self.queries.push(query.clone());
+		Ok(())
+	}
+	fn read_response(&mut self) -> Result<QueryBuf, Error> {
+		let query = self.next_query()?;
+		query_udp(&self.socket, &mut self.tcp_stream, self.resolver, self.tcp_timeout, &mut self.recv_buf, query)
+	}
+	fn set_read_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+		self.socket.set_read_timeout(Some(cmp::min(UDP_RETRANSMIT_TIMEOUT, timeout)))?;
+		if let Some(tcp_stream) = &self.tcp_stream { tcp_stream.set_read_timeout(Some(timeout))?; }
+		self.tcp_timeout = timeout;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncDnsTransport for UdpTransport<TokioUdpSocket, TokioTcpStream> {
+	fn send_query<'a>(&'a mut self, query: &'a QueryBuf) -> TransportFuture<'a, ()> {
+		self.queries.push(query.clone());
+		Box::pin(async { Ok(()) })
+	}
+	fn read_response(&mut self) -> TransportFuture<'_, QueryBuf> {
+		Box::pin(async move {
+			let query = self.next_query()?;
+			query_udp_async(&self.socket, &mut self.tcp_stream, self.resolver, &mut self.recv_buf, query).await
+		})
+	}
+}
+
+/// Opens a UDP socket to the given `resolver`, returning a transport over it which falls back to
+/// TCP for truncated responses, timing out connecting and reading over TCP after `timeout`.
+#[cfg(feature = "std")]
+fn connect_udp(resolver: SocketAddr, timeout: Duration) -> Result<UdpTransport<UdpSocket, TcpStream>, Error> {
+	let socket = UdpSocket::bind(udp_bind_addr(resolver))?;
+	socket.connect(resolver)?;
+	socket.set_read_timeout(Some(cmp::min(UDP_RETRANSMIT_TIMEOUT, timeout)))?;
+	Ok(UdpTransport::new(socket, resolver, timeout))
+}
+
+/// Opens a UDP socket to the given `resolver`, returning a transport over it which falls back to
+/// TCP for truncated responses.
+#[cfg(feature = "tokio")]
+async fn connect_udp_async(resolver: SocketAddr, timeout: Duration)
+-> Result<UdpTransport<TokioUdpSocket, TokioTcpStream>, Error> {
+	let socket = TokioUdpSocket::bind(udp_bind_addr(resolver)).await?;
+	socket.connect(resolver).await?;
+	Ok(UdpTransport::new(socket, resolver, timeout))
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof`] does, but by querying
+/// the recursive resolver over UDP rather than TCP.
+///
+/// Queries advertise an EDNS UDP payload size of 1232 bytes and use random transaction IDs, and
+/// any responses which don't match the query we sent are ignored. Queries are re-sent a few times
+/// if no response arrives, and if the resolver indicates the response was truncated the query is
+/// made again over TCP to the same `resolver`. Queries otherwise time out and are retried as
+/// [`ProofBuildingConfig::new`] describes.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_udp(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_udp(resolver, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but by querying the recursive resolver over UDP (see
+/// [`build_proof_udp`]) rather than TCP.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "std")]
+pub fn build_proof_for_types_udp(resolver: SocketAddr, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	let config = ProofBuildingConfig::new(vec![resolver]);
+	build_proof_for_questions_with_config_and_transport(&config, &type_questions(domain, types), connect_udp)
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_async`] does, but by
+/// querying the recursive resolver over UDP (see [`build_proof_udp`]) rather than TCP.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_udp_async(resolver: SocketAddr, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_udp_async(resolver, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_async`] does, but by querying the recursive resolver over UDP (see
+/// [`build_proof_udp`]) rather than TCP.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_types_udp_async(resolver: SocketAddr, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	let config = ProofBuildingConfig::new(vec![resolver]);
+	build_proof_for_questions_with_config_and_transport_async(&config, &type_questions(domain, types), connect_udp_async).await
+}
+
+#[cfg(all(feature = "validation", feature = "std", test))]
+mod tests {
+	use super::*;
+	use crate::validation::*;
+
+	use rand::seq::SliceRandom;
+
+	use std::net::ToSocketAddrs;
+	use std::time::SystemTime;
+
+	/// Builds a response to the given `query` as a validating recursive resolver serving the given
+	/// `zone` would, answering with any records (and RRSIGs over them) at the queried name and
+	/// type.
+	pub(super) fn canned_response(query: &[u8], zone: &[RR]) -> Vec<u8> {
+		let mut read = &query[12..];
+		let name = read_wire_packet_name(&mut read, query).unwrap();
+		let ty = read_u16(&mut read).unwrap();
+		read_u16(&mut read).unwrap(); // class
+		let question_len = query.len() - 12 - read.len();
+		let answers = zone.iter().filter(|rr| rr.name() == &name && match rr {
+			RR::RRSig(rrsig) => rrsig.ty == ty,
+			_ => rr.ty() == ty,
+		}).collect::<Vec<_>>();
+
+		let mut resp = query[..2].to_vec();
+		resp.extend_from_slice(&[0x81, 0xa0]); // Flags: Response, Recursive, Authenticated Data
+		resp.extend_from_slice(&[0, 1]); // One question
+		resp.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+		resp.extend_from_slice(&[0, 0, 0, 0]); // No authorities or additional records
+		resp.extend_from_slice(&query[12..12 + question_len]);
+		for rr in answers { write_rr(rr, 3600, &mut resp); }
+		resp
+	}
+
+	/// The records served by our stand-in resolvers. The signatures are bogus, but the
+	/// [`ProofBuilder`] doesn't validate them.
+	pub(super) const TEST_ZONE: &str = "\
+		example.com. 3600 IN TXT \"dnssec_prover_test\"\n\
+		example.com. 3600 IN RRSIG TXT 13 2 3600 20300101000000 20200101000000 1234 com. AAAA\n\
+		com. 3600 IN DNSKEY 257 3 13 AAAA\n\
+		com. 3600 IN RRSIG DNSKEY 13 1 3600 20300101000000 20200101000000 1234 com. AAAA\n\
+		com. 3600 IN DS 1234 13 2 0000000000000000000000000000000000000000000000000000000000000000\n\
+		com. 3600 IN RRSIG DS 8 1 3600 20300101000000 20200101000000 4321 . AAAA\n\
+		. 3600 IN DNSKEY 257 3 8 AAAA\n\
+		. 3600 IN RRSIG DNSKEY 8 0 3600 20300101000000 20200101000000 4321 . AAAA\n\
+	";
+
+	/// Checks that the given proof, built from a stand-in resolver serving [`TEST_ZONE`], contains
+	/// exactly the records in it.
+	pub(super) fn check_test_zone_proof(proof: &[u8], ttl: u32) {
+		let mut expected_rrs = crate::zonefile::parse_zone_file(TEST_ZONE).unwrap();
+		expected_rrs.sort_unstable();
+		let mut rrs = parse_rr_stream(proof).unwrap();
+		rrs.sort_unstable();
+		assert_eq!(rrs, expected_rrs);
+		assert_eq!(ttl, 3600);
+	}
+
+	/// A [`DnsTransport`] and [`AsyncDnsTransport`] for unit tests, which answers queries from the
+	/// given zone (as [`canned_response`] does) and records the queries it has been sent.
+	pub(super) struct MockTransport {
+		zone: Vec<RR>,
+		pending: Vec<QueryBuf>,
+		pub(super) queries: Vec<(Name, u16)>,
+	}
+
+	impl MockTransport {
+		pub(super) fn new(zone: &str) -> Self {
+			Self::from_records(crate::zonefile::parse_zone_file(zone).unwrap())
+		}
+
+		pub(super) fn from_records(zone: Vec<RR>) -> Self {
+			Self { zone, pending: Vec::new(), queries: Vec::new() }
+		}
+	}
+
+	impl DnsTransport for MockTransport {
+		fn send_query(&mut self, query: &QueryBuf) -> Result<(), Error> {
+			let mut read = &query[12..];
+			let name = read_wire_packet_name(&mut read, query).unwrap();
+			self.queries.push((name, read_u16(&mut read).unwrap()));
+			self.pending.push(query.clone());
+			Ok(())
+		}
+		fn read_response(&mut self) -> Result<QueryBuf, Error> {
+			let query = self.pending.pop()
+				.ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "No queries pending"))?;
+			let resp = canned_response(&query, &self.zone);
+			let mut resp_buf = QueryBuf::new_zeroed(0);
+			resp_buf.extend_from_slice(&resp);
+			Ok(resp_buf)
+		}
+	}
+
+	impl AsyncDnsTransport for MockTransport {
+		fn send_query<'a>(&'a mut self, query: &'a QueryBuf) -> TransportFuture<'a, ()> {
+			Box::pin(async move { DnsTransport::send_query(self, query) })
+		}
+		fn read_response(&mut self) -> TransportFuture<'_, QueryBuf> {
+			Box::pin(async move { DnsTransport::read_response(self) })
+		}
+	}
+
+	#[test]
+	fn test_mock_transport() {
+		let query_name = "example.com.".try_into().unwrap();
+		let mut transport = MockTransport::new(TEST_ZONE);
+		let (proof, ttl) = build_proof_with_transport(&mut transport, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(transport.queries, vec![
+			(query_name.clone(), Txt::TYPE), ("com.".try_into().unwrap(), DnsKey::TYPE),
+			("com.".try_into().unwrap(), DS::TYPE), (".".try_into().unwrap(), DnsKey::TYPE),
+		]);
+
+		// A single proof covers all the given types (though com.'s DNSKEYs appear twice, as they're
+		// also fetched when walking up to the root)
+		let com = "com.".try_into().unwrap();
+		let mut transport = MockTransport::new(TEST_ZONE);
+		let (proof, ttl) =
+			build_proof_for_types_with_transport(&mut transport, &com, &[DS::TYPE, DnsKey::TYPE]).unwrap();
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		rrs.dedup();
+		let mut expected_rrs = crate::zonefile::parse_zone_file(TEST_ZONE).unwrap();
+		expected_rrs.retain(|rr| rr.name() != &query_name);
+		expected_rrs.sort_unstable();
+		assert_eq!(rrs, expected_rrs);
+		assert_eq!(ttl, 3600);
+
+		// Responses without any answers and unsupported types are rejected
+		let missing = "missing.example.com.".try_into().unwrap();
+		let err = build_proof_with_transport(&mut MockTransport::new(TEST_ZONE), &missing, Txt::TYPE).unwrap_err();
+		assert_eq!(err.into_inner().unwrap().downcast().unwrap(), Box::new(ProofBuildingError::InvalidResponse));
+		let err = build_proof_with_transport(&mut MockTransport::new(TEST_ZONE), &query_name, RRSig::TYPE).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	}
+
+	#[test]
+	fn test_proof_cache() {
+		let zone = crate::zonefile::parse_zone_file(TEST_ZONE).unwrap();
+		let query_name = "example.com.".try_into().unwrap();
+		let build_proof = |cache: &mut ProofCache, now| {
+			let (mut builder, initial_query) = ProofBuilder::new(&query_name, Txt::TYPE);
+			let mut queries = vec![initial_query];
+			let mut queries_made = 0;
+			while let Some(query) = queries.pop() {
+				queries_made += 1;
+				let mut resp = QueryBuf::new_zeroed(0);
+				resp.extend_from_slice(&canned_response(&query, &zone));
+				queries.extend(builder.process_response_with_cache(&resp, cache, now).unwrap());
+			}
+			let (proof, ttl) = builder.finish_proof().unwrap();
+			(proof, ttl, queries_made)
+		};
+
+		let mut cache = ProofCache::new();
+		let (proof, ttl, queries_made) = build_proof(&mut cache, 1_000);
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries_made, 4);
+
+		// Once the keys are cached, only the TXT records need to be fetched, with the proof's TTL
+		// limited by how long the cached records remain valid for.
+		let (cached_proof, ttl, queries_made) = build_proof(&mut cache, 1_100);
+		assert_eq!(queries_made, 1);
+		assert_eq!(ttl, 3500);
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		let mut cached_rrs = parse_rr_stream(&cached_proof).unwrap();
+		cached_rrs.sort_unstable();
+		assert_eq!(rrs, cached_rrs);
+
+		// Expired records are not used
+		let (proof, ttl, queries_made) = build_proof(&mut cache, 1_000 + 3600);
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries_made, 4);
+
+		cache.clear();
+		assert_eq!(build_proof(&mut cache, 5_000).2, 4);
+	}
+
+	#[test]
+	fn test_multi_question_proof() {
+		let zone = format!("{TEST_ZONE}\
+			example.com. 3600 IN A 192.0.2.1\n\
+			example.com. 3600 IN RRSIG A 13 2 3600 20300101000000 20200101000000 1234 com. AAAA\n\
+			_443._tcp.example.com. 3600 IN TLSA 3 1 1 0000000000000000000000000000000000000000000000000000000000000000\n\
+			_443._tcp.example.com. 3600 IN RRSIG TLSA 13 4 3600 20300101000000 20200101000000 1234 com. AAAA\n\
+		");
+		let example_com: Name = "example.com.".try_into().unwrap();
+		let questions = [
+			(example_com.clone(), Txt::TYPE),
+			("_443._tcp.example.com.".try_into().unwrap(), TLSA::TYPE),
+			(example_com.clone(), A::TYPE),
+			// Asking the same question twice doesn't duplicate any records
+			(example_com, Txt::TYPE),
+		];
+		let mut transport = MockTransport::new(&zone);
+		let (proof, ttl) = build_proof_for_questions_with_transport(&mut transport, &questions).unwrap();
+		// One query per question, then each of the keys up to the root only once
+		assert_eq!(transport.queries.len(), questions.len() + 3);
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		let mut expected_rrs = crate::zonefile::parse_zone_file(&zone).unwrap();
+		expected_rrs.sort_unstable();
+		assert_eq!(rrs, expected_rrs);
+		assert_eq!(ttl, 3600);
+
+		let err = build_proof_for_questions_with_transport(&mut MockTransport::new(&zone), &[]).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+		// A builder with no questions is immediately done, with an empty proof
+		let (builder, queries) = ProofBuilder::new_for_questions(&[]);
+		assert!(queries.is_empty());
+		assert!(!builder.awaiting_responses());
+		assert!(builder.finish_proof().unwrap().0.is_empty());
+	}
+
+	#[test]
+	fn test_multi_question_proof_validates() {
+		use crate::validation::tests::*;
+
+		// Build a single proof for TXT records under both mattcorallo.com. and bitcoin.ninja. from
+		// a stand-in resolver serving all the (real) records, checking that it validates as-is.
+		let (txt_a, txt_a_rrsig) = mattcorallo_txt_record();
+		let (txt_b, txt_b_rrsig) = bitcoin_ninja_txt_record();
+		let questions = [(txt_a.name.clone(), Txt::TYPE), (txt_b.name.clone(), Txt::TYPE)];
+		let mut zone = Vec::new();
+		zone.extend(root_dnskey().1);
+		zone.extend(com_dnskey().1);
+		zone.extend(mattcorallo_dnskey().1);
+		zone.extend(ninja_dnskey().1);
+		zone.extend(bitcoin_ninja_dnskey().1);
+		zone.extend([txt_a.into(), txt_a_rrsig.into(), txt_b.into(), txt_b_rrsig.into()]);
+
+		let mut transport = MockTransport::from_records(zone.clone());
+		let (proof, _) = build_proof_for_questions_with_transport(&mut transport, &questions).unwrap();
+		// The root DNSKEYs are only fetched once
+		assert_eq!(transport.queries.len(), 11);
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		zone.sort_unstable();
+		assert_eq!(rrs, zone);
+
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 2);
+		assert!(verified_rrs.verified_rrs.iter().all(|rr| matches!(rr, RR::Txt(_))));
+	}
+
+	/// Runs a stand-in recursive resolver over TCP, handling one connection at a time and
+	/// responding to each query with whatever `respond` returns (if anything). Each query received
+	/// is reported over the returned channel.
+	fn start_tcp_resolver<F: FnMut(&[u8]) -> Option<Vec<u8>> + Send + 'static>(mut respond: F)
+	-> (SocketAddr, std::sync::mpsc::Receiver<(Name, u16)>) {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		let (query_sender, query_receiver) = std::sync::mpsc::channel();
+		std::thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = stream.unwrap();
+				while let Ok(query) = read_response(&mut stream) {
+					let mut read = &query[12..];
+					let name = read_wire_packet_name(&mut read, &query).unwrap();
+					let _ = query_sender.send((name, read_u16(&mut read).unwrap()));
+					if let Some(resp) = respond(&query) {
+						if send_query(&mut stream, &resp).is_err() { break; }
+					}
+				}
+			}
+		});
+		(addr, query_receiver)
+	}
+
+	fn test_zone_responder() -> impl FnMut(&[u8]) -> Option<Vec<u8>> + Send + 'static {
+		let zone = crate::zonefile::parse_zone_file(TEST_ZONE).unwrap();
+		move |query| Some(canned_response(query, &zone))
+	}
+
+	/// Gets an address which refuses TCP connections.
+	fn refusing_resolver() -> SocketAddr {
+		std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+	}
+
+	/// Runs a stand-in recursive resolver over UDP on the given address which responds to every
+	/// query with an empty, truncated response, forcing clients to retry over TCP.
+	fn start_truncating_udp_resolver(addr: SocketAddr) {
+		let socket = UdpSocket::bind(addr).unwrap();
+		std::thread::spawn(move || {
+			let mut buf = [0; 512];
+			while let Ok((len, client)) = socket.recv_from(&mut buf) {
+				let mut truncated = buf[..len].to_vec();
+				truncated[2] |= 0x82; // QR and TC bits
+				let _ = socket.send_to(&truncated, client);
+			}
+		});
+	}
+
+	#[test]
+	fn test_query_failover() {
+		let query_name = "example.com.".try_into().unwrap();
+		let (hung_resolver, _) = start_tcp_resolver(|_| None);
+		let (resolver, queries) = start_tcp_resolver(test_zone_responder());
+
+		let mut config = ProofBuildingConfig::new(vec![hung_resolver, refusing_resolver(), resolver]);
+		config.query_timeout = Duration::from_millis(100);
+		let (proof, ttl) = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries.try_iter().count(), 4);
+
+		// With only one retry, we never get to the working resolver
+		config.query_retries = 1;
+		assert!(build_proof_with_config(&config, &query_name, Txt::TYPE).is_err());
+		assert_eq!(queries.try_iter().count(), 0);
+
+		config.resolvers = Vec::new();
+		let err = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	}
+
+	#[test]
+	fn test_connection_failure_accounting() {
+		let query_name = "example.com.".try_into().unwrap();
+		let config = ProofBuildingConfig::new(vec!["127.0.0.1:1".parse().unwrap()]);
+		let mut queries = RetryingQueries::new(&config).unwrap();
+		queries.start(vec![build_query(&query_name, Txt::TYPE), build_query(&query_name, A::TYPE)]);
+		let failures = |queries: &RetryingQueries| queries.pending.iter().map(|q| q.failures).collect::<Vec<_>>();
+
+		// Only queries in flight on the failed connection are charged with the failure...
+		queries.pending[0].sent = true;
+		queries.connection_failed(Error::new(ErrorKind::ConnectionReset, "Reset")).unwrap();
+		assert_eq!(failures(&queries), vec![1, 0]);
+		assert!(queries.pending.iter().all(|query| !query.sent));
+
+		// ...unless we failed before sending anything, which blocked all of them.
+		queries.connection_failed(Error::new(ErrorKind::ConnectionRefused, "Refused")).unwrap();
+		assert_eq!(failures(&queries), vec![2, 1]);
+	}
+
+	#[test]
+	fn test_reads_time_out_at_deadline() {
+		// Each read is bounded by the time left before the overall deadline, not just the
+		// per-query timeout the connection was made with.
+		let query_name = "example.com.".try_into().unwrap();
+		let mut respond = test_zone_responder();
+		let (resolver, _) = start_tcp_resolver(move |query| {
+			std::thread::sleep(Duration::from_secs(1));
+			respond(query)
+		});
+		let mut config = ProofBuildingConfig::new(vec![resolver]);
+		config.query_timeout = Duration::from_secs(10);
+		config.overall_timeout = Duration::from_millis(1200);
+		let start = Instant::now();
+		let err = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::TimedOut);
+		assert!(start.elapsed() < Duration::from_millis(1800));
+	}
+
+	#[test]
+	fn test_transport_factory_failover() {
+		let query_name: Name = "example.com.".try_into().unwrap();
+		let questions = [(query_name, Txt::TYPE)];
+		let failing_resolver = "127.0.0.1:1".parse().unwrap();
+		let resolver = "127.0.0.1:2".parse().unwrap();
+		let config = ProofBuildingConfig::new(vec![failing_resolver, resolver]);
+
+		let mut connections = Vec::new();
+		let (proof, ttl) = build_proof_for_questions_with_config_and_transport(&config, &questions, |addr, timeout| {
+			connections.push(addr);
+			assert_eq!(timeout, config.query_timeout);
+			if addr == failing_resolver {
+				Err(Error::new(ErrorKind::ConnectionRefused, "Refused"))
+			} else {
+				Ok(MockTransport::new(TEST_ZONE))
+			}
+		}).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(connections, vec![failing_resolver, resolver]);
+
+		let res = build_proof_for_questions_with_config_and_transport(&config, &questions,
+			|_, _| Err::<MockTransport, _>(Error::new(ErrorKind::ConnectionRefused, "Refused")));
+		assert_eq!(res.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_transport_factory_async() {
+		let query_name: Name = "example.com.".try_into().unwrap();
+		let config = ProofBuildingConfig::new(vec!["127.0.0.1:1".parse().unwrap()]);
+		let (proof, ttl) = build_proof_for_questions_with_config_and_transport_async(&config,
+			&[(query_name, Txt::TYPE)], |_, _| async { Ok(MockTransport::new(TEST_ZONE)) }).await.unwrap();
+		check_test_zone_proof(&proof, ttl);
+	}
+
+	#[test]
+	fn test_config_cache() {
+		let (resolver, queries) = start_tcp_resolver(test_zone_responder());
+		let mut config = ProofBuildingConfig::new(vec![resolver]);
+		config.cache = Some(Arc::new(Mutex::new(ProofCache::new())));
+
+		let query_name = "example.com.".try_into().unwrap();
+		let (proof, ttl) = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries.try_iter().count(), 4);
+
+		// The second proof reuses the keys fetched for the first
+		let (proof, _) = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap();
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		let mut expected_rrs = crate::zonefile::parse_zone_file(TEST_ZONE).unwrap();
+		expected_rrs.sort_unstable();
+		assert_eq!(rrs, expected_rrs);
+		assert_eq!(queries.try_iter().collect::<Vec<_>>(), vec![(query_name, Txt::TYPE)]);
+	}
+
+	#[test]
+	fn test_server_failure_retries() {
+		let query_name = "example.com.".try_into().unwrap();
+		let mut responder = test_zone_responder();
+		let mut server_failures = 1;
+		let (resolver, queries) = start_tcp_resolver(move |query| {
+			let mut resp = responder(query)?;
+			if query[query.len() - 15..query.len() - 13] == DS::TYPE.to_be_bytes() && server_failures > 0 {
+				server_failures -= 1;
+				resp[3] = (resp[3] & 0xf0) | 2; // SERVFAIL
+			}
+			Some(resp)
+		});
+
+		// Only the DS query which failed is retried, the proof isn't restarted
+		let config = ProofBuildingConfig::new(vec![resolver]);
+		let (proof, ttl) = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		let queries = queries.try_iter().collect::<Vec<_>>();
+		assert_eq!(queries.iter().filter(|(_, ty)| *ty == Txt::TYPE).count(), 1);
+		assert_eq!(queries.iter().filter(|(_, ty)| *ty == DS::TYPE).count(), 2);
+		assert_eq!(queries.len(), 5);
+
+		// A resolver which always fails is eventually given up on
+		let (resolver, queries) = start_tcp_resolver(|query| {
+			let mut resp = query.to_vec();
+			resp[2..4].copy_from_slice(&[0x81, 0x82]); // Response, SERVFAIL
+			Some(resp)
+		});
+		let err = build_proof_with_config(&ProofBuildingConfig::new(vec![resolver]), &query_name, Txt::TYPE).unwrap_err();
+		assert_eq!(err.into_inner().unwrap().downcast().unwrap(), Box::new(ProofBuildingError::ServerFailure));
+		assert_eq!(queries.try_iter().count(), 3);
+	}
+
+	#[test]
+	fn test_overall_timeout() {
+		let query_name = "example.com.".try_into().unwrap();
+		let (hung_resolver, _) = start_tcp_resolver(|_| None);
+		let mut config = ProofBuildingConfig::new(vec![hung_resolver]);
+		config.overall_timeout = Duration::from_millis(200);
+		config.query_retries = usize::MAX;
+
+		let start = Instant::now();
+		let err = build_proof_with_config(&config, &query_name, Txt::TYPE).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::TimedOut);
+		assert!(start.elapsed() < Duration::from_secs(2));
+	}
+
+	#[test]
+	fn test_cloudflare_txt_query() {
+		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "cloudflare.com.".try_into().unwrap();
+		let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert!(verified_rrs.verified_rrs.len() > 1);
+
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+		assert!(verified_rrs.valid_from < now);
+		assert!(verified_rrs.expires > now);
+	}
+
+	#[test]
+	fn test_sha1_query() {
+		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "benthecarman.com.".try_into().unwrap();
+		let (proof, _) = build_a_proof(sockaddr, &query_name).unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert!(!verified_rrs.verified_rrs.is_empty());
+
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+		assert!(verified_rrs.valid_from < now);
+		assert!(verified_rrs.expires > now);
+	}
+
+	#[test]
+	fn test_txt_query() {
+		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap();
+		let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 1);
+
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+		assert!(verified_rrs.valid_from < now);
+		assert!(verified_rrs.expires > now);
+	}
+
+	#[test]
+	fn test_cname_query() {
+		for resolver in ["1.1.1.1:53", "8.8.8.8:53", "9.9.9.9:53"] {
+			let sockaddr = resolver.to_socket_addrs().unwrap().next().unwrap();
+			let query_name = "cname_test.dnssec_proof_tests.bitcoin.ninja.".try_into().unwrap();
+			let (proof, _) = build_txt_proof(sockaddr, &query_name).unwrap();
+
+			let mut rrs = parse_rr_stream(&proof).unwrap();
+			rrs.shuffle(&mut rand::rngs::OsRng);
+			let verified_rrs = verify_rr_stream(&rrs).unwrap();
+			assert_eq!(verified_rrs.verified_rrs.len(), 2);
+
+			let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+			assert!(verified_rrs.valid_from < now);
 			assert!(verified_rrs.expires > now);
 
 			let resolved_rrs = verified_rrs.resolve_name(&query_name);
@@ -621,9 +2258,179 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn test_multi_type_query() {
+		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "cloudflare.com.".try_into().unwrap();
+		let (proof, _) = build_proof_for_types(sockaddr, &query_name, &[A::TYPE, AAAA::TYPE]).unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		let rr_count = rrs.len();
+		rrs.sort_unstable();
+		rrs.dedup();
+		assert_eq!(rrs.len(), rr_count);
+
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		let resolved_rrs = verified_rrs.resolve_name(&query_name);
+		assert!(resolved_rrs.iter().any(|rr| matches!(rr, RR::A(_))));
+		assert!(resolved_rrs.iter().any(|rr| matches!(rr, RR::AAAA(_))));
+	}
+
+	#[test]
+	fn test_unsupported_types() {
+		// Unsupported types are rejected before we ever try to connect to the resolver.
+		let sockaddr = "127.0.0.1:1".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "example.com.".try_into().unwrap();
+		for types in [&[][..], &[RRSig::TYPE], &[A::TYPE, 13]] {
+			let err = build_proof_for_types(sockaddr, &query_name, types).unwrap_err();
+			assert_eq!(err.kind(), ErrorKind::InvalidInput);
+		}
+		let err = build_proof(sockaddr, &query_name, 13).unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidInput);
+	}
+
+	#[test]
+	fn test_udp_query() {
+		let sockaddr = "8.8.8.8:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap();
+		let (proof, _) = build_proof_udp(sockaddr, &query_name, Txt::TYPE).unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 1);
+	}
+
+	#[test]
+	fn test_udp_query_truncated() {
+		// Check that we fall back to TCP for truncated responses against a local stand-in resolver
+		let query_name = "example.com.".try_into().unwrap();
+		let (resolver, queries) = start_tcp_resolver(test_zone_responder());
+		start_truncating_udp_resolver(resolver);
+		let (proof, ttl) = build_proof_udp(resolver, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries.try_iter().count(), 4);
+	}
+
+	#[test]
+	fn test_response_matches() {
+		let query_name = "example.com.".try_into().unwrap();
+		// Type 0x41 is an ASCII 'A', so would match 0x61 ('a') if compared ignoring case.
+		let query = build_query(&query_name, 0x41);
+		let question_end = query.len() - 11;
+		let mut resp = query[..question_end].to_vec();
+		resp[2] |= 0x80; // QR bit
+		assert!(response_matches(&query, &resp));
+
+		let mut upper_name = resp.clone();
+		upper_name[13..20].make_ascii_uppercase();
+		assert!(response_matches(&query, &upper_name));
+
+		let mut lower_ty = resp.clone();
+		lower_ty[question_end - 3] = 0x61;
+		assert!(!response_matches(&query, &lower_ty));
+
+		let mut no_qr = resp.clone();
+		no_qr[2] &= !0x80;
+		assert!(!response_matches(&query, &no_qr));
+		assert!(!response_matches(&query, &query));
+	}
+
+	#[test]
+	fn test_udp_response_handling() {
+		// Use a local stand-in resolver to check that we ignore responses that don't match our
+		// query and fall back to TCP when the response is truncated.
+		let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+		let server_addr = server.local_addr().unwrap();
+		let tcp_listener = std::net::TcpListener::bind(server_addr).unwrap();
+
+		let server_thread = std::thread::spawn(move || {
+			let mut buf = [0; 512];
+			let (len, client) = server.recv_from(&mut buf).unwrap();
+			let mut resp = buf[..len].to_vec();
+			resp[2] |= 0x80; // QR bit
+
+			// Our own query echoed back isn't a response
+			server.send_to(&buf[..len], client).unwrap();
+			let mut bad_txid = resp.clone();
+			bad_txid[1] ^= 1;
+			server.send_to(&bad_txid, client).unwrap();
+			let mut bad_question = resp.clone();
+			bad_question[len - 12] ^= 1; // The low byte of the question class
+			server.send_to(&bad_question, client).unwrap();
+			// The question is matched ignoring case
+			let mut upper_question = resp.clone();
+			upper_question[12..len - 15].make_ascii_uppercase();
+			server.send_to(&upper_question, client).unwrap();
+
+			let (len, client) = server.recv_from(&mut buf).unwrap();
+			let mut truncated = buf[..len].to_vec();
+			truncated[2] |= 0x82; // QR and TC bits
+			server.send_to(&truncated, client).unwrap();
+
+			let (mut tcp_stream, _) = tcp_listener.accept().unwrap();
+			let tcp_query = read_response(&mut tcp_stream).unwrap();
+			assert_eq!(tcp_query[..2], TXID.to_be_bytes());
+			assert_eq!(tcp_query[2..], buf[2..len]);
+			send_query(&mut tcp_stream, b"tcp response").unwrap();
+		});
+
+		let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+		socket.connect(server_addr).unwrap();
+		socket.set_read_timeout(Some(UDP_RETRANSMIT_TIMEOUT)).unwrap();
+		let mut tcp_stream = None;
+		let mut recv_buf = [0; 512];
+		let query_name = "example.com.".try_into().unwrap();
+
+		let query = build_query(&query_name, Txt::TYPE);
+		let resp = query_udp(&socket, &mut tcp_stream, server_addr, Duration::from_secs(5), &mut recv_buf, query.clone()).unwrap();
+		assert_eq!(resp[..2], TXID.to_be_bytes());
+		assert_eq!(resp[2], query[2] | 0x80);
+		assert_eq!(resp[3..13], query[3..13]);
+		assert_eq!(resp[13..20], *b"EXAMPLE");
+		assert!(resp[20..].eq_ignore_ascii_case(&query[20..]));
+		assert!(tcp_stream.is_none());
+
+		let resp = query_udp(&socket, &mut tcp_stream, server_addr, Duration::from_secs(5), &mut recv_buf, query).unwrap();
+		assert_eq!(&resp[..], b"tcp response");
+		assert!(tcp_stream.is_some());
+
+		server_thread.join().unwrap();
+	}
+
 	#[cfg(feature = "tokio")]
 	use tokio_crate as tokio;
 
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_mock_transport_async() {
+		let query_name = "example.com.".try_into().unwrap();
+		let mut transport = MockTransport::new(TEST_ZONE);
+		let (proof, ttl) = build_proof_with_transport_async(&mut transport, &query_name, Txt::TYPE).await.unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(transport.queries.len(), 4);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_query_failover_async() {
+		let query_name = "example.com.".try_into().unwrap();
+		let (hung_resolver, _) = start_tcp_resolver(|_| None);
+		let (resolver, queries) = start_tcp_resolver(test_zone_responder());
+
+		let mut config = ProofBuildingConfig::new(vec![hung_resolver, refusing_resolver(), resolver]);
+		config.query_timeout = Duration::from_millis(100);
+		let (proof, ttl) = build_proof_with_config_async(&config, &query_name, Txt::TYPE).await.unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries.try_iter().count(), 4);
+
+		config.resolvers = vec![hung_resolver];
+		config.overall_timeout = Duration::from_millis(200);
+		let err = build_proof_with_config_async(&config, &query_name, Txt::TYPE).await.unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::TimedOut);
+	}
+
 	#[cfg(feature = "tokio")]
 	#[tokio::test]
 	async fn test_txt_query_async() {
@@ -720,6 +2527,30 @@ mod tests {
 		}
 	}
 
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_udp_query_truncated_async() {
+		let query_name = "example.com.".try_into().unwrap();
+		let (resolver, queries) = start_tcp_resolver(test_zone_responder());
+		start_truncating_udp_resolver(resolver);
+		let (proof, ttl) = build_proof_udp_async(resolver, &query_name, Txt::TYPE).await.unwrap();
+		check_test_zone_proof(&proof, ttl);
+		assert_eq!(queries.try_iter().count(), 4);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_udp_query_async() {
+		let sockaddr = "1.1.1.1:53".to_socket_addrs().unwrap().next().unwrap();
+		let query_name = "cname_test.dnssec_proof_tests.bitcoin.ninja.".try_into().unwrap();
+		let (proof, _) = build_proof_udp_async(sockaddr, &query_name, Txt::TYPE).await.unwrap();
+
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.shuffle(&mut rand::rngs::OsRng);
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 2);
+	}
+
 	#[cfg(feature = "tokio")]
 	#[tokio::test]
 	async fn test_no_dnssec() {
@@ -0,0 +1,73 @@
+//! A cache of the DNSKEY and DS records fetched while building proofs, allowing them to be reused
+//! across [`ProofBuilder`]s.
+
+use super::*;
+
+/// The maximum number of zones' DNSKEY or DS records we'll keep in a [`ProofCache`].
+const MAX_CACHE_ENTRIES: usize = 1024;
+
+/// The records (and RRSIGs over them) we got in response to a DNSKEY or DS query.
+#[derive(Clone, Debug)]
+struct CacheEntry {
+	name: Name,
+	ty: u16,
+	records: Vec<RR>,
+	/// The signers of any RRSIGs in `records`, whose keys are needed to validate them.
+	rrsig_key_names: Vec<Name>,
+	/// The time (as a UNIX timestamp, in seconds) after which the records must not be used.
+	expiry: u64,
+}
+
+/// A cache of the DNSKEY and DS records (and the RRSIGs over them) fetched while building proofs,
+/// which can be shared across [`ProofBuilder`]s.
+///
+/// Passing a cache to [`ProofBuilder::process_response_with_cache`] allows a builder to skip
+/// querying for the keys of any zones whose keys were fetched recently (e.g. while building a
+/// proof for a sibling name), splicing the cached records into the proof instead. Records are
+/// only used for as long as their TTL allows, with the proof's TTL reduced accordingly.
+///
+/// As we may not have access to a clock, the current time must be provided (as a UNIX timestamp,
+/// in seconds) whenever the cache is used.
+#[derive(Clone, Debug, Default)]
+pub struct ProofCache {
+	entries: Vec<CacheEntry>,
+}
+
+impl ProofCache {
+	/// Constructs a new, empty, cache.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Gets the cached records for the given `name` and `ty`pe (which is either DNSKEY or DS), as
+	/// well as the signers of any RRSIGs over them and their remaining TTL.
+	pub(super) fn get(&self, name: &Name, ty: u16, now: u64) -> Option<(&[RR], &[Name], u32)> {
+		self.entries.iter()
+			.find(|entry| entry.ty == ty && entry.name == *name && entry.expiry > now)
+			.map(|entry| {
+				let ttl = cmp::min(entry.expiry - now, u32::MAX as u64) as u32;
+				(&entry.records[..], &entry.rrsig_key_names[..], ttl)
+			})
+	}
+
+	/// Caches the records (and the signers of any RRSIGs over them) we got in response to a
+	/// DNSKEY or DS query for the given `name`, replacing any existing entry.
+	pub(super) fn insert(&mut self, name: Name, ty: u16, records: Vec<RR>, rrsig_key_names: Vec<Name>, ttl: u32, now: u64) {
+		self.entries.retain(|entry| entry.expiry > now && !(entry.ty == ty && entry.name == name));
+		if ttl == 0 { return; }
+		if self.entries.len() >= MAX_CACHE_ENTRIES {
+			// Make room by dropping whichever entry would have expired first.
+			let (idx, _) = self.entries.iter().enumerate()
+				.min_by_key(|(_, entry)| entry.expiry)
+				.expect("MAX_CACHE_ENTRIES is non-zero");
+			self.entries.swap_remove(idx);
+		}
+		let expiry = now.saturating_add(ttl as u64);
+		self.entries.push(CacheEntry { name, ty, records, rrsig_key_names, expiry });
+	}
+
+	/// Removes all entries from the cache.
+	pub fn clear(&mut self) {
+		self.entries.clear();
+	}
+}
@@ -0,0 +1,601 @@
+//! A DNS-over-HTTPS ([RFC 8484](https://www.rfc-editor.org/rfc/rfc8484.html)) driver for
+//! [`ProofBuilder`].
+//!
+//! Queries are made over a single HTTP/1.1 connection, with all the queries returned by
+//! [`ProofBuilder::process_response`] pipelined on the connection before we wait for their
+//! responses.
+
+use super::*;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use std::net::ToSocketAddrs;
+
+use rustls::pki_types::ServerName;
+
+/// The maximum size of the status line and headers of an HTTP response we'll accept.
+const MAX_HEADERS_LEN: usize = 8192;
+
+/// The maximum length of the line giving the size of a chunk in a chunked HTTP response body
+/// (including any chunk extensions, which we ignore).
+const MAX_CHUNK_SIZE_LINE_LEN: usize = 64;
+
+/// The HTTP method used to make DNS-over-HTTPS queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DohMethod {
+	/// Queries are made as `GET` requests with the query base64url-encoded in the `dns` URI
+	/// parameter. This allows responses to be cached by HTTP caches.
+	Get,
+	/// Queries are made as `POST` requests with the query as the request body.
+	Post,
+}
+
+/// A parsed DoH endpoint URI.
+#[derive(Clone, Copy)]
+struct Endpoint<'a> {
+	tls: bool,
+	/// The host, including the port if one was given, as used in the `Host` header.
+	authority: &'a str,
+	/// The host, without the port or any brackets around an IPv6 address.
+	host: &'a str,
+	port: u16,
+	path: &'a str,
+}
+
+/// Strips the scheme from the given endpoint URI, returning whether TLS should be used.
+///
+/// Only `https://` URIs are accepted, except in tests, where our local stand-in server speaks
+/// plain `http://`.
+fn strip_scheme(uri: &str) -> Option<(bool, &str)> {
+	if let Some(rest) = uri.strip_prefix("https://") { return Some((true, rest)); }
+	#[cfg(test)]
+	if let Some(rest) = uri.strip_prefix("http://") { return Some((false, rest)); }
+	None
+}
+
+impl<'a> Endpoint<'a> {
+	fn parse(uri: &'a str) -> Result<Self, Error> {
+		let invalid = || Error::new(ErrorKind::InvalidInput, "Invalid DoH endpoint URI");
+		let (tls, rest) = strip_scheme(uri).ok_or_else(invalid)?;
+		let (authority, path) = match rest.find('/') {
+			Some(idx) => rest.split_at(idx),
+			None => (rest, "/"),
+		};
+		let default_port = if tls { 443 } else { 80 };
+		let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+			let (host, port) = bracketed.split_once(']').ok_or_else(invalid)?;
+			match port.strip_prefix(':') {
+				Some(port) => (host, port.parse().map_err(|_| invalid())?),
+				None if port.is_empty() => (host, default_port),
+				None => return Err(invalid()),
+			}
+		} else {
+			match authority.split_once(':') {
+				Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+				None => (authority, default_port),
+			}
+		};
+		if host.is_empty() || path.contains('#') { return Err(invalid()); }
+		Ok(Endpoint { tls, authority, host, port, path })
+	}
+
+	fn server_name(&self) -> Result<ServerName<'static>, Error> {
+		ServerName::try_from(String::from(self.host))
+			.map_err(|_| Error::new(ErrorKind::InvalidInput, "Invalid DoH endpoint host"))
+	}
+}
+
+/// Encodes the given bytes as unpadded base64url, as required for `GET` requests.
+fn base64url(data: &[u8]) -> String {
+	crate::base64::encode(data).chars().filter_map(|c| match c {
+		'+' => Some('-'),
+		'/' => Some('_'),
+		'=' => None,
+		c => Some(c),
+	}).collect()
+}
+
+fn write_request(out: &mut Vec<u8>, endpoint: &Endpoint, method: DohMethod, query: &[u8]) {
+	match method {
+		DohMethod::Get => {
+			let sep = if endpoint.path.contains('?') { '&' } else { '?' };
+			out.extend_from_slice(format!(
+				"GET {}{}dns={} HTTP/1.1\r\nHost: {}\r\nAccept: application/dns-message\r\n\r\n",
+				endpoint.path, sep, base64url(query), endpoint.authority,
+			).as_bytes());
+		},
+		DohMethod::Post => {
+			out.extend_from_slice(format!(
+				"POST {} HTTP/1.1\r\nHost: {}\r\nAccept: application/dns-message\r\nContent-Type: application/dns-message\r\nContent-Length: {}\r\n\r\n",
+				endpoint.path, endpoint.authority, query.len(),
+			).as_bytes());
+			out.extend_from_slice(query);
+		},
+	}
+}
+
+/// How the body of an HTTP response is delimited.
+enum BodyLength {
+	ContentLength(u16),
+	Chunked,
+}
+
+/// Parses the status line and headers of an HTTP response, returning how its body is delimited.
+fn parse_response_head(head: &[u8]) -> Result<BodyLength, Error> {
+	let invalid = |msg| Error::new(ErrorKind::InvalidData, msg);
+	let head = core::str::from_utf8(head).map_err(|_| invalid("Invalid HTTP response"))?;
+	let mut lines = head.split("\r\n");
+	let status_line = lines.next().unwrap_or("");
+	let mut status = status_line.splitn(3, ' ');
+	if !status.next().unwrap_or("").starts_with("HTTP/1.") { return Err(invalid("Invalid HTTP response")); }
+	if status.next() != Some("200") { return Err(invalid("DoH server returned an HTTP error")); }
+
+	let mut body_len = None;
+	for line in lines {
+		let (name, value) = if let Some(header) = line.split_once(':') { header } else { continue };
+		let value = value.trim();
+		if name.eq_ignore_ascii_case("Content-Length") {
+			let len = value.parse().map_err(|_| invalid("Invalid or too large HTTP response body"))?;
+			body_len = Some(BodyLength::ContentLength(len));
+		} else if name.eq_ignore_ascii_case("Transfer-Encoding") && value.eq_ignore_ascii_case("chunked") {
+			body_len = Some(BodyLength::Chunked);
+		}
+	}
+	body_len.ok_or_else(|| invalid("HTTP response body length unknown"))
+}
+
+/// Parses the size line at the start of a chunk in a chunked HTTP body.
+fn parse_chunk_size(line: &[u8]) -> Result<usize, Error> {
+	let invalid = || Error::new(ErrorKind::InvalidData, "Invalid HTTP response chunk");
+	let line = core::str::from_utf8(line).map_err(|_| invalid())?;
+	let size = line.trim_end().split(';').next().unwrap_or("");
+	usize::from_str_radix(size, 16).map_err(|_| invalid())
+}
+
+/// The maximum size of an HTTP response we'll accept, including its headers, chunk framing and
+/// any trailers. This bounds how much a server can make us buffer with many tiny chunks.
+const MAX_RESPONSE_LEN: usize = MAX_HEADERS_LEN * 2 + u16::MAX as usize * 2;
+
+/// Gets the line (including its terminating `\n`) starting at `*pos` in `buf`, advancing `*pos`
+/// past it, or `None` if `buf` does not yet contain the full line.
+fn next_line<'a>(buf: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+	let len = buf[*pos..].iter().position(|b| *b == b'\n')? + 1;
+	*pos += len;
+	Some(&buf[*pos - len..*pos])
+}
+
+/// Parses the HTTP response at the start of `buf`, returning its body and the number of bytes of
+/// `buf` it took up, or `None` if `buf` does not yet hold the full response.
+fn parse_response(buf: &[u8]) -> Result<Option<(QueryBuf, usize)>, Error> {
+	let too_long = || Error::new(ErrorKind::InvalidData, "HTTP response too long");
+	let invalid_chunk = || Error::new(ErrorKind::InvalidData, "Invalid HTTP response chunk");
+	let mut pos = 0;
+	loop {
+		let line = match next_line(buf, &mut pos) {
+			Some(line) => line,
+			None if buf.len() > MAX_HEADERS_LEN => return Err(too_long()),
+			None => return Ok(None),
+		};
+		if pos > MAX_HEADERS_LEN { return Err(too_long()); }
+		if line == b"\r\n" { break; }
+	}
+	match parse_response_head(&buf[..pos.saturating_sub(4)])? {
+		BodyLength::ContentLength(len) => {
+			let body = match buf.get(pos..pos + len as usize) {
+				Some(body) => body,
+				None => return Ok(None),
+			};
+			let mut resp = QueryBuf::new_zeroed(0);
+			resp.extend_from_slice(body);
+			Ok(Some((resp, pos + len as usize)))
+		},
+		BodyLength::Chunked => {
+			let mut resp = QueryBuf::new_zeroed(0);
+			loop {
+				let line = match next_line(buf, &mut pos) {
+					Some(line) => line,
+					None if buf.len() - pos >= MAX_CHUNK_SIZE_LINE_LEN => return Err(invalid_chunk()),
+					None => return Ok(None),
+				};
+				if line.len() > MAX_CHUNK_SIZE_LINE_LEN { return Err(invalid_chunk()); }
+				let chunk_len = parse_chunk_size(line)?;
+				if chunk_len == 0 { break; }
+				if chunk_len > u16::MAX as usize - resp.len() { return Err(too_long()); }
+				let chunk = match buf.get(pos..pos + chunk_len + 2) {
+					Some(chunk) => chunk,
+					None => return Ok(None),
+				};
+				if chunk[chunk_len..] != *b"\r\n" { return Err(invalid_chunk()); }
+				resp.extend_from_slice(&chunk[..chunk_len]);
+				pos += chunk_len + 2;
+			}
+			// Skip any trailer fields, up to the empty line which ends the body.
+			let trailers_start = pos;
+			loop {
+				let line = match next_line(buf, &mut pos) {
+					Some(line) => line,
+					None if buf.len() - trailers_start > MAX_HEADERS_LEN => return Err(too_long()),
+					None => return Ok(None),
+				};
+				if pos - trailers_start > MAX_HEADERS_LEN { return Err(too_long()); }
+				if line == b"\r\n" { break; }
+			}
+			Ok(Some((resp, pos)))
+		},
+	}
+}
+
+/// Reads the next HTTP response from `stream`, buffering any data read past its end in
+/// `read_buf`.
+fn read_response<S: Read>(stream: &mut S, read_buf: &mut Vec<u8>) -> Result<QueryBuf, Error> {
+	let mut buf = [0; 4096];
+	loop {
+		if let Some((resp, len)) = parse_response(read_buf)? {
+			read_buf.drain(..len);
+			return Ok(resp);
+		}
+		if read_buf.len() > MAX_RESPONSE_LEN {
+			return Err(Error::new(ErrorKind::InvalidData, "HTTP response too long"));
+		}
+		let read = stream.read(&mut buf)?;
+		if read == 0 {
+			return Err(Error::new(ErrorKind::UnexpectedEof, "DoH server closed the connection"));
+		}
+		read_buf.extend_from_slice(&buf[..read]);
+	}
+}
+
+/// Reads the next HTTP response from `stream` as [`read_response`] does, but asynchronously.
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+async fn read_response_async<S: AsyncRead + Unpin>(stream: &mut S, read_buf: &mut Vec<u8>) -> Result<QueryBuf, Error> {
+	let mut buf = [0; 4096];
+	loop {
+		if let Some((resp, len)) = parse_response(read_buf)? {
+			read_buf.drain(..len);
+			return Ok(resp);
+		}
+		if read_buf.len() > MAX_RESPONSE_LEN {
+			return Err(Error::new(ErrorKind::InvalidData, "HTTP response too long"));
+		}
+		let read = stream.read(&mut buf).await?;
+		if read == 0 {
+			return Err(Error::new(ErrorKind::UnexpectedEof, "DoH server closed the connection"));
+		}
+		read_buf.extend_from_slice(&buf[..read]);
+	}
+}
+
+fn tls_config() -> Arc<rustls::ClientConfig> {
+	let mut config = tls::config_builder()
+		.with_root_certificates(tls::mozilla_roots())
+		.with_no_client_auth();
+	config.alpn_protocols = vec![b"http/1.1".to_vec()];
+	Arc::new(config)
+}
+
+/// A connection to a DoH server, over which we can make queries.
+struct DohConnection<'a, S> {
+	stream: S,
+	endpoint: Endpoint<'a>,
+	method: DohMethod,
+	write_buf: Vec<u8>,
+	read_buf: Vec<u8>,
+	/// A handle to the TCP socket underlying `stream`, used to set read timeouts.
+	socket: Option<TcpStream>,
+}
+
+impl<'a, S> DohConnection<'a, S> {
+	fn new(stream: S, endpoint: Endpoint<'a>, method: DohMethod) -> Self {
+		DohConnection { stream, endpoint, method, write_buf: Vec::new(), read_buf: Vec::new(), socket: None }
+	}
+}
+
+impl<S: Read + Write> DnsTransport for DohConnection<'_, S> {
+	fn send_query(&mut self, query: &QueryBuf) -> Result<(), Error> {
+		self.write_buf.clear();
+		write_request(&mut self.write_buf, &self.endpoint, self.method, query);
+		self.stream.write_all(&self.write_buf)?;
+		self.stream.flush()
+	}
+	fn read_response(&mut self) -> Result<QueryBuf, Error> {
+		read_response(&mut self.stream, &mut self.read_buf)
+	}
+	fn set_read_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+		match &self.socket {
+			Some(socket) => socket.set_read_timeout(Some(timeout)),
+			None => Ok(()),
+		}
+	}
+}
+
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncDnsTransport for DohConnection<'_, S> {
+	fn send_query<'a>(&'a mut self, query: &'a QueryBuf) -> TransportFuture<'a, ()> {
+		Box::pin(async move {
+			self.write_buf.clear();
+			write_request(&mut self.write_buf, &self.endpoint, self.method, query);
+			self.stream.write_all(&self.write_buf).await?;
+			self.stream.flush().await
+		})
+	}
+	fn read_response(&mut self) -> TransportFuture<'_, QueryBuf> {
+		Box::pin(async move { read_response_async(&mut self.stream, &mut self.read_buf).await })
+	}
+}
+
+/// The stream underlying a [`DohConnection`], either a plain TCP stream or a TLS stream.
+trait Stream: Read + Write {}
+impl<S: Read + Write> Stream for S {}
+
+/// The async equivalent of [`Stream`].
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+trait AsyncStream: AsyncRead + AsyncWrite + Unpin + Send {}
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> AsyncStream for S {}
+
+/// Connects to the DoH server at `endpoint` on the given `addr`, timing out connecting and each
+/// write after `timeout`. Reads time out as set by [`DnsTransport::set_read_timeout`].
+fn connect<'a>(endpoint: Endpoint<'a>, method: DohMethod, addr: SocketAddr, timeout: Duration)
+-> Result<DohConnection<'a, Box<dyn Stream>>, Error> {
+	let tcp_stream = connect_tcp(addr, timeout)?;
+	let socket = tcp_stream.try_clone()?;
+	let stream: Box<dyn Stream> = if endpoint.tls {
+		let tls = rustls::ClientConnection::new(tls_config(), endpoint.server_name()?)
+			.map_err(|e| Error::new(ErrorKind::Other, e))?;
+		Box::new(rustls::StreamOwned::new(tls, tcp_stream))
+	} else {
+		Box::new(tcp_stream)
+	};
+	let mut connection = DohConnection::new(stream, endpoint, method);
+	connection.socket = Some(socket);
+	Ok(connection)
+}
+
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+async fn connect_async(endpoint: Endpoint<'_>, method: DohMethod, addr: SocketAddr)
+-> Result<DohConnection<'_, Box<dyn AsyncStream>>, Error> {
+	let tcp_stream = TokioTcpStream::connect(addr).await?;
+	let stream: Box<dyn AsyncStream> = if endpoint.tls {
+		let connector = tokio_rustls::TlsConnector::from(tls_config());
+		Box::new(connector.connect(endpoint.server_name()?, tcp_stream).await?)
+	} else {
+		Box::new(tcp_stream)
+	};
+	Ok(DohConnection::new(stream, endpoint, method))
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by querying a DNS-over-HTTPS server at
+/// the given `endpoint` URI (e.g. `https://cloudflare-dns.com/dns-query`), returning the proof as
+/// well as the TTL for the proof provided by the server.
+///
+/// The endpoint must be an `https://` URI, and the server's certificate is validated against the
+/// Mozilla root certificates.
+///
+/// All the queries needed to build the proof are made over a single HTTP/1.1 connection, with
+/// queries pipelined, so the server must support HTTP/1.1 keep-alive. Queries time out and are
+/// retried as [`ProofBuildingConfig::new`] describes, failing over between the addresses the
+/// endpoint's host resolves to.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_doh(endpoint: &str, method: DohMethod, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_doh(endpoint, method, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but by querying a DNS-over-HTTPS server (see
+/// [`build_proof_doh`]).
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_for_types_doh(endpoint: &str, method: DohMethod, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_types(types)?;
+	let endpoint = Endpoint::parse(endpoint)?;
+	let config = ProofBuildingConfig::new((endpoint.host, endpoint.port).to_socket_addrs()?.collect());
+	build_proof_for_questions_with_config_and_transport(&config, &type_questions(domain, types),
+		|addr, timeout| connect(endpoint, method, addr, timeout))
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_doh`] does, but
+/// asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+pub async fn build_proof_doh_async(endpoint: &str, method: DohMethod, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_doh_async(endpoint, method, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_doh`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+pub async fn build_proof_for_types_doh_async(endpoint: &str, method: DohMethod, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_types(types)?;
+	let endpoint = Endpoint::parse(endpoint)?;
+	let config = ProofBuildingConfig::new(tokio_crate::net::lookup_host((endpoint.host, endpoint.port)).await?.collect());
+	build_proof_for_questions_with_config_and_transport_async(&config, &type_questions(domain, types),
+		|addr, _| connect_async(endpoint, method, addr)).await
+}
+
+#[cfg(all(feature = "validation", test))]
+mod tests {
+	use super::*;
+	use crate::query::tests::{TEST_ZONE, canned_response, check_test_zone_proof};
+	use crate::zonefile::parse_zone_file;
+
+	use base64::Engine;
+
+	use std::io::{BufRead, BufReader};
+	use std::net::TcpListener;
+	use std::sync::mpsc;
+
+	#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+	use tokio_crate as tokio;
+
+	#[test]
+	fn endpoint_parsing() {
+		let endpoint = Endpoint::parse("https://dns.example/dns-query").unwrap();
+		assert!(endpoint.tls);
+		assert_eq!((endpoint.authority, endpoint.host, endpoint.port, endpoint.path),
+			("dns.example", "dns.example", 443, "/dns-query"));
+		let endpoint = Endpoint::parse("http://[::1]:8053/q?x=y").unwrap();
+		assert!(!endpoint.tls);
+		assert_eq!((endpoint.authority, endpoint.host, endpoint.port, endpoint.path),
+			("[::1]:8053", "::1", 8053, "/q?x=y"));
+		let endpoint = Endpoint::parse("https://1.1.1.1").unwrap();
+		assert_eq!((endpoint.host, endpoint.port, endpoint.path), ("1.1.1.1", 443, "/"));
+
+		for invalid in ["dns.example/dns-query", "ftp://dns.example/", "https://:443/", "https://a:b/",
+			"https://[::1/", "https://[::1]x/", "https://a/#frag"]
+		{
+			assert!(Endpoint::parse(invalid).is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn response_parsing() {
+		let mut resp = &b"HTTP/1.1 200 OK\r\ncontent-length: 3\r\n\r\nabcHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nde\r\n1;ext=1\r\nf\r\n0\r\n\r\nHTTP/1.1 404 Not Found\r\n\r\n"[..];
+		let mut read_buf = Vec::new();
+		assert_eq!(&read_response(&mut resp, &mut read_buf).unwrap()[..], b"abc");
+		assert_eq!(&read_response(&mut resp, &mut read_buf).unwrap()[..], b"def");
+		assert!(read_response(&mut resp, &mut read_buf).is_err());
+		let read = |resp: &[u8]| read_response(&mut &resp[..], &mut Vec::new());
+		assert!(read(b"HTTP/1.1 200 OK\r\n\r\n").is_err());
+		assert!(read(b"HTTP/1.1 200 OK\r\nContent-Length: 70000\r\n\r\n").is_err());
+		assert!(read(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nab").is_err());
+
+		// Trailers after the last chunk are skipped
+		let mut resp = &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\n0\r\nX-A: b\r\nX-C: d\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 1\r\n\r\nb"[..];
+		let mut read_buf = Vec::new();
+		assert_eq!(&read_response(&mut resp, &mut read_buf).unwrap()[..], b"a");
+		assert_eq!(&read_response(&mut resp, &mut read_buf).unwrap()[..], b"b");
+
+		// Partial responses are left in the buffer until the rest arrives
+		let partial = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\n";
+		assert!(parse_response(partial).unwrap().is_none());
+		let mut full = partial.to_vec();
+		full.extend_from_slice(b"0\r\n\r\n");
+		assert_eq!(&parse_response(&full).unwrap().unwrap().0[..], b"a");
+
+		// Over-long lines are rejected, without buffering them in full
+		let chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+		let mut long_chunk_size = chunked.to_vec();
+		long_chunk_size.extend_from_slice(&[b'0'; 1_000_000]);
+		assert!(read(&long_chunk_size[..]).is_err());
+		let mut long_trailer = chunked.to_vec();
+		long_trailer.extend_from_slice(b"0\r\nX-A: ");
+		long_trailer.extend_from_slice(&[b'a'; 1_000_000]);
+		assert!(read(&long_trailer[..]).is_err());
+		let mut long_header = b"HTTP/1.1 200 OK\r\nX-A: ".to_vec();
+		long_header.extend_from_slice(&[b'a'; 1_000_000]);
+		assert!(read(&long_header[..]).is_err());
+		let mut huge_chunk = chunked.to_vec();
+		huge_chunk.extend_from_slice(b"ffffffffffffffff\r\n");
+		assert!(read(&huge_chunk[..]).is_err());
+		// As are responses with excessive chunk framing, even if the body itself is short
+		let mut tiny_chunks = chunked.to_vec();
+		for _ in 0..3000 { tiny_chunks.extend_from_slice(format!("1;{}\r\na\r\n", "x".repeat(60)).as_bytes()); }
+		tiny_chunks.extend_from_slice(b"0\r\n\r\n");
+		assert!(read(&tiny_chunks[..]).is_err());
+	}
+
+	/// Runs a stand-in DoH server over plain HTTP on a single connection, serving responses from
+	/// [`TEST_ZONE`] and reporting the method and number of queries in each batch it received.
+	fn run_server(listener: TcpListener, batches: mpsc::Sender<(String, usize)>) {
+		let zone = parse_zone_file(TEST_ZONE).unwrap();
+		let (stream, _) = listener.accept().unwrap();
+		let mut stream = BufReader::new(stream);
+		let mut queries = Vec::new();
+		loop {
+			let mut request_line = String::new();
+			if stream.read_line(&mut request_line).unwrap() == 0 { return; }
+			let mut content_len = 0;
+			loop {
+				let mut header = String::new();
+				stream.read_line(&mut header).unwrap();
+				if header == "\r\n" { break; }
+				let (name, value) = header.split_once(':').unwrap();
+				if name.eq_ignore_ascii_case("Content-Length") { content_len = value.trim().parse().unwrap(); }
+			}
+			let mut parts = request_line.split(' ');
+			let method = parts.next().unwrap().to_owned();
+			let path = parts.next().unwrap();
+			let query = if method == "GET" {
+				let b64 = path.strip_prefix("/dns-query?dns=").unwrap();
+				base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(b64).unwrap()
+			} else {
+				assert_eq!(path, "/dns-query");
+				let mut body = vec![0; content_len];
+				stream.read_exact(&mut body).unwrap();
+				body
+			};
+			queries.push(query);
+			// Only respond once all pipelined queries have been read, giving the client a moment to
+			// send any further queries it has.
+			if stream.buffer().is_empty() {
+				stream.get_ref().set_read_timeout(Some(Duration::from_millis(250))).unwrap();
+				let more_pending = matches!(stream.fill_buf(), Ok(buf) if !buf.is_empty());
+				stream.get_ref().set_read_timeout(None).unwrap();
+				if more_pending { continue; }
+				batches.send((method, queries.len())).unwrap();
+				for query in queries.drain(..) {
+					let resp = canned_response(&query, &zone);
+					let head = format!("HTTP/1.1 200 OK\r\nContent-Type: application/dns-message\r\nContent-Length: {}\r\n\r\n", resp.len());
+					stream.get_mut().write_all(head.as_bytes()).unwrap();
+					stream.get_mut().write_all(&resp).unwrap();
+				}
+			}
+		}
+	}
+
+	fn start_server() -> (String, mpsc::Receiver<(String, usize)>, std::thread::JoinHandle<()>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = format!("http://{}/dns-query", listener.local_addr().unwrap());
+		let (batches_send, batches) = mpsc::channel();
+		(endpoint, batches, std::thread::spawn(move || run_server(listener, batches_send)))
+	}
+
+	#[test]
+	fn local_doh_proof() {
+		let query_name = "example.com.".try_into().unwrap();
+		for (method, method_str) in [(DohMethod::Get, "GET"), (DohMethod::Post, "POST")] {
+			let (endpoint, batches, server) = start_server();
+			let (proof, ttl) = build_proof_doh(&endpoint, method, &query_name, Txt::TYPE).unwrap();
+			check_test_zone_proof(&proof, ttl);
+			server.join().unwrap();
+
+			// After the initial query, the DNSKEY and DS queries for com. are pipelined together.
+			let batches = batches.iter().collect::<Vec<_>>();
+			assert_eq!(batches.iter().map(|(_, count)| *count).sum::<usize>(), 4);
+			assert!(batches.iter().any(|(_, count)| *count == 2));
+			assert!(batches.iter().all(|(method, _)| method == method_str));
+		}
+	}
+
+	#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+	#[tokio::test]
+	async fn local_doh_proof_async() {
+		let query_name = "example.com.".try_into().unwrap();
+		for method in [DohMethod::Get, DohMethod::Post] {
+			let (endpoint, batches, server) = start_server();
+			let (proof, ttl) = build_proof_doh_async(&endpoint, method, &query_name, Txt::TYPE).await.unwrap();
+			check_test_zone_proof(&proof, ttl);
+			server.join().unwrap();
+			assert_eq!(batches.iter().map(|(_, count)| count).sum::<usize>(), 4);
+		}
+	}
+
+	#[test]
+	fn test_cloudflare_doh() {
+		let query_name = "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap();
+		let (proof, _) = build_proof_doh("https://cloudflare-dns.com/dns-query", DohMethod::Get,
+			&query_name, Txt::TYPE).unwrap();
+		let rrs = parse_rr_stream(&proof).unwrap();
+		let verified_rrs = crate::validation::verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 1);
+	}
+}
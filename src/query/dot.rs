@@ -0,0 +1,327 @@
+//! A DNS-over-TLS ([RFC 7858](https://www.rfc-editor.org/rfc/rfc7858.html)) driver for
+//! [`ProofBuilder`].
+//!
+//! This is simply the same length-prefixed framing we use over TCP, run over a TLS stream.
+
+use super::*;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use bitcoin_hashes::{sha256, Hash};
+
+use rustls::{ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme, StreamOwned};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, SubjectPublicKeyInfoDer, UnixTime};
+
+/// How the identity of a DNS-over-TLS resolver is authenticated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DotAuthentication {
+	/// The resolver's certificate must chain to one of the Mozilla root certificates and be valid
+	/// for the given host name (e.g. `"one.one.one.one"`).
+	Name(String),
+	/// The SHA-256 hash of the DER-encoded SubjectPublicKeyInfo in the resolver's certificate must
+	/// match one of the given pins, as described in RFC 7858 section 4.2.
+	///
+	/// The certificate's issuer, names and validity period are not checked, so this can be used
+	/// with self-signed certificates.
+	SpkiPins(Vec<[u8; 32]>),
+}
+
+/// Gets the DER-encoded SubjectPublicKeyInfo from a DER-encoded X.509 certificate.
+fn cert_spki(cert: &[u8]) -> Result<SubjectPublicKeyInfoDer<'static>, ()> {
+	let cert = CertificateDer::from(cert);
+	Ok(webpki::EndEntityCert::try_from(&cert).map_err(|_| ())?.subject_public_key_info())
+}
+
+/// A [`ServerCertVerifier`] which only checks the SubjectPublicKeyInfo of the server's certificate
+/// against a set of pins.
+#[derive(Debug)]
+struct SpkiPinVerifier {
+	pins: Vec<[u8; 32]>,
+	provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for SpkiPinVerifier {
+	fn verify_server_cert(
+		&self, end_entity: &CertificateDer<'_>, _intermediates: &[CertificateDer<'_>],
+		_server_name: &ServerName<'_>, _ocsp_response: &[u8], _now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		let spki = cert_spki(end_entity)
+			.map_err(|()| rustls::Error::InvalidCertificate(rustls::CertificateError::BadEncoding))?;
+		if self.pins.contains(&sha256::Hash::hash(&spki).to_byte_array()) {
+			Ok(ServerCertVerified::assertion())
+		} else {
+			Err(rustls::Error::InvalidCertificate(rustls::CertificateError::ApplicationVerificationFailure))
+		}
+	}
+
+	fn verify_tls12_signature(
+		&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn verify_tls13_signature(
+		&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.provider.signature_verification_algorithms.supported_schemes()
+	}
+}
+
+/// Builds the TLS configuration and server name to use to connect to the given `resolver`,
+/// checking [`DotAuthentication::Name`]s against the given `roots`.
+fn tls_config(resolver: SocketAddr, auth: &DotAuthentication, roots: RootCertStore)
+-> Result<(Arc<ClientConfig>, ServerName<'static>), Error> {
+	match auth {
+		DotAuthentication::Name(name) => {
+			let server_name = ServerName::try_from(name.clone())
+				.map_err(|_| Error::new(ErrorKind::InvalidInput, "Invalid DoT resolver name"))?;
+			let config = tls::config_builder().with_root_certificates(roots).with_no_client_auth();
+			Ok((Arc::new(config), server_name))
+		},
+		DotAuthentication::SpkiPins(pins) => {
+			let verifier = SpkiPinVerifier { pins: pins.clone(), provider: tls::crypto_provider() };
+			let config = tls::config_builder().dangerous()
+				.with_custom_certificate_verifier(Arc::new(verifier))
+				.with_no_client_auth();
+			Ok((Arc::new(config), ServerName::IpAddress(resolver.ip().into())))
+		},
+	}
+}
+
+fn build_proof_with_roots(
+	resolver: SocketAddr, auth: &DotAuthentication, roots: RootCertStore, domain: &Name, types: &[u16],
+) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_types(types)?;
+	let (config, server_name) = tls_config(resolver, auth, roots)?;
+	let proof_config = ProofBuildingConfig::new(vec![resolver]);
+	build_proof_for_questions_with_config_and_transport(&proof_config, &type_questions(domain, types), |resolver, timeout| {
+		let tls = ClientConnection::new(Arc::clone(&config), server_name.clone())
+			.map_err(|e| Error::new(ErrorKind::Other, e))?;
+		let tcp_stream = connect_tcp(resolver, timeout)?;
+		let socket = tcp_stream.try_clone()?;
+		Ok(StreamTransport::new_over_tcp(StreamOwned::new(tls, tcp_stream), socket))
+	})
+}
+
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+async fn build_proof_with_roots_async(
+	resolver: SocketAddr, auth: &DotAuthentication, roots: RootCertStore, domain: &Name, types: &[u16],
+) -> Result<(Vec<u8>, u32), Error> {
+	check_provable_types(types)?;
+	let (config, server_name) = tls_config(resolver, auth, roots)?;
+	let connector = tokio_rustls::TlsConnector::from(config);
+	let proof_config = ProofBuildingConfig::new(vec![resolver]);
+	build_proof_for_questions_with_config_and_transport_async(&proof_config, &type_questions(domain, types), |resolver, _| {
+		let (connector, server_name) = (connector.clone(), server_name.clone());
+		async move {
+			let tcp_stream = TokioTcpStream::connect(resolver).await?;
+			Ok(StreamTransport::new(connector.connect(server_name, tcp_stream).await?))
+		}
+	}).await
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by querying a recursive resolver over
+/// DNS-over-TLS (usually on port 853), returning the proof as well as the TTL for the proof
+/// provided by the recursive resolver.
+///
+/// The resolver's certificate is checked as described by the given [`DotAuthentication`]. Queries
+/// time out and are retried as [`ProofBuildingConfig::new`] describes.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_dot(resolver: SocketAddr, auth: &DotAuthentication, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_dot(resolver, auth, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but by querying the recursive resolver over DNS-over-TLS (see
+/// [`build_proof_dot`]).
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_for_types_dot(resolver: SocketAddr, auth: &DotAuthentication, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_with_roots(resolver, auth, tls::mozilla_roots(), domain, types)
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_dot`] does, but
+/// asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+pub async fn build_proof_dot_async(resolver: SocketAddr, auth: &DotAuthentication, domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_dot_async(resolver, auth, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_dot`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+pub async fn build_proof_for_types_dot_async(resolver: SocketAddr, auth: &DotAuthentication, domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_with_roots_async(resolver, auth, tls::mozilla_roots(), domain, types).await
+}
+
+#[cfg(all(feature = "validation", test))]
+mod tests {
+	use super::*;
+	use crate::query::tests::{TEST_ZONE, canned_response, check_test_zone_proof};
+	use crate::zonefile::parse_zone_file;
+
+	use rustls::pki_types::PrivateKeyDer;
+
+	use hex_conservative::FromHex;
+
+	use std::net::TcpListener;
+
+	#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+	use tokio_crate as tokio;
+
+	/// A self-signed certificate for `dns.example`, valid until 2124.
+	const TEST_CERT: &str = concat!(
+		"308201983082013ea003020102021405f4c74efe386ff5be53fc23bec1d7d03b39ca28300a06082a8648ce3d04030230",
+		"163114301206035504030c0b646e732e6578616d706c653020170d3234303130313030303030305a180f323132343031",
+		"30313030303030305a30163114301206035504030c0b646e732e6578616d706c653059301306072a8648ce3d02010608",
+		"2a8648ce3d03010703420004c37276e962b68da860d301e5da1714f83b0bdeb785c94cc0c741c8fb0ad24d1dbfa9424e",
+		"47e578001e968a0fbf69f13146493a735b102db1a3f5e2f39a7f05faa3683066301d0603551d0e041604148fe7c9ad64",
+		"75b2167e395822583323da7e98b321301f0603551d230418301680148fe7c9ad6475b2167e395822583323da7e98b321",
+		"30160603551d11040f300d820b646e732e6578616d706c65300c0603551d130101ff04023000300a06082a8648ce3d04",
+		"03020348003045022063eb4e95a76fed005611f54d0dd2927a1caec8f5492cd51d85f68f1438bd9460022100daf80a82",
+		"3b947bb1cc5a27d91afa82e18083ec7ef08ad13a81a4c7d241c635eb",
+	);
+	/// The PKCS#8-encoded private key for [`TEST_CERT`].
+	const TEST_KEY: &str = concat!(
+		"308187020100301306072a8648ce3d020106082a8648ce3d030107046d306b020101042069d4a18720f91feab35078dd",
+		"783a6f5bfc194c44941bf9c32bf08601f4995d37a14403420004c37276e962b68da860d301e5da1714f83b0bdeb785c9",
+		"4cc0c741c8fb0ad24d1dbfa9424e47e578001e968a0fbf69f13146493a735b102db1a3f5e2f39a7f05fa",
+	);
+	/// The DER-encoded `SubjectPublicKeyInfo` of [`TEST_CERT`].
+	const TEST_SPKI: &str = concat!(
+		"3059301306072a8648ce3d020106082a8648ce3d03010703420004c37276e962b68da860d301e5da1714f83b0bdeb785",
+		"c94cc0c741c8fb0ad24d1dbfa9424e47e578001e968a0fbf69f13146493a735b102db1a3f5e2f39a7f05fa",
+	);
+
+	fn test_cert() -> CertificateDer<'static> {
+		Vec::from_hex(TEST_CERT).unwrap().into()
+	}
+
+	/// Runs a stand-in DoT resolver with [`TEST_CERT`] on a single connection, serving responses
+	/// from [`TEST_ZONE`].
+	fn start_server() -> (SocketAddr, std::thread::JoinHandle<()>) {
+		let key = PrivateKeyDer::Pkcs8(Vec::from_hex(TEST_KEY).unwrap().into());
+		let config = rustls::ServerConfig::builder_with_provider(tls::crypto_provider())
+			.with_safe_default_protocol_versions().unwrap()
+			.with_no_client_auth()
+			.with_single_cert(vec![test_cert()], key).unwrap();
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		let server = std::thread::spawn(move || {
+			let zone = parse_zone_file(TEST_ZONE).unwrap();
+			let (tcp_stream, _) = listener.accept().unwrap();
+			let tls = rustls::ServerConnection::new(Arc::new(config)).unwrap();
+			let mut stream = StreamOwned::new(tls, tcp_stream);
+			// Serve queries until the client goes away (or fails to complete the handshake).
+			while let Ok(query) = read_response(&mut stream) {
+				send_query(&mut stream, &canned_response(&query, &zone)).unwrap();
+			}
+		});
+		(addr, server)
+	}
+
+	fn spki_pin() -> [u8; 32] {
+		sha256::Hash::hash(&Vec::from_hex(TEST_SPKI).unwrap()).to_byte_array()
+	}
+
+	fn test_roots() -> RootCertStore {
+		let mut roots = RootCertStore::empty();
+		roots.add(test_cert()).unwrap();
+		roots
+	}
+
+	#[test]
+	fn spki_parsing() {
+		let der = test_cert();
+		assert_eq!(&cert_spki(&der).unwrap()[..], &Vec::from_hex(TEST_SPKI).unwrap()[..]);
+
+		for len in 0..der.len() {
+			assert!(cert_spki(&der[..len]).is_err());
+		}
+		assert!(cert_spki(&[0x30, 0x85, 0, 0, 0, 0, 0]).is_err());
+	}
+
+	#[test]
+	fn local_dot_proof() {
+		let query_name = "example.com.".try_into().unwrap();
+
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::SpkiPins(vec![[0; 32], spki_pin()]);
+		let (proof, ttl) = build_proof_dot(addr, &auth, &query_name, Txt::TYPE).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		server.join().unwrap();
+
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::Name("dns.example".to_owned());
+		let (proof, ttl) = build_proof_with_roots(addr, &auth, test_roots(), &query_name, &[Txt::TYPE]).unwrap();
+		check_test_zone_proof(&proof, ttl);
+		server.join().unwrap();
+	}
+
+	#[test]
+	fn local_dot_authentication_failures() {
+		let query_name = "example.com.".try_into().unwrap();
+
+		// A pin which doesn't match the server's key
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::SpkiPins(vec![[0; 32]]);
+		assert!(build_proof_dot(addr, &auth, &query_name, Txt::TYPE).is_err());
+		server.join().unwrap();
+
+		// A self-signed certificate isn't trusted when checking the name
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::Name("dns.example".to_owned());
+		assert!(build_proof_dot(addr, &auth, &query_name, Txt::TYPE).is_err());
+		server.join().unwrap();
+
+		// A trusted certificate for a different name
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::Name("other.example".to_owned());
+		assert!(build_proof_with_roots(addr, &auth, test_roots(), &query_name, &[Txt::TYPE]).is_err());
+		server.join().unwrap();
+	}
+
+	#[cfg(all(feature = "tokio", feature = "tokio-rustls"))]
+	#[tokio::test]
+	async fn local_dot_proof_async() {
+		let query_name = "example.com.".try_into().unwrap();
+
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::SpkiPins(vec![spki_pin()]);
+		let (proof, ttl) = build_proof_dot_async(addr, &auth, &query_name, Txt::TYPE).await.unwrap();
+		check_test_zone_proof(&proof, ttl);
+		server.join().unwrap();
+
+		let (addr, server) = start_server();
+		let auth = DotAuthentication::Name("other.example".to_owned());
+		assert!(build_proof_with_roots_async(addr, &auth, test_roots(), &query_name, &[Txt::TYPE]).await.is_err());
+		server.join().unwrap();
+	}
+
+	#[test]
+	fn test_cloudflare_dot() {
+		let resolver = "1.1.1.1:853".parse().unwrap();
+		let auth = DotAuthentication::Name("one.one.one.one".to_owned());
+		let query_name = "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap();
+		let (proof, _) = build_proof_dot(resolver, &auth, &query_name, Txt::TYPE).unwrap();
+		let rrs = parse_rr_stream(&proof).unwrap();
+		let verified_rrs = crate::validation::verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs.len(), 1);
+	}
+}
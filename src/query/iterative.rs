@@ -0,0 +1,1079 @@
+//! Building proofs by iteratively querying authoritative servers, starting from the root, rather
+//! than relying on a (validating) recursive resolver.
+//!
+//! We follow referrals from the root down to the zone containing the records we want, remembering
+//! the servers for each zone we find along the way so that DNSKEY and DS queries can be sent
+//! directly to the servers for the zone which holds them.
+
+use super::*;
+
+use core::mem;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// The maximum number of queries we'll make per question to build a proof iteratively.
+///
+/// This is somewhat higher than [`MAX_PROOF_STEPS`] as we also have to follow referrals and may
+/// have to look up the addresses of name servers.
+pub(super) const MAX_ITERATIVE_QUERIES: usize = MAX_PROOF_STEPS * 4;
+
+/// The maximum number of name servers we'll look up addresses for when we're referred to a zone
+/// without any glue records.
+const MAX_GLUELESS_NS_LOOKUPS: usize = 2;
+
+/// The types of the records we look up for each name server without glue records.
+const NAME_SERVER_ADDRESS_TYPES: [u16; 2] = [A::TYPE, AAAA::TYPE];
+
+/// The IPv4 and IPv6 addresses of the 13 DNS root servers (`a.root-servers.net` through
+/// `m.root-servers.net`), as published in IANA's root hints file.
+///
+/// The IPv4 addresses are listed first, as they're more likely to be reachable.
+pub const ROOT_HINTS: [IpAddr; 26] = [
+	IpAddr::V4(Ipv4Addr::new(198, 41, 0, 4)),
+	IpAddr::V4(Ipv4Addr::new(170, 247, 170, 2)),
+	IpAddr::V4(Ipv4Addr::new(192, 33, 4, 12)),
+	IpAddr::V4(Ipv4Addr::new(199, 7, 91, 13)),
+	IpAddr::V4(Ipv4Addr::new(192, 203, 230, 10)),
+	IpAddr::V4(Ipv4Addr::new(192, 5, 5, 241)),
+	IpAddr::V4(Ipv4Addr::new(192, 112, 36, 4)),
+	IpAddr::V4(Ipv4Addr::new(198, 97, 190, 53)),
+	IpAddr::V4(Ipv4Addr::new(192, 36, 148, 17)),
+	IpAddr::V4(Ipv4Addr::new(192, 58, 128, 30)),
+	IpAddr::V4(Ipv4Addr::new(193, 0, 14, 129)),
+	IpAddr::V4(Ipv4Addr::new(199, 7, 83, 42)),
+	IpAddr::V4(Ipv4Addr::new(202, 12, 27, 33)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x503, 0xba3e, 0, 0, 0, 0x2, 0x30)),
+	IpAddr::V6(Ipv6Addr::new(0x2801, 0x1b8, 0x10, 0, 0, 0, 0, 0xb)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x2, 0, 0, 0, 0, 0xc)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x2d, 0, 0, 0, 0, 0xd)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0xa8, 0, 0, 0, 0, 0xe)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x2f, 0, 0, 0, 0, 0xf)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x12, 0, 0, 0, 0, 0xd0d)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x1, 0, 0, 0, 0, 0x53)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x7fe, 0, 0, 0, 0, 0, 0x53)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x503, 0xc27, 0, 0, 0, 0x2, 0x30)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x7fd, 0, 0, 0, 0, 0, 0x1)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0x500, 0x9f, 0, 0, 0, 0, 0x42)),
+	IpAddr::V6(Ipv6Addr::new(0x2001, 0xdc3, 0, 0, 0, 0, 0, 0x35)),
+];
+
+/// A query generated by a [`ProofBuilder`] in iterative mode (see [`ProofBuilder::new_iterative`]),
+/// which should be sent to any one of the authoritative `servers`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct IterativeQuery {
+	/// The query to send.
+	pub query: QueryBuf,
+	/// The addresses of the authoritative servers to send the query to (on port 53). Any of them
+	/// should be able to answer it, so if one fails to respond the next should be tried.
+	pub servers: Vec<IpAddr>,
+}
+
+/// Why we made a query.
+#[derive(Clone, PartialEq, Eq)]
+enum QueryPurpose {
+	/// The records in the response are part of the proof.
+	Proof,
+	/// We're looking up the address of a name server for the given zone, as we were referred to
+	/// it without any glue records.
+	NameServerAddress { zone: Name },
+}
+
+/// A query we need to make.
+#[derive(Clone)]
+struct Query {
+	name: Name,
+	ty: u16,
+	/// Why we need the response. Several questions may need the same query, in which case we only
+	/// make it once, for all of them.
+	purposes: Vec<QueryPurpose>,
+}
+
+/// A query we've sent and are waiting on a response to.
+#[derive(Clone)]
+struct PendingQuery {
+	query: Query,
+	/// The zone whose servers we sent the query to.
+	zone: Name,
+}
+
+/// A zone we've found (or, for the root, were given) the servers for.
+#[derive(Clone)]
+struct Zone {
+	name: Name,
+	servers: Vec<IpAddr>,
+	/// Queries we need to send to this zone's servers, once we've looked up their addresses.
+	waiting: Vec<Query>,
+	/// The number of name server address lookups we're still waiting on for this zone.
+	lookups_pending: usize,
+}
+
+/// The state a [`ProofBuilder`] needs to build a proof iteratively.
+#[derive(Clone)]
+pub(super) struct IterativeState {
+	zones: Vec<Zone>,
+	pending: Vec<PendingQuery>,
+}
+
+impl IterativeState {
+	/// Finds the deepest zone we know of whose servers can answer queries for `name` and `ty`.
+	fn zone_for(&mut self, name: &Name, ty: u16) -> &mut Zone {
+		self.zones.iter_mut()
+			// DS records live in the parent zone, so have to be fetched from its servers.
+			.filter(|zone| name.is_subdomain_of(&zone.name) && !(ty == DS::TYPE && zone.name == *name))
+			.max_by_key(|zone| zone.name.labels())
+			.expect("We always know the root zone, which covers every name")
+	}
+
+	/// Routes a new query (see [`Self::route_query`]), unless we're already making the same query,
+	/// in which case the `purpose` is simply added to it.
+	///
+	/// Responses are matched to queries by name and type, so we must never have two of the same
+	/// query outstanding.
+	fn route(&mut self, name: Name, ty: u16, purpose: QueryPurpose) -> Option<IterativeQuery> {
+		let existing = self.pending.iter_mut().map(|pending| &mut pending.query)
+			.chain(self.zones.iter_mut().flat_map(|zone| zone.waiting.iter_mut()))
+			.find(|query| query.name == name && query.ty == ty);
+		if let Some(query) = existing {
+			query.purposes.push(purpose);
+			return None;
+		}
+		self.route_query(Query { name, ty, purposes: vec![purpose] })
+	}
+
+	/// Routes a query to the servers of the deepest zone we know of which can answer it, returning
+	/// it if we know that zone's servers' addresses, or storing it to be sent once we've looked
+	/// them up.
+	fn route_query(&mut self, query: Query) -> Option<IterativeQuery> {
+		let zone = self.zone_for(&query.name, query.ty);
+		if zone.lookups_pending > 0 {
+			zone.waiting.push(query);
+			return None;
+		}
+		let iterative_query = IterativeQuery {
+			query: build_query_with_flags(&query.name, query.ty, 0), servers: zone.servers.clone(),
+		};
+		let zone = zone.name.clone();
+		self.pending.push(PendingQuery { query, zone });
+		Some(iterative_query)
+	}
+
+	/// Stores the servers for a zone we were referred to, returning the names of any name servers
+	/// we need to look up the (IPv4 and IPv6) addresses of, if the referral didn't include any
+	/// glue.
+	fn add_zone(&mut self, zone: Name, name_servers: Vec<Name>, glue: Vec<(Name, IpAddr)>)
+	-> Result<Vec<Name>, ProofBuildingError> {
+		if self.zones.iter().any(|known| known.name == zone) { return Ok(Vec::new()); }
+		let mut servers = glue.into_iter()
+			.filter(|(name, _)| name_servers.contains(name))
+			.map(|(_, addr)| addr)
+			.collect::<Vec<_>>();
+		// Prefer IPv4 servers, as they're more likely to be reachable.
+		servers.sort_by_key(|addr| addr.is_ipv6());
+		let lookups = if servers.is_empty() {
+			// Name servers within the zone itself can't be looked up without glue.
+			name_servers.into_iter()
+				.filter(|name_server| !name_server.is_subdomain_of(&zone))
+				.take(MAX_GLUELESS_NS_LOOKUPS)
+				.collect()
+		} else { Vec::new() };
+		if servers.is_empty() && lookups.is_empty() { return Err(ProofBuildingError::InvalidResponse); }
+		let lookups_pending = lookups.len() * NAME_SERVER_ADDRESS_TYPES.len();
+		self.zones.push(Zone { name: zone, servers, waiting: Vec::new(), lookups_pending });
+		Ok(lookups)
+	}
+
+	/// Handles the completion of a name server address lookup for the given zone, returning any
+	/// queries which were waiting on the zone's servers and can now be sent, once all the lookups
+	/// for the zone are done.
+	fn name_server_lookup_done(&mut self, zone: &Name, addrs: Vec<IpAddr>)
+	-> Result<Vec<Query>, ProofBuildingError> {
+		let zone = self.zones.iter_mut().find(|known| known.name == *zone)
+			.expect("We only look up name servers for zones we know");
+		zone.lookups_pending -= 1;
+		zone.servers.extend(addrs);
+		if zone.lookups_pending > 0 { return Ok(Vec::new()); }
+		if zone.servers.is_empty() { return Err(ProofBuildingError::InvalidResponse); }
+		// As with glue, prefer IPv4 servers.
+		zone.servers.sort_by_key(|addr| addr.is_ipv6());
+		Ok(mem::take(&mut zone.waiting))
+	}
+}
+
+/// A resource record in a DNS message, with its RDATA left unparsed.
+struct RawRR<'a> {
+	name: Name,
+	ty: u16,
+	class: u16,
+	ttl: u32,
+	data: &'a [u8],
+}
+
+impl RawRR<'_> {
+	fn parse(&self, wire_packet: &[u8]) -> Result<RR, ()> {
+		if self.class != 1 { return Err(()); } // We only support the INternet
+		parse_rdata(self.name.clone(), self.ty, self.data, wire_packet)
+	}
+}
+
+fn read_raw_rr<'a>(inp: &mut &'a [u8], wire_packet: &[u8]) -> Result<RawRR<'a>, ()> {
+	let name = read_wire_packet_name(inp, wire_packet)?;
+	let ty = read_u16(inp)?;
+	let class = read_u16(inp)?;
+	let ttl = read_u32(inp)?;
+	let data_len = read_u16(inp)? as usize;
+	if inp.len() < data_len { return Err(()); }
+	let data = &inp[..data_len];
+	*inp = &inp[data_len..];
+	Ok(RawRR { name, ty, class, ttl, data })
+}
+
+/// A DNS response from an authoritative server, split into its sections.
+struct Response<'a> {
+	flags: u16,
+	name: Name,
+	ty: u16,
+	answers: Vec<RawRR<'a>>,
+	authorities: Vec<RawRR<'a>>,
+	additional: Vec<RawRR<'a>>,
+}
+
+fn parse_response(resp: &[u8]) -> Result<Response<'_>, ()> {
+	let mut read = resp;
+	let _txid = read_u16(&mut read)?;
+	let flags = read_u16(&mut read)?;
+	let questions = read_u16(&mut read)?;
+	if questions != 1 { return Err(()); }
+	let answers = read_u16(&mut read)?;
+	let authorities = read_u16(&mut read)?;
+	let additional = read_u16(&mut read)?;
+	let name = read_wire_packet_name(&mut read, resp)?;
+	let ty = read_u16(&mut read)?;
+	let _class = read_u16(&mut read)?;
+	let mut read_section = |count| {
+		(0..count).map(|_| read_raw_rr(&mut read, resp)).collect::<Result<Vec<_>, ()>>()
+	};
+	let answers = read_section(answers)?;
+	let authorities = read_section(authorities)?;
+	let additional = read_section(additional)?;
+	Ok(Response { flags, name, ty, answers, authorities, additional })
+}
+
+/// What an authoritative server told us in response to a query.
+enum ResponseKind {
+	/// The server answered our query.
+	Answer,
+	/// The server referred us to the servers for a zone closer to the name we queried.
+	Referral { zone: Name, name_servers: Vec<Name>, glue: Vec<(Name, IpAddr)> },
+	/// The server told us that there are no records of the type we queried at the name (though
+	/// the name exists), i.e. a NODATA response.
+	NoData,
+}
+
+/// Works out what kind of response the server gave us, without touching any state.
+///
+/// Fails with [`ProofBuildingError::ServerFailure`] if the server failed to answer the query or
+/// refused to, in which case another server for the zone may be able to.
+fn classify_response(resp: &[u8], response: &Response) -> Result<ResponseKind, ProofBuildingError> {
+	if response.flags & 0b1000_0000_0000_0000 == 0 {
+		// This message is tagged as a query, not a response?
+		return Err(ProofBuildingError::InvalidResponse);
+	}
+	match response.flags & 0b1111 {
+		0 => {},
+		// SERVFAIL, FORMERR and REFUSED all indicate this server can't help us, but another may.
+		1|2|5 => return Err(ProofBuildingError::ServerFailure),
+		3 => return Err(ProofBuildingError::MissingRecord),
+		_ => return Err(ProofBuildingError::InvalidResponse),
+	}
+	if !response.answers.is_empty() { return Ok(ResponseKind::Answer); }
+
+	let mut zone = None;
+	let mut name_servers = Vec::new();
+	for rr in response.authorities.iter().filter(|rr| rr.ty == NS::TYPE) {
+		let ns = match rr.parse(resp).map_err(|()| ProofBuildingError::InvalidResponse)? {
+			RR::NS(ns) => ns,
+			_ => return Err(ProofBuildingError::InvalidResponse),
+		};
+		if zone.get_or_insert_with(|| ns.name.clone()) != &ns.name {
+			return Err(ProofBuildingError::InvalidResponse);
+		}
+		name_servers.push(ns.name_server);
+	}
+	let zone = match zone {
+		Some(zone) => zone,
+		// A response with no answers or referral but with the zone's SOA is NODATA.
+		None if response.authorities.iter().any(|rr| rr.ty == SOA::TYPE) => return Ok(ResponseKind::NoData),
+		None => return Err(ProofBuildingError::InvalidResponse),
+	};
+
+	let mut glue = Vec::new();
+	for rr in response.additional.iter() {
+		if rr.class != 1 { continue; }
+		match rr.parse(resp) {
+			Ok(RR::A(a)) => glue.push((a.name, IpAddr::from(a.address))),
+			Ok(RR::AAAA(aaaa)) => glue.push((aaaa.name, IpAddr::from(aaaa.address))),
+			_ => {},
+		}
+	}
+	Ok(ResponseKind::Referral { zone, name_servers, glue })
+}
+
+/// Gets the name the answers in the given response are aliased to (via CNAMEs) if the response
+/// doesn't contain the records we want at that name, i.e. if we need to query for them there.
+fn alias_target(resp: &[u8], response: &Response) -> Result<Option<Name>, ProofBuildingError> {
+	if response.ty == CName::TYPE { return Ok(None); }
+	let mut target = response.name.clone();
+	for _ in 0..MAX_PROOF_STEPS {
+		let mut next = None;
+		for rr in response.answers.iter().filter(|rr| rr.name == target) {
+			if rr.ty == response.ty { return Ok(None); }
+			if rr.ty == CName::TYPE {
+				if let Ok(RR::CName(cname)) = rr.parse(resp) { next = Some(cname.canonical_name); }
+			}
+		}
+		match next {
+			Some(next) => target = next,
+			None if target == response.name => return Err(ProofBuildingError::InvalidResponse),
+			None => return Ok(Some(target)),
+		}
+	}
+	Err(ProofBuildingError::InvalidResponse)
+}
+
+impl ProofBuilder {
+	/// Constructs a new [`ProofBuilder`] which builds a proof by querying authoritative servers
+	/// directly, rather than a recursive resolver, as well as the initial query to send.
+	///
+	/// Resolution starts at the root servers at the given `root_hints` (generally
+	/// [`ROOT_HINTS`]), following referrals down to the servers for the zone containing the
+	/// records, and the DNSKEY and DS records needed for the proof are fetched from the servers
+	/// for the zones which hold them. Because nothing relies on a resolver having validated the
+	/// responses (i.e. setting the AD bit), proofs can be built from anywhere.
+	///
+	/// Responses must be passed to [`Self::process_iterative_response`] rather than
+	/// [`Self::process_response`], and each query returned from it should be sent to one of its
+	/// [`IterativeQuery::servers`]. Otherwise the builder is used as with [`Self::new`].
+	pub fn new_iterative(name: &Name, ty: u16, root_hints: &[IpAddr]) -> (ProofBuilder, IterativeQuery) {
+		let (builder, mut initial_queries) = ProofBuilder::new_iterative_for_questions(&[(name.clone(), ty)], root_hints);
+		(builder, initial_queries.pop().expect("We build one query per question"))
+	}
+
+	/// Constructs a new [`ProofBuilder`] which builds a single proof for records of each of the
+	/// given types at the given names by querying authoritative servers directly (see
+	/// [`Self::new_iterative`]), as well as the initial queries to send.
+	///
+	/// As with [`Self::new_for_questions`], the DNSKEY and DS records needed for several of the
+	/// questions are only queried for once, and the referrals followed for one question are
+	/// reused for the others.
+	pub fn new_iterative_for_questions(questions: &[(Name, u16)], root_hints: &[IpAddr])
+	-> (ProofBuilder, Vec<IterativeQuery>) {
+		let (mut builder, _) = ProofBuilder::new_for_questions(questions);
+		let root = Zone {
+			name: ".".try_into().expect("The root is a valid name"),
+			servers: root_hints.to_vec(), waiting: Vec::new(), lookups_pending: 0,
+		};
+		let mut state = IterativeState { zones: vec![root], pending: Vec::new() };
+		let initial_queries = questions.iter()
+			.filter_map(|(name, ty)| state.route(name.clone(), *ty, QueryPurpose::Proof))
+			.collect();
+		builder.iterative = Some(state);
+		(builder, initial_queries)
+	}
+
+	/// Processes a response from an authoritative server to one of the queries generated by a
+	/// [`ProofBuilder`] constructed with [`Self::new_iterative`], returning a list of new queries
+	/// to send.
+	///
+	/// If this fails with [`ProofBuildingError::ServerFailure`], the server which sent the
+	/// response couldn't answer the query and it should be sent to the next of its
+	/// [`IterativeQuery::servers`]. Any other error is fatal.
+	pub fn process_iterative_response(&mut self, resp: &QueryBuf) -> Result<Vec<IterativeQuery>, ProofBuildingError> {
+		let mut state = self.iterative.take().ok_or(ProofBuildingError::NoResponseExpected)?;
+		let res = self.handle_iterative_response(&mut state, resp);
+		self.iterative = Some(state);
+		let new_queries = res?;
+		if self.queries_made <= self.query_limit() {
+			Ok(new_queries)
+		} else {
+			Ok(Vec::new())
+		}
+	}
+
+	fn handle_iterative_response(&mut self, state: &mut IterativeState, resp: &[u8])
+	-> Result<Vec<IterativeQuery>, ProofBuildingError> {
+		if self.pending_queries == 0 { return Err(ProofBuildingError::NoResponseExpected); }
+		let response = parse_response(resp).map_err(|()| ProofBuildingError::InvalidResponse)?;
+		let idx = state.pending.iter()
+			.position(|pending| pending.query.name == response.name && pending.query.ty == response.ty)
+			.ok_or(ProofBuildingError::InvalidResponse)?;
+		let needed_for_proof = state.pending[idx].query.purposes.contains(&QueryPurpose::Proof);
+
+		let kind = match classify_response(resp, &response) {
+			Ok(kind) => kind,
+			// Leave the query pending so that it can be sent to another server.
+			Err(ProofBuildingError::ServerFailure) => return Err(ProofBuildingError::ServerFailure),
+			Err(err) if needed_for_proof => {
+				if self.proof.is_empty() && err == ProofBuildingError::MissingRecord {
+					return Err(ProofBuildingError::NoSuchName);
+				}
+				return Err(err);
+			},
+			Err(_) => {
+				// We may be able to find another of the zone's name servers.
+				let pending = state.pending.swap_remove(idx);
+				return self.query_done(state, pending.query, Vec::new());
+			},
+		};
+		let PendingQuery { query, zone: queried_zone } = state.pending.swap_remove(idx);
+
+		match kind {
+			ResponseKind::Referral { zone, name_servers, glue } => {
+				// We should only ever be referred further down the tree, towards the name we
+				// queried, otherwise we may never get an answer.
+				if zone == queried_zone || !zone.is_subdomain_of(&queried_zone) || !query.name.is_subdomain_of(&zone)
+					|| (query.ty == DS::TYPE && zone == query.name)
+				{
+					return Err(ProofBuildingError::InvalidResponse);
+				}
+				let lookups = state.add_zone(zone.clone(), name_servers, glue)?;
+				// The query remains pending, it just has to be sent to the new zone's servers.
+				self.queries_made += 1;
+				let mut queries = Vec::new();
+				queries.extend(state.route_query(query));
+				for name_server in lookups {
+					for ty in NAME_SERVER_ADDRESS_TYPES {
+						self.pending_queries += 1;
+						self.queries_made += 1;
+						let purpose = QueryPurpose::NameServerAddress { zone: zone.clone() };
+						queries.extend(state.route(name_server.clone(), ty, purpose));
+					}
+				}
+				Ok(queries)
+			},
+			ResponseKind::Answer => {
+				let mut queries = Vec::new();
+				if needed_for_proof {
+					let mut rrsig_key_names = Vec::new();
+					let min_ttl = handle_response(resp, &mut self.proof, &mut rrsig_key_names, false)?;
+					self.min_ttl = cmp::min(self.min_ttl, min_ttl);
+					// `key_queries` already counts the new queries as pending.
+					for (name, ty) in self.key_queries(rrsig_key_names, None) {
+						queries.extend(state.route(name, ty, QueryPurpose::Proof));
+					}
+					if let Some(target) = alias_target(resp, &response)? {
+						self.pending_queries += 1;
+						self.queries_made += 1;
+						queries.extend(state.route(target, query.ty, QueryPurpose::Proof));
+					}
+				}
+				let addrs = response.answers.iter()
+					.filter(|rr| rr.name == query.name)
+					.filter_map(|rr| match rr.parse(resp) {
+						Ok(RR::A(a)) => Some(IpAddr::from(a.address)),
+						Ok(RR::AAAA(aaaa)) => Some(IpAddr::from(aaaa.address)),
+						_ => None,
+					}).collect();
+				queries.extend(self.query_done(state, query, addrs)?);
+				Ok(queries)
+			},
+			ResponseKind::NoData => {
+				let mut queries = Vec::new();
+				if needed_for_proof {
+					let rrsig_key_names = self.prove_no_data(resp, &response)?;
+					for (name, ty) in self.key_queries(rrsig_key_names, None) {
+						queries.extend(state.route(name, ty, QueryPurpose::Proof));
+					}
+				}
+				queries.extend(self.query_done(state, query, Vec::new())?);
+				Ok(queries)
+			},
+		}
+	}
+
+	/// Adds the SOA and NSEC/NSEC3 records (and signatures thereover) from a NODATA response to the
+	/// proof, proving that the records we queried for don't exist, and returning the signers of
+	/// the signatures.
+	///
+	/// Fails with [`ProofBuildingError::MissingRecord`] if the server gave us no NSEC/NSEC3 records
+	/// (e.g. because the zone isn't signed), as we can't prove anything without them.
+	fn prove_no_data(&mut self, resp: &[u8], response: &Response) -> Result<Vec<Name>, ProofBuildingError> {
+		if !response.authorities.iter().any(|rr| rr.ty == NSec::TYPE || rr.ty == NSec3::TYPE) {
+			return Err(ProofBuildingError::MissingRecord);
+		}
+		let mut rrsig_key_names = Vec::new();
+		for raw_rr in response.authorities.iter() {
+			if ![SOA::TYPE, NSec::TYPE, NSec3::TYPE, RRSig::TYPE].contains(&raw_rr.ty) { continue; }
+			let rr = raw_rr.parse(resp).map_err(|()| ProofBuildingError::InvalidResponse)?;
+			if let Some(ttl) = handle_authority_rr(rr, raw_rr.ttl, &mut self.proof, &mut rrsig_key_names) {
+				self.min_ttl = cmp::min(self.min_ttl, ttl);
+			}
+		}
+		Ok(rrsig_key_names)
+	}
+
+	/// Handles the completion of the given `query`, passing any name server addresses it found to
+	/// the zones which were waiting on them and routing any queries which were waiting on those
+	/// zones' servers.
+	fn query_done(&mut self, state: &mut IterativeState, query: Query, addrs: Vec<IpAddr>)
+	-> Result<Vec<IterativeQuery>, ProofBuildingError> {
+		self.pending_queries -= query.purposes.len();
+		let mut queries = Vec::new();
+		for purpose in query.purposes {
+			if let QueryPurpose::NameServerAddress { zone } = purpose {
+				// Queries which were waiting were already counted as pending.
+				for waiting in state.name_server_lookup_done(&zone, addrs.clone())? {
+					queries.extend(state.route_query(waiting));
+				}
+			}
+		}
+		Ok(queries)
+	}
+}
+
+/// The port we query authoritative servers on.
+const DNS_PORT: u16 = 53;
+
+/// Starts tracking a query generated by a [`ProofBuilder`] in iterative mode in
+/// [`RetryingQueries`].
+pub(super) fn pending_query(query: IterativeQuery) -> super::PendingQuery {
+	let servers = query.servers.into_iter().map(|server| SocketAddr::new(server, DNS_PORT)).collect();
+	super::PendingQuery { query: query.query, failures: 0, sent: false, servers }
+}
+
+impl RetryingQueries<'_> {
+	/// Starts tracking the queries for a new [`ProofBuilder`] in iterative mode.
+	fn start_iterative(&mut self, initial_queries: Vec<IterativeQuery>) {
+		self.pending = initial_queries.into_iter().map(pending_query).collect();
+	}
+
+	/// Gets the next query to make and the server to send it to, marking it as sent.
+	///
+	/// Queries are made one at a time, each to the next of its servers after every failure.
+	fn next_iterative_query(&mut self) -> Result<(QueryBuf, SocketAddr), Error> {
+		// If we run out of queries while still awaiting responses, the remaining queries are
+		// waiting on name server addresses which we'll never find.
+		let query = self.pending.iter_mut().rev().find(|query| !query.sent)
+			.ok_or_else(|| Error::new(ErrorKind::Other, "Failed to find the servers for a zone"))?;
+		if query.servers.is_empty() {
+			return Err(Error::new(ErrorKind::InvalidInput, "No servers to query"));
+		}
+		query.sent = true;
+		Ok((query.query.clone(), query.servers[query.failures % query.servers.len()]))
+	}
+
+	/// Handles a response to the query returned by [`Self::next_iterative_query`]. A response to
+	/// some other query is counted as a failure of the server.
+	fn handle_iterative_response(&mut self, builder: &mut ProofBuilder, resp: &QueryBuf) -> Result<(), Error> {
+		self.handle_response(builder, resp)?;
+		if self.pending.iter().any(|query| query.sent) {
+			self.connection_failed(Error::new(ErrorKind::InvalidData, "Server responded to a different query"))?;
+		}
+		Ok(())
+	}
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_iterative_with_config`] does, but making queries over transports
+/// returned by `connect` (see [`build_proof_for_questions_with_config_and_transport`]), which is
+/// called with each authoritative server we query.
+fn build_proof_iterative_with_transport<T, F>(
+	config: &ProofBuildingConfig, questions: &[(Name, u16)], mut connect: F,
+) -> Result<(Vec<u8>, u32), Error>
+where T: DnsTransport, F: FnMut(SocketAddr, Duration) -> Result<T, Error> {
+	check_provable_questions(questions)?;
+	let mut queries = RetryingQueries::new(config)?;
+	let root_hints = config.resolvers.iter().map(SocketAddr::ip).collect::<Vec<_>>();
+	let (mut builder, initial_queries) = ProofBuilder::new_iterative_for_questions(questions, &root_hints);
+	queries.start_iterative(initial_queries);
+	while builder.awaiting_responses() {
+		let timeout = queries.timeout()?;
+		let (query, server) = queries.next_iterative_query()?;
+		let res = connect(server, timeout).and_then(|mut transport| {
+			transport.send_query(&query)?;
+			transport.set_read_timeout(queries.timeout()?)?;
+			transport.read_response()
+		});
+		match res {
+			Ok(resp) => queries.handle_iterative_response(&mut builder, &resp)?,
+			Err(err) => queries.connection_failed(err)?,
+		}
+	}
+	finish(builder)
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_iterative_with_transport`] does, but asynchronously using `tokio` over
+/// [`AsyncDnsTransport`]s.
+#[cfg(feature = "tokio")]
+async fn build_proof_iterative_with_transport_async<T, F, Fut>(
+	config: &ProofBuildingConfig, questions: &[(Name, u16)], mut connect: F,
+) -> Result<(Vec<u8>, u32), Error>
+where T: AsyncDnsTransport, F: FnMut(SocketAddr, Duration) -> Fut, Fut: Future<Output = Result<T, Error>> {
+	check_provable_questions(questions)?;
+	let mut queries = RetryingQueries::new(config)?;
+	let root_hints = config.resolvers.iter().map(SocketAddr::ip).collect::<Vec<_>>();
+	let (mut builder, initial_queries) = ProofBuilder::new_iterative_for_questions(questions, &root_hints);
+	queries.start_iterative(initial_queries);
+	while builder.awaiting_responses() {
+		let timeout = queries.timeout()?;
+		let (query, server) = queries.next_iterative_query()?;
+		let step = async {
+			let mut transport = connect(server, timeout).await?;
+			transport.send_query(&query).await?;
+			transport.read_response().await
+		};
+		let res = match tokio_crate::time::timeout(timeout, step).await {
+			Ok(res) => res,
+			Err(_) => Err(Error::new(ErrorKind::TimedOut, "Timed out waiting for the server")),
+		};
+		match res {
+			Ok(resp) => queries.handle_iterative_response(&mut builder, &resp)?,
+			Err(err) => queries.connection_failed(err)?,
+		}
+	}
+	finish(builder)
+}
+
+/// Gets the config used to build proofs iteratively starting from the given `root_hints`.
+fn root_hints_config(root_hints: &[IpAddr]) -> ProofBuildingConfig {
+	ProofBuildingConfig::new(root_hints.iter().map(|server| SocketAddr::new(*server, DNS_PORT)).collect())
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe by iteratively querying authoritative
+/// servers, starting at the root servers at the given `root_hints` (generally [`ROOT_HINTS`]),
+/// returning the proof as well as the TTL for the proof.
+///
+/// This does not require a recursive resolver (see [`ProofBuilder::new_iterative`]), but does
+/// require that we can reach authoritative servers over UDP (or TCP) on port 53. Queries which fail
+/// are retried on the next of the zone's servers, and time out, as [`ProofBuildingConfig::new`]
+/// describes.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_iterative(root_hints: &[IpAddr], domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_iterative(root_hints, domain, &[ty])
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types`] does, but by iteratively querying authoritative servers (see
+/// [`build_proof_iterative`]).
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_for_types_iterative(root_hints: &[IpAddr], domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_iterative_with_config(&root_hints_config(root_hints), &type_questions(domain, types))
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` by iteratively querying
+/// authoritative servers (see [`build_proof_iterative`]), with the timeouts and retries given in
+/// the `config`.
+///
+/// The config's [`resolvers`](ProofBuildingConfig::resolvers) are used as the root servers
+/// (generally [`ROOT_HINTS`]), though, as with all the authoritative servers we query, they're
+/// queried on port 53 whatever port is given. Its [`cache`](ProofBuildingConfig::cache) isn't used.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+pub fn build_proof_for_questions_iterative_with_config(config: &ProofBuildingConfig, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_iterative_with_transport(config, questions, connect_udp)
+}
+
+/// Builds a DNSSEC proof for records of the given `ty`pe as [`build_proof_iterative`] does, but
+/// asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_iterative_async(root_hints: &[IpAddr], domain: &Name, ty: u16) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_types_iterative_async(root_hints, domain, &[ty]).await
+}
+
+/// Builds a single DNSSEC proof for records of each of the given `types` as
+/// [`build_proof_for_types_iterative`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_types_iterative_async(root_hints: &[IpAddr], domain: &Name, types: &[u16]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_for_questions_iterative_with_config_async(&root_hints_config(root_hints), &type_questions(domain, types)).await
+}
+
+/// Builds a single DNSSEC proof for each of the given `questions` as
+/// [`build_proof_for_questions_iterative_with_config`] does, but asynchronously using `tokio`.
+///
+/// Note that this proof is NOT verified in any way, you need to use the [`crate::validation`]
+/// module to validate the records contained.
+#[cfg(feature = "tokio")]
+pub async fn build_proof_for_questions_iterative_with_config_async(config: &ProofBuildingConfig, questions: &[(Name, u16)]) -> Result<(Vec<u8>, u32), Error> {
+	build_proof_iterative_with_transport_async(config, questions, connect_udp_async).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::zonefile::parse_zone_file;
+
+	#[cfg(feature = "tokio")]
+	use tokio_crate as tokio;
+
+	const SIG: &str = "3600 20300101000000 20200101000000";
+	const ZERO_DIGEST: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+	/// The zones served by our stand-in authoritative servers, with the addresses of the server for
+	/// each. The signatures are bogus, but the [`ProofBuilder`] doesn't validate them.
+	///
+	/// The addresses are only used to tell the servers apart, queries to them are actually sent to
+	/// the servers [`start_authoritative_servers`] runs on localhost.
+	fn test_zones() -> Vec<(Vec<IpAddr>, Name, String)> {
+		let zone = |addrs: &[&str], apex: &str, records: String| {
+			(addrs.iter().map(|addr| addr.parse().unwrap()).collect(), apex.try_into().unwrap(), records)
+		};
+		vec![
+			zone(&["192.0.2.1"], ".", format!("\
+				. 3600 IN DNSKEY 257 3 13 AAAA\n\
+				. 3600 IN RRSIG DNSKEY 13 0 {SIG} 1234 . AAAA\n\
+				com. 3600 IN NS ns.com.\n\
+				ns.com. 3600 IN A 192.0.2.2\n\
+				com. 3600 IN DS 2345 13 2 {ZERO_DIGEST}\n\
+				com. 3600 IN RRSIG DS 13 1 {SIG} 1234 . AAAA\n\
+				net. 3600 IN NS ns.net.\n\
+				ns.net. 3600 IN A 192.0.2.4\n\
+			")),
+			zone(&["192.0.2.2"], "com.", format!("\
+				com. 3600 IN DNSKEY 257 3 13 AAAA\n\
+				com. 3600 IN RRSIG DNSKEY 13 1 {SIG} 2345 com. AAAA\n\
+				example.com. 3600 IN NS ns.example.net.\n\
+				example.com. 3600 IN DS 3456 13 2 {ZERO_DIGEST}\n\
+				example.com. 3600 IN RRSIG DS 13 2 {SIG} 2345 com. AAAA\n\
+			")),
+			zone(&["192.0.2.4"], "net.", "\
+				net. 3600 IN SOA ns.net. hostmaster.net. 1 7200 3600 1209600 300\n\
+				ns.example.net. 3600 IN A 192.0.2.3\n\
+				ns.example.net. 3600 IN AAAA 2001:db8::3\n\
+			".to_owned()),
+			zone(&["192.0.2.3", "2001:db8::3"], "example.com.", format!("\
+				example.com. 3600 IN TXT \"dnssec_prover_test\"\n\
+				example.com. 3600 IN RRSIG TXT 13 2 {SIG} 3456 example.com. AAAA\n\
+				example.com. 3600 IN DNSKEY 257 3 13 AAAA\n\
+				example.com. 3600 IN RRSIG DNSKEY 13 2 {SIG} 3456 example.com. AAAA\n\
+				www.example.com. 3600 IN CNAME example.com.\n\
+				www.example.com. 3600 IN RRSIG CNAME 13 3 {SIG} 3456 example.com. AAAA\n\
+				example.com. 3600 IN SOA ns.example.net. hostmaster.example.com. 1 7200 3600 1209600 300\n\
+				example.com. 3600 IN RRSIG SOA 13 2 {SIG} 3456 example.com. AAAA\n\
+				example.com. 3600 IN NSEC www.example.com. TXT SOA RRSIG NSEC DNSKEY\n\
+				example.com. 3600 IN RRSIG NSEC 13 2 {SIG} 3456 example.com. AAAA\n\
+			")),
+		]
+	}
+
+	/// Builds a response to the given `query` with the given records in each section.
+	fn response(query: &[u8], flags: u16, answers: &[&RR], authorities: &[&RR], additional: &[&RR]) -> QueryBuf {
+		let mut read = &query[12..];
+		read_wire_packet_name(&mut read, query).unwrap();
+		let question_len = query.len() - 12 - read.len() + 4;
+
+		let mut resp = query[..2].to_vec();
+		resp.extend_from_slice(&flags.to_be_bytes());
+		resp.extend_from_slice(&[0, 1]); // One question
+		resp.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+		resp.extend_from_slice(&(authorities.len() as u16).to_be_bytes());
+		resp.extend_from_slice(&(additional.len() as u16).to_be_bytes());
+		resp.extend_from_slice(&query[12..12 + question_len]);
+		for rr in answers.iter().chain(authorities).chain(additional) {
+			write_rr(*rr, 3600, &mut resp);
+		}
+		let mut resp_buf = QueryBuf::new_zeroed(0);
+		resp_buf.extend_from_slice(&resp);
+		resp_buf
+	}
+
+	/// Builds a response to the given `query` as an authoritative server for the zone at `apex`
+	/// would, referring the query to the servers for any zone cut below the apex (with glue), or
+	/// otherwise answering with the records (or a CNAME) at the queried name, or the zone's SOA and
+	/// any NSEC record at the name if there are none. The AD bit is never set.
+	fn authoritative_response(query: &[u8], apex: &Name, zone: &[RR]) -> QueryBuf {
+		let mut read = &query[12..];
+		let name = read_wire_packet_name(&mut read, query).unwrap();
+		let ty = read_u16(&mut read).unwrap();
+
+		let cut = zone.iter().filter_map(|rr| match rr {
+			RR::NS(ns) if ns.name != *apex && name.is_subdomain_of(&ns.name)
+				&& !(ty == DS::TYPE && ns.name == name) => Some(&ns.name),
+			_ => None,
+		}).max_by_key(|cut| cut.labels());
+		if let Some(cut) = cut {
+			let authorities = zone.iter()
+				.filter(|rr| rr.name() == cut && rr.ty() == NS::TYPE).collect::<Vec<_>>();
+			let glue = zone.iter().filter(|rr| rr.ty() == A::TYPE && authorities.iter().any(|ns| match ns {
+				RR::NS(ns) => ns.name_server == *rr.name(),
+				_ => false,
+			})).collect::<Vec<_>>();
+			response(query, 0x8000, &[], &authorities, &glue) // Response
+		} else {
+			let records_at = |ty| zone.iter().filter(|rr| rr.name() == &name && match rr {
+				RR::RRSig(rrsig) => rrsig.ty == ty,
+				_ => rr.ty() == ty,
+			}).collect::<Vec<_>>();
+			let mut answers = records_at(ty);
+			if answers.is_empty() { answers = records_at(CName::TYPE); }
+			let mut authorities = Vec::new();
+			if answers.is_empty() {
+				authorities = zone.iter().filter(|rr| match rr {
+					RR::SOA(soa) => soa.name == *apex,
+					RR::RRSig(rrsig) => rrsig.ty == SOA::TYPE && rrsig.name == *apex,
+					_ => false,
+				}).collect();
+				authorities.extend(records_at(NSec::TYPE));
+			}
+			response(query, 0x8400, &answers, &authorities, &[]) // Response, Authoritative Answer
+		}
+	}
+
+	/// Runs stand-in authoritative servers for each of [`test_zones`] over UDP on localhost,
+	/// returning the address each zone's server is listening on in place of its address in
+	/// [`test_zones`].
+	fn start_authoritative_servers() -> Vec<(IpAddr, SocketAddr)> {
+		let mut servers = Vec::new();
+		for (addrs, apex, records) in test_zones() {
+			let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+			servers.extend(addrs.into_iter().map(|addr| (addr, socket.local_addr().unwrap())));
+			let zone = parse_zone_file(&records).unwrap();
+			std::thread::spawn(move || {
+				let mut buf = [0; 4096];
+				loop {
+					let (len, peer) = socket.recv_from(&mut buf).unwrap();
+					let resp = authoritative_response(&buf[..len], &apex, &zone);
+					socket.send_to(&resp, peer).unwrap();
+				}
+			});
+		}
+		servers
+	}
+
+	/// Gets the address the stand-in server for the given authoritative `server` is actually
+	/// listening on, failing as a server which doesn't respond would for any we aren't running.
+	fn test_server_addr(servers: &[(IpAddr, SocketAddr)], server: SocketAddr) -> Result<SocketAddr, Error> {
+		assert_eq!(server.port(), 53);
+		servers.iter().find(|(addr, _)| *addr == server.ip()).map(|(_, listen_addr)| *listen_addr)
+			.ok_or_else(|| Error::new(ErrorKind::ConnectionRefused, "No test server at the address"))
+	}
+
+	/// Gets the type of the given record, or of the records it signs if it is an RRSIG.
+	fn covered_ty(rr: &RR) -> u16 {
+		if let RR::RRSig(rrsig) = rr { rrsig.ty } else { rr.ty() }
+	}
+
+	/// Returns true if the given record is only used to deny the existence of other records.
+	fn is_denial_rr(rr: &RR) -> bool {
+		covered_ty(rr) == SOA::TYPE || covered_ty(rr) == NSec::TYPE
+	}
+
+	/// Gets the records we expect in a proof built from [`test_zones`], i.e. everything other than
+	/// the referrals, glue and denials of existence, optionally including the `www.example.com.`
+	/// CNAME.
+	fn expected_proof_records(include_cname: bool) -> Vec<RR> {
+		let mut rrs = test_zones().into_iter()
+			.flat_map(|(_, _, records)| parse_zone_file(&records).unwrap())
+			.filter(|rr| ![NS::TYPE, A::TYPE, AAAA::TYPE].contains(&rr.ty()) && !is_denial_rr(rr))
+			.filter(|rr| include_cname || rr.name().as_str() != "www.example.com.")
+			.collect::<Vec<_>>();
+		rrs.sort_unstable();
+		rrs
+	}
+
+	fn check_proof(proof: &[u8], ttl: u32, include_cname: bool) {
+		let mut rrs = parse_rr_stream(proof).unwrap();
+		rrs.sort_unstable();
+		assert_eq!(rrs, expected_proof_records(include_cname));
+		assert_eq!(ttl, 3600);
+	}
+
+	const ROOT: [IpAddr; 1] = [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))];
+
+	/// Builds a proof for each of the given `questions` iteratively, querying our stand-in
+	/// `servers`.
+	fn build_test_proof(servers: &[(IpAddr, SocketAddr)], config: &ProofBuildingConfig, questions: &[(Name, u16)])
+	-> Result<(Vec<u8>, u32), Error> {
+		build_proof_iterative_with_transport(config, questions, |server, timeout| {
+			connect_udp(test_server_addr(servers, server)?, timeout)
+		})
+	}
+
+	#[test]
+	fn test_iterative_proof() {
+		let servers = start_authoritative_servers();
+		let query_name: Name = "example.com.".try_into().unwrap();
+		let question = [(query_name.clone(), Txt::TYPE)];
+		let (proof, ttl) = build_test_proof(&servers, &root_hints_config(&ROOT), &question).unwrap();
+		check_proof(&proof, ttl, false);
+
+		// CNAMEs are followed, with the target queried at its own authoritative servers
+		let www: Name = "www.example.com.".try_into().unwrap();
+		let (proof, ttl) = build_test_proof(&servers, &root_hints_config(&ROOT), &[(www.clone(), Txt::TYPE)]).unwrap();
+		check_proof(&proof, ttl, true);
+
+		// Several questions can be answered in one proof
+		let questions = [(query_name, Txt::TYPE), (www, Txt::TYPE)];
+		let (proof, ttl) = build_test_proof(&servers, &root_hints_config(&ROOT), &questions).unwrap();
+		check_proof(&proof, ttl, true);
+
+		// Servers which don't respond are skipped
+		let dead_server = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 5));
+		let hints = [dead_server, ROOT[0]];
+		let (proof, ttl) = build_test_proof(&servers, &root_hints_config(&hints), &question).unwrap();
+		check_proof(&proof, ttl, false);
+
+		// But only as many times as the config allows
+		let mut config = root_hints_config(&hints);
+		config.query_retries = 0;
+		assert!(build_test_proof(&servers, &config, &question).is_err());
+
+		// And we fail if none of them respond
+		assert!(build_test_proof(&servers, &root_hints_config(&hints[..1]), &question).is_err());
+		assert!(build_test_proof(&servers, &root_hints_config(&[]), &question).is_err());
+
+		// Name servers' IPv6 addresses are looked up too, so if example.com.'s server doesn't
+		// respond on its IPv4 address we move on to its IPv6 one
+		let ipv4_server = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 3));
+		let ipv6_servers = servers.iter().filter(|(addr, _)| *addr != ipv4_server).cloned().collect::<Vec<_>>();
+		let (proof, ttl) = build_test_proof(&ipv6_servers, &root_hints_config(&ROOT), &question).unwrap();
+		check_proof(&proof, ttl, false);
+	}
+
+	/// Makes the given `queries` (and any further queries the `builder` generates) by hand, one at
+	/// a time, returning the name and type of each along with the zone it was sent to.
+	fn make_queries(builder: &mut ProofBuilder, mut queries: Vec<IterativeQuery>) -> Vec<(String, u16, String)> {
+		let zones = test_zones().into_iter()
+			.map(|(addrs, apex, records)| (addrs, apex, parse_zone_file(&records).unwrap()))
+			.collect::<Vec<_>>();
+		let mut sent = Vec::new();
+		while let Some(query) = queries.pop() {
+			let (addrs, apex, zone) = zones.iter().find(|(addrs, _, _)| addrs.contains(&query.servers[0])).unwrap();
+			assert_eq!(&query.servers, addrs);
+			let mut read = &query.query[12..];
+			let name = read_wire_packet_name(&mut read, &query.query).unwrap();
+			sent.push((name.as_str().to_owned(), read_u16(&mut read).unwrap(), apex.as_str().to_owned()));
+			let resp = authoritative_response(&query.query, apex, zone);
+			queries.extend(builder.process_iterative_response(&resp).unwrap());
+		}
+		assert!(!builder.awaiting_responses());
+		sent
+	}
+
+	#[test]
+	fn test_iterative_no_data() {
+		let servers = start_authoritative_servers();
+		let config = root_hints_config(&ROOT);
+
+		// example.com. has no AAAA records, which its signed NSEC record proves
+		let question = [("example.com.".try_into().unwrap(), AAAA::TYPE)];
+		let (proof, ttl) = build_test_proof(&servers, &config, &question).unwrap();
+		let mut rrs = parse_rr_stream(&proof).unwrap();
+		rrs.sort_unstable();
+		let mut expected_rrs = test_zones().into_iter()
+			.flat_map(|(_, _, records)| parse_zone_file(&records).unwrap())
+			.filter(|rr| covered_ty(rr) == DnsKey::TYPE || covered_ty(rr) == DS::TYPE
+				|| (is_denial_rr(rr) && rr.name().as_str() == "example.com."))
+			.collect::<Vec<_>>();
+		expected_rrs.sort_unstable();
+		assert_eq!(rrs, expected_rrs);
+		// The SOA's minimum TTL limits how long the denial can be cached
+		assert_eq!(ttl, 300);
+
+		// But net. isn't signed, so we can't prove that ns.example.net. has no TXT records
+		let question = [("ns.example.net.".try_into().unwrap(), Txt::TYPE)];
+		let err = build_test_proof(&servers, &config, &question).unwrap_err();
+		let err = err.get_ref().and_then(|err| err.downcast_ref::<ProofBuildingError>());
+		assert!(err == Some(&ProofBuildingError::MissingRecord));
+	}
+
+	#[test]
+	fn test_iterative_queries() {
+		// Walk through the queries the builder makes, checking each is sent to the right servers
+		// and that responses with the AD bit unset are accepted.
+		let query_name = "example.com.".try_into().unwrap();
+		let (mut builder, initial_query) = ProofBuilder::new_iterative(&query_name, Txt::TYPE, &ROOT);
+		assert!(builder.process_response(&initial_query.query).is_err());
+
+		let sent = make_queries(&mut builder, vec![initial_query]);
+		let (proof, ttl) = builder.finish_proof().unwrap();
+		check_proof(&proof, ttl, false);
+
+		let query = |name: &str, ty, apex: &str| (name.to_owned(), ty, apex.to_owned());
+		assert_eq!(sent, vec![
+			query("example.com.", Txt::TYPE, "."),
+			query("example.com.", Txt::TYPE, "com."),
+			query("ns.example.net.", AAAA::TYPE, "."),
+			query("ns.example.net.", AAAA::TYPE, "net."),
+			query("ns.example.net.", A::TYPE, "."),
+			query("ns.example.net.", A::TYPE, "net."),
+			query("example.com.", Txt::TYPE, "example.com."),
+			query("example.com.", DS::TYPE, "com."),
+			query("com.", DS::TYPE, "."),
+			query(".", DnsKey::TYPE, "."),
+			query("com.", DnsKey::TYPE, "com."),
+			query("example.com.", DnsKey::TYPE, "example.com."),
+		]);
+	}
+
+	#[test]
+	fn test_iterative_multi_question_queries() {
+		// Questions which need the same queries (here because `www.example.com.` is a CNAME to
+		// `example.com.`) only make them once, even while they're outstanding.
+		let questions = [
+			("example.com.".try_into().unwrap(), Txt::TYPE),
+			("www.example.com.".try_into().unwrap(), Txt::TYPE),
+			("example.com.".try_into().unwrap(), Txt::TYPE),
+		];
+		let (mut builder, initial_queries) = ProofBuilder::new_iterative_for_questions(&questions, &ROOT);
+		assert_eq!(initial_queries.len(), 2);
+
+		let mut sent = make_queries(&mut builder, initial_queries);
+		let (proof, ttl) = builder.finish_proof().unwrap();
+		check_proof(&proof, ttl, true);
+
+		let sent_count = sent.len();
+		sent.sort_unstable();
+		sent.dedup();
+		assert_eq!(sent.len(), sent_count);
+	}
+
+	#[test]
+	fn test_iterative_bogus_referrals() {
+		let query_name: Name = "example.com.".try_into().unwrap();
+		let (mut builder, initial_query) = ProofBuilder::new_iterative(&query_name, Txt::TYPE, &ROOT);
+
+		// Referrals back up the tree, to some other part of it, or below the name we queried are
+		// rejected (as they'd never lead to an answer).
+		for (zone, ns) in [(".", "a.root-servers.net."), ("org.", "ns.org."), ("www.example.com.", "ns.com.")] {
+			let referral = parse_zone_file(&format!("{zone} 3600 IN NS {ns}\n{ns} 3600 IN A 192.0.2.1\n")).unwrap();
+			let resp = response(&initial_query.query, 0x8000, &[], &[&referral[0]], &[&referral[1]]);
+			assert!(builder.clone().process_iterative_response(&resp) == Err(ProofBuildingError::InvalidResponse));
+		}
+		// As are referrals without any usable name servers
+		let referral = parse_zone_file("com. 3600 IN NS ns.com.\n").unwrap();
+		let resp = response(&initial_query.query, 0x8000, &[], &[&referral[0]], &[]);
+		assert!(builder.clone().process_iterative_response(&resp) == Err(ProofBuildingError::InvalidResponse));
+
+		// Server failures leave the query pending, so that another server can be tried
+		let resp = response(&initial_query.query, 0x8402, &[], &[], &[]); // SERVFAIL
+		assert!(builder.process_iterative_response(&resp) == Err(ProofBuildingError::ServerFailure));
+		assert!(builder.awaiting_responses());
+		let resp = response(&initial_query.query, 0x8403, &[], &[], &[]); // NXDOMAIN
+		assert!(builder.process_iterative_response(&resp) == Err(ProofBuildingError::NoSuchName));
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_iterative_proof_async() {
+		let servers = start_authoritative_servers();
+		let www = "www.example.com.".try_into().unwrap();
+		let hints = [IpAddr::V4(Ipv4Addr::new(192, 0, 2, 5)), ROOT[0]];
+		let (proof, ttl) = build_proof_iterative_with_transport_async(&root_hints_config(&hints), &[(www, Txt::TYPE)],
+			|server, timeout| {
+				let addr = test_server_addr(&servers, server);
+				async move { connect_udp_async(addr?, timeout).await }
+			}).await.unwrap();
+		check_proof(&proof, ttl, true);
+	}
+}
@@ -0,0 +1,23 @@
+//! TLS configuration shared by the DNS-over-HTTPS and DNS-over-TLS drivers.
+
+use alloc::sync::Arc;
+
+use rustls::{ClientConfig, ConfigBuilder, RootCertStore, WantsVerifier};
+use rustls::crypto::CryptoProvider;
+
+/// The crypto provider used for all our TLS connections.
+pub(super) fn crypto_provider() -> Arc<CryptoProvider> {
+	Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// Starts building a [`ClientConfig`] using [`crypto_provider`] and the default protocol versions.
+pub(super) fn config_builder() -> ConfigBuilder<ClientConfig, WantsVerifier> {
+	ClientConfig::builder_with_provider(crypto_provider())
+		.with_safe_default_protocol_versions()
+		.expect("The ring provider supports the default protocol versions")
+}
+
+/// The Mozilla root certificates, as provided by `webpki-roots`.
+pub(super) fn mozilla_roots() -> RootCertStore {
+	RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() }
+}
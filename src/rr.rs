@@ -5,7 +5,7 @@
 
 use alloc::vec::Vec;
 use alloc::string::String;
-use alloc::borrow::ToOwned;
+use alloc::borrow::{Cow, ToOwned};
 use alloc::format;
 
 use core::cmp::Ordering;
@@ -17,66 +17,310 @@ use crate::ser::*;
 
 /// A valid domain name.
 ///
-/// It must end with a ".", be no longer than 255 bytes, consist of only printable ASCII
-/// characters and each label may be no longer than 63 bytes.
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Name(String);
+/// It is stored in wire format (a series of length-prefixed labels ending with the empty root
+/// label), with ASCII letters lowercased, alongside its RFC 1035 presentation form. Labels may
+/// contain arbitrary bytes - in the presentation form `.`, `\` and a few other special
+/// characters are escaped with a `\` and bytes which are not printable ASCII are written as
+/// `\DDD`, where `DDD` is the decimal value of the byte.
+///
+/// The presentation form must end with a ".". The wire form may be no longer than 255 bytes and
+/// each label may be no longer than 63 bytes.
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub struct Name {
+	wire: Vec<u8>,
+	presentation: String,
+}
 impl Name {
 	/// Gets the underlying human-readable domain name
-	pub fn as_str(&self) -> &str { &self.0 }
+	pub fn as_str(&self) -> &str { &self.presentation }
+	/// Gets the (canonical, i.e. lowercased) wire encoding of this name.
+	pub(crate) fn wire(&self) -> &[u8] { &self.wire }
 	/// Gets the number of labels in this name
 	pub fn labels(&self) -> u8 {
-		if self.as_str() == "." {
-			0
-		} else {
-			self.as_str().chars().filter(|c| *c == '.').count() as u8
+		self.iter_labels().count() as u8
+	}
+	/// Gets the offsets into the wire and presentation forms at which the last `n` labels start.
+	fn trailing_n_labels_offsets(&self, n: u8) -> Option<(usize, usize)> {
+		let labels = self.labels();
+		if n > labels { return None; }
+		if n == 0 { return Some((self.wire.len() - 1, self.presentation.len() - 1)); }
+		let (mut wire_offset, mut presentation_offset) = (0, 0);
+		for _ in 0..labels - n {
+			let len = self.wire[wire_offset] as usize;
+			presentation_offset += escaped_label_len(&self.wire[wire_offset + 1..wire_offset + 1 + len]) + 1;
+			wire_offset += len + 1;
 		}
+		Some((wire_offset, presentation_offset))
 	}
 	/// Gets a string containing the last `n` labels in this [`Name`] (which is also a valid name).
 	pub fn trailing_n_labels(&self, n: u8) -> Option<&str> {
-		let labels = self.labels();
-		if n > labels {
-			None
-		} else if n == labels {
-			Some(self.as_str())
-		} else if n == 0 {
-			Some(".")
+		self.trailing_n_labels_offsets(n).map(|(_, offset)| &self.presentation[offset..])
+	}
+	/// Gets the wire encoding of the last `n` labels in this [`Name`].
+	pub(crate) fn trailing_n_labels_wire(&self, n: u8) -> Option<&[u8]> {
+		self.trailing_n_labels_offsets(n).map(|(offset, _)| &self.wire[offset..])
+	}
+	/// Gets an iterator over the (raw, unescaped) labels in this name, from the leftmost label up
+	/// to (but not including) the empty root label.
+	pub fn iter_labels(&self) -> LabelIter<'_> {
+		LabelIter { remaining: &self.wire }
+	}
+	/// Gets the name one level up from this one (i.e. this name with its leftmost label removed),
+	/// or `None` if this is the root.
+	pub fn parent(&self) -> Option<Name> {
+		let first_label_len = self.wire[0] as usize;
+		if first_label_len == 0 { return None; }
+		Some(Name::from_wire(&self.wire[first_label_len + 1..]).expect("Suffixes of valid names are valid"))
+	}
+	/// Returns true if this name is equal to or below `zone` in the DNS hierarchy.
+	///
+	/// Unlike a simple string suffix check, this only matches on label boundaries, so
+	/// `example.com.` is a subdomain of `com.` but not of `ample.com.`.
+	pub fn is_subdomain_of(&self, zone: &Name) -> bool {
+		self.trailing_n_labels_wire(zone.labels()) == Some(zone.wire())
+	}
+	/// Builds a new name consisting of the labels in this name followed by the labels in
+	/// `suffix`, e.g. `www.` joined with `example.com.` is `www.example.com.`.
+	///
+	/// Fails if the resulting name would be too long.
+	pub fn join(&self, suffix: &Name) -> Result<Name, ()> {
+		let mut wire = Vec::with_capacity(self.wire.len() + suffix.wire.len() - 1);
+		wire.extend_from_slice(&self.wire[..self.wire.len() - 1]);
+		wire.extend_from_slice(&suffix.wire);
+		Name::from_wire(&wire)
+	}
+	/// Gets the wildcard name directly below this name (i.e. `*.` followed by this name), whose
+	/// records would be used to synthesize answers for nonexistent children of this name.
+	///
+	/// Fails if the resulting name would be too long.
+	pub fn wildcard_of(&self) -> Result<Name, ()> {
+		let mut wire = Vec::with_capacity(self.wire.len() + 2);
+		wire.extend_from_slice(b"\x01*");
+		wire.extend_from_slice(&self.wire);
+		Name::from_wire(&wire)
+	}
+	/// Builds a [`Name`] from its wire encoding, lowercasing any ASCII letters.
+	///
+	/// `wire` must contain exactly one name, with no compression pointers.
+	pub(crate) fn from_wire(wire: &[u8]) -> Result<Name, ()> {
+		if wire.len() > 255 { return Err(()); }
+		let wire = wire.to_ascii_lowercase();
+		let mut presentation = String::with_capacity(wire.len());
+		let mut remaining = &wire[..];
+		loop {
+			let len = *remaining.first().ok_or(())? as usize;
+			if len == 0 { break; }
+			if len > 63 || remaining.len() <= len { return Err(()); }
+			write_escaped_label(&mut presentation, &remaining[1..len + 1]);
+			presentation.push('.');
+			remaining = &remaining[len + 1..];
+		}
+		if remaining.len() != 1 { return Err(()); }
+		if presentation.is_empty() { presentation.push('.'); }
+		Ok(Name { wire, presentation })
+	}
+	/// Builds a [`Name`] from a (possibly internationalized) domain name, as a user might type it.
+	///
+	/// Labels containing non-ASCII characters are mapped (e.g. lowercased) per UTS #46 and
+	/// converted to their punycode ("`xn--`") form. Unlike [`Name::try_from`], the trailing `.` is
+	/// optional and no presentation-format escapes are interpreted.
+	///
+	/// See the note in [`Self::to_unicode`] for limitations of our UTS #46 implementation.
+	pub fn from_unicode(name: &str) -> Result<Name, ()> {
+		Name::from_wire(&crate::idna::unicode_to_wire(name)?)
+	}
+	/// Gets the human-readable, internationalized form of this name, with any valid punycode
+	/// ("`xn--`") labels converted back to Unicode.
+	///
+	/// Labels which are not valid punycode are left in their (escaped) presentation form.
+	///
+	/// Note that we only implement the parts of UTS #46 which do not require large Unicode data
+	/// tables. Notably, names are not normalized (input is assumed to be in Unicode Normalization
+	/// Form C already) and bidirectional text rules are not enforced.
+	pub fn to_unicode(&self) -> String {
+		let mut res = String::with_capacity(self.presentation.len());
+		for label in self.iter_labels() {
+			if let Some(unicode) = crate::idna::label_to_unicode(label) {
+				res += &unicode;
+			} else {
+				write_escaped_label(&mut res, label);
+			}
+			res.push('.');
+		}
+		if res.is_empty() { res.push('.'); }
+		res
+	}
+	/// Gets the JSON fields describing this name as a record's owner name - `name` and, if it
+	/// contains internationalized labels, `unicode_name`.
+	pub(crate) fn json_owner_fields(&self) -> String {
+		let mut res = format!("\"name\":\"{}\"", self.json_str());
+		let unicode = self.to_unicode();
+		if unicode != self.presentation {
+			write!(&mut res, ",\"unicode_name\":\"{}\"", unicode.replace('\\', "\\\\").replace('"', "\\\""))
+				.expect("Write to a String shouldn't fail");
+		}
+		res
+	}
+	/// Gets the presentation form of this name, escaped for inclusion in a JSON string.
+	pub(crate) fn json_str(&self) -> Cow<'_, str> {
+		if self.presentation.contains(['\\', '"']) {
+			Cow::Owned(self.presentation.replace('\\', "\\\\").replace('"', "\\\""))
 		} else {
-			self.as_str().splitn(labels as usize - n as usize + 1, '.').last()
+			Cow::Borrowed(&self.presentation)
 		}
 	}
 }
+/// Returns true if the given label byte must be escaped with a `\` in presentation form.
+fn needs_escape(b: u8) -> bool {
+	matches!(b, b'.' | b'\\' | b'"' | b'(' | b')' | b';' | b'@' | b'$')
+}
+fn escaped_label_len(label: &[u8]) -> usize {
+	label.iter().map(|b| if needs_escape(*b) { 2 } else if b.is_ascii_graphic() { 1 } else { 4 }).sum()
+}
+fn write_escaped_label(out: &mut String, label: &[u8]) {
+	for b in label.iter() {
+		if needs_escape(*b) {
+			out.push('\\');
+			out.push(*b as char);
+		} else if b.is_ascii_graphic() {
+			out.push(*b as char);
+		} else {
+			write!(out, "\\{:03}", b).expect("Write to a String shouldn't fail");
+		}
+	}
+}
+/// Reads the byte encoded by a presentation-format escape (either `\X` or `\DDD`, where `DDD` is
+/// the decimal value of the byte) from `bytes`, which must be positioned just after the `\`.
+pub(crate) fn read_escaped_byte<I: Iterator<Item = u8>>(bytes: &mut I) -> Result<u8, ()> {
+	let c = bytes.next().ok_or(())?;
+	if c.is_ascii_digit() {
+		let (d2, d3) = (bytes.next().ok_or(())?, bytes.next().ok_or(())?);
+		if !d2.is_ascii_digit() || !d3.is_ascii_digit() { return Err(()); }
+		let val = (c - b'0') as u16 * 100 + (d2 - b'0') as u16 * 10 + (d3 - b'0') as u16;
+		val.try_into().map_err(|_| ())
+	} else if c.is_ascii_graphic() || c == b' ' {
+		Ok(c)
+	} else { Err(()) }
+}
+/// An iterator over the labels in a [`Name`], returned by [`Name::iter_labels`].
+#[derive(Clone)]
+pub struct LabelIter<'a> {
+	remaining: &'a [u8],
+}
+impl<'a> Iterator for LabelIter<'a> {
+	type Item = &'a [u8];
+	fn next(&mut self) -> Option<&'a [u8]> {
+		let len = *self.remaining.first()? as usize;
+		if len == 0 { return None; }
+		let label = &self.remaining[1..len + 1];
+		self.remaining = &self.remaining[len + 1..];
+		Some(label)
+	}
+}
+
+/// Splits a wire-encoded name into its labels, returning them (from the root up) in a fixed-size
+/// array along with the number of labels.
+fn wire_labels_reversed(mut name: &[u8]) -> ([&[u8]; 128], usize) {
+	let mut labels: [&[u8]; 128] = [&[]; 128];
+	let mut count = 0;
+	while let Some(len) = name.first() {
+		let len = *len as usize;
+		if len == 0 || name.len() <= len || count == labels.len() { break; }
+		labels[count] = &name[1..len + 1];
+		count += 1;
+		name = &name[len + 1..];
+	}
+	labels[..count].reverse();
+	(labels, count)
+}
+
+/// Compares two wire-encoded names in RFC 4034 canonical order.
+///
+/// Labels are compared from the root up, byte-wise, with ASCII letters compared as lowercase.
+pub(crate) fn canonical_name_cmp(a: &[u8], b: &[u8]) -> Ordering {
+	let (a_labels, a_count) = wire_labels_reversed(a);
+	let (b_labels, b_count) = wire_labels_reversed(b);
+	for (a_label, b_label) in a_labels[..a_count].iter().zip(b_labels[..b_count].iter()) {
+		let a_bytes = a_label.iter().map(|b| b.to_ascii_lowercase());
+		let b_bytes = b_label.iter().map(|b| b.to_ascii_lowercase());
+		match a_bytes.cmp(b_bytes) {
+			Ordering::Equal => {},
+			ord => return ord,
+		}
+	}
+	a_count.cmp(&b_count)
+}
+/// Names are ordered in RFC 4034 canonical order, i.e. by label from the root up, comparing
+/// labels as (lowercased) bytes.
+impl Ord for Name {
+	fn cmp(&self, o: &Name) -> Ordering { canonical_name_cmp(&self.wire, &o.wire) }
+}
+impl PartialOrd for Name {
+	fn partial_cmp(&self, o: &Name) -> Option<Ordering> { Some(self.cmp(o)) }
+}
 impl core::ops::Deref for Name {
 	type Target = str;
-	fn deref(&self) -> &str { &self.0 }
+	fn deref(&self) -> &str { &self.presentation }
 }
 impl fmt::Display for Name {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-		self.0.fmt(f)
+		self.presentation.fmt(f)
+	}
+}
+impl fmt::Debug for Name {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		f.debug_tuple("Name").field(&self.presentation).finish()
 	}
 }
 impl TryFrom<String> for Name {
 	type Error = ();
 	fn try_from(s: String) -> Result<Name, ()> {
-		if s.is_empty() { return Err(()); }
-		if *s.as_bytes().last().unwrap_or(&0) != b"."[0] { return Err(()); }
-		if s.len() > 255 { return Err(()); }
-		if s.chars().any(|c| !c.is_ascii_graphic() || c == '"') { return Err(()); }
-		for label in s.split('.') {
-			if label.len() > 63 { return Err(()); }
-		}
-
-		Ok(Name(s.to_ascii_lowercase()))
+		Self::try_from(&s[..])
 	}
 }
 impl TryFrom<&str> for Name {
 	type Error = ();
 	fn try_from(s: &str) -> Result<Name, ()> {
-		Self::try_from(s.to_owned())
+		if s.is_empty() { return Err(()); }
+		if s == "." { return Name::from_wire(&[0]); }
+		let mut wire = Vec::with_capacity(s.len() + 1);
+		let mut label_start = 0;
+		wire.push(0);
+		let mut bytes = s.bytes();
+		while let Some(b) = bytes.next() {
+			if b == b'.' {
+				let label_len = wire.len() - label_start - 1;
+				if label_len == 0 || label_len > 63 { return Err(()); }
+				wire[label_start] = label_len as u8;
+				label_start = wire.len();
+				wire.push(0);
+			} else if b == b'\\' {
+				wire.push(read_escaped_byte(&mut bytes)?);
+			} else if b.is_ascii_graphic() && b != b'"' {
+				wire.push(b);
+			} else { return Err(()); }
+		}
+		// The name must have ended with an (unescaped) ".", leaving only the root label
+		if wire.len() != label_start + 1 { return Err(()); }
+		Name::from_wire(&wire)
+	}
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for Name {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.presentation)
+	}
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Name {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+		Name::try_from(name).map_err(|()| serde::de::Error::custom("invalid DNS name"))
 	}
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A supported Resource Record
 ///
 /// Note that we only currently support a handful of RR types as needed to generate and validate
@@ -88,6 +332,8 @@ pub enum RR {
 	AAAA(AAAA),
 	/// A name server resource record
 	NS(NS),
+	/// A start of authority resource record
+	SOA(SOA),
 	/// A text resource record
 	Txt(Txt),
 	/// A TLS Certificate Association resource record
@@ -96,6 +342,8 @@ pub enum RR {
 	CName(CName),
 	/// A Delegation Name record
 	DName(DName),
+	/// A Pointer record
+	PTR(PTR),
 	/// A DNS (Public) Key resource record
 	DnsKey(DnsKey),
 	/// A Delegated Signer resource record
@@ -106,6 +354,22 @@ pub enum RR {
 	NSec(NSec),
 	/// A Next Secure Record version 3 record
 	NSec3(NSec3),
+	/// An S/MIME Certificate Association resource record
+	SMIMEA(SMIMEA),
+	/// An OpenPGP public key resource record
+	OpenPGPKey(OpenPGPKey),
+	/// A Uniform Resource Identifier resource record
+	URI(URI),
+	/// A Naming Authority Pointer resource record
+	NAPTR(NAPTR),
+	/// A Certificate resource record
+	CERT(CERT),
+	/// A Child DS resource record
+	CDS(CDS),
+	/// A Child DNS (Public) Key resource record
+	CDnsKey(CDnsKey),
+	/// A Zone Message Digest resource record
+	ZoneMD(ZoneMD),
 }
 impl RR {
 	/// Gets the name this record refers to.
@@ -114,48 +378,116 @@ impl RR {
 			RR::A(rr) => &rr.name,
 			RR::AAAA(rr) => &rr.name,
 			RR::NS(rr) => &rr.name,
+			RR::SOA(rr) => &rr.name,
 			RR::Txt(rr) => &rr.name,
 			RR::CName(rr) => &rr.name,
 			RR::DName(rr) => &rr.name,
+			RR::PTR(rr) => &rr.name,
 			RR::TLSA(rr) => &rr.name,
 			RR::DnsKey(rr) => &rr.name,
 			RR::DS(rr) => &rr.name,
 			RR::RRSig(rr) => &rr.name,
 			RR::NSec(rr) => &rr.name,
 			RR::NSec3(rr) => &rr.name,
+			RR::SMIMEA(rr) => &rr.name,
+			RR::OpenPGPKey(rr) => &rr.name,
+			RR::URI(rr) => &rr.name,
+			RR::NAPTR(rr) => &rr.name,
+			RR::CERT(rr) => &rr.name,
+			RR::CDS(rr) => &rr.name,
+			RR::CDnsKey(rr) => &rr.name,
+			RR::ZoneMD(rr) => &rr.name,
 		}
 	}
 	/// Gets a JSON encoding of this record
+	///
+	/// The encoding is a single object with a `"type"` field containing the lowercase record type
+	/// (e.g. `"a"`, `"txt"` or `"rrsig"`) and a `"name"` field containing the owner name in
+	/// presentation form (with a trailing `.`). If the owner name contains punycode labels a
+	/// `"unicode_name"` field with its Unicode form is also included.
+	///
+	/// The remaining fields are named after the record's fields (see [`Self::from_json`] for
+	/// cases where they differ) and are encoded as:
+	///  * integers for numeric fields,
+	///  * strings in presentation form for names,
+	///  * strings of uppercase hex for opaque binary data, e.g. keys, digests and signatures,
+	///  * arrays of byte values for addresses and [`NSec3`] salts and hashes,
+	///  * for character-strings (e.g. [`Txt`] contents or [`NAPTR`] fields), a string if all bytes
+	///    are printable ASCII, otherwise an array of byte values,
+	///  * for [`NSecTypeMask`]s, an array of the record types included, as uppercase mnemonics
+	///    for types we support and integers for other types.
 	pub fn json(&self) -> String {
 		match self {
 			RR::A(rr) => StaticRecord::json(rr),
 			RR::AAAA(rr) => StaticRecord::json(rr),
 			RR::NS(rr) => StaticRecord::json(rr),
+			RR::SOA(rr) => StaticRecord::json(rr),
 			RR::Txt(rr) => StaticRecord::json(rr),
 			RR::CName(rr) => StaticRecord::json(rr),
 			RR::DName(rr) => StaticRecord::json(rr),
+			RR::PTR(rr) => StaticRecord::json(rr),
 			RR::TLSA(rr) => StaticRecord::json(rr),
 			RR::DnsKey(rr) => StaticRecord::json(rr),
 			RR::DS(rr) => StaticRecord::json(rr),
 			RR::RRSig(rr) => StaticRecord::json(rr),
 			RR::NSec(rr) => StaticRecord::json(rr),
 			RR::NSec3(rr) => StaticRecord::json(rr),
+			RR::SMIMEA(rr) => StaticRecord::json(rr),
+			RR::OpenPGPKey(rr) => StaticRecord::json(rr),
+			RR::URI(rr) => StaticRecord::json(rr),
+			RR::NAPTR(rr) => StaticRecord::json(rr),
+			RR::CERT(rr) => StaticRecord::json(rr),
+			RR::CDS(rr) => StaticRecord::json(rr),
+			RR::CDnsKey(rr) => StaticRecord::json(rr),
+			RR::ZoneMD(rr) => StaticRecord::json(rr),
 		}
 	}
+	/// Parses a record from the JSON encoding returned by [`Self::json`].
+	///
+	/// Fields may appear in any order, and the same checks are applied as when parsing a record
+	/// from the wire. Note that the split of [`Txt`] contents into character-strings is not
+	/// included in the JSON encoding, so a long [`Txt`] record may differ from its original form
+	/// in how its contents are split.
+	///
+	/// Fields whose names don't directly match the record's fields are:
+	///  * `"ns"` for [`NS::name_server`],
+	///  * `"contents"` for [`Txt::data`],
+	///  * `"usage"` for [`TLSA::cert_usage`] and [`SMIMEA::cert_usage`],
+	///  * `"signed_record_type"` for [`RRSig::ty`] and `"signed_labels"` for [`RRSig::labels`].
+	pub fn from_json(json: &str) -> Result<RR, ()> {
+		crate::json::parse_rr(json)
+	}
+	/// Gets the record type for the given mnemonic (e.g. `"TXT"`, ignoring case), if it is one of
+	/// the types supported by this library (i.e. one which has an [`RR`] variant).
+	///
+	/// The resulting type can be passed to [`crate::query::ProofBuilder::new`].
+	pub fn ty_from_name(name: &str) -> Option<u16> {
+		RR_TYPES.iter().copied().find(|ty| RR::ty_to_rr_name(*ty).unwrap_or("").eq_ignore_ascii_case(name))
+	}
 	fn ty(&self) -> u16 {
 		match self {
 			RR::A(_) => A::TYPE,
 			RR::AAAA(_) => AAAA::TYPE,
 			RR::NS(_) => NS::TYPE,
+			RR::SOA(_) => SOA::TYPE,
 			RR::Txt(_) => Txt::TYPE,
 			RR::CName(_) => CName::TYPE,
 			RR::DName(_) => DName::TYPE,
+			RR::PTR(_) => PTR::TYPE,
 			RR::TLSA(_) => TLSA::TYPE,
 			RR::DnsKey(_) => DnsKey::TYPE,
 			RR::DS(_) => DS::TYPE,
 			RR::RRSig(_) => RRSig::TYPE,
 			RR::NSec(_) => NSec::TYPE,
 			RR::NSec3(_) => NSec3::TYPE,
+			RR::SMIMEA(_) => SMIMEA::TYPE,
+			RR::OpenPGPKey(_) => OpenPGPKey::TYPE,
+			RR::URI(_) => URI::TYPE,
+			RR::NAPTR(_) => NAPTR::TYPE,
+			RR::CERT(_) => CERT::TYPE,
+			RR::CDS(_) => CDS::TYPE,
+			RR::CDnsKey(_) => CDnsKey::TYPE,
+			RR::ZoneMD(_) => ZoneMD::TYPE,
 		}
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
@@ -163,31 +495,51 @@ impl RR {
 			RR::A(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::AAAA(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::NS(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::SOA(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::Txt(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::CName(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::DName(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::PTR(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::TLSA(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::DnsKey(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::DS(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::RRSig(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::NSec(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 			RR::NSec3(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::SMIMEA(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::OpenPGPKey(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::URI(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::NAPTR(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::CERT(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::CDS(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::CDnsKey(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
+			RR::ZoneMD(rr) => StaticRecord::write_u16_len_prefixed_data(rr, out),
 		}
 	}
-	fn ty_to_rr_name(ty: u16) -> Option<&'static str> {
+	pub(crate) fn ty_to_rr_name(ty: u16) -> Option<&'static str> {
 		match ty {
 			A::TYPE => Some("A"),
 			AAAA::TYPE => Some("AAAA"),
 			NS::TYPE => Some("NS"),
+			SOA::TYPE => Some("SOA"),
 			Txt::TYPE => Some("TXT"),
 			CName::TYPE => Some("CNAME"),
 			DName::TYPE => Some("DNAME"),
+			PTR::TYPE => Some("PTR"),
 			TLSA::TYPE => Some("TLSA"),
 			DnsKey::TYPE => Some("DNSKEY"),
 			DS::TYPE => Some("DS"),
 			RRSig::TYPE => Some("RRSIG"),
 			NSec::TYPE => Some("NSEC"),
 			NSec3::TYPE => Some("NSEC3"),
+			SMIMEA::TYPE => Some("SMIMEA"),
+			OpenPGPKey::TYPE => Some("OPENPGPKEY"),
+			URI::TYPE => Some("URI"),
+			NAPTR::TYPE => Some("NAPTR"),
+			CERT::TYPE => Some("CERT"),
+			CDS::TYPE => Some("CDS"),
+			CDnsKey::TYPE => Some("CDNSKEY"),
+			ZoneMD::TYPE => Some("ZONEMD"),
 			_ => None,
 		}
 	}
@@ -195,15 +547,25 @@ impl RR {
 impl From<A> for RR { fn from(a: A) -> RR { RR::A(a) } }
 impl From<AAAA> for RR { fn from(aaaa: AAAA) -> RR { RR::AAAA(aaaa) } }
 impl From<NS> for RR { fn from(ns: NS) -> RR { RR::NS(ns) } }
+impl From<SOA> for RR { fn from(soa: SOA) -> RR { RR::SOA(soa) } }
 impl From<Txt> for RR { fn from(txt: Txt) -> RR { RR::Txt(txt) } }
 impl From<CName> for RR { fn from(cname: CName) -> RR { RR::CName(cname) } }
 impl From<DName> for RR { fn from(cname: DName) -> RR { RR::DName(cname) } }
+impl From<PTR> for RR { fn from(ptr: PTR) -> RR { RR::PTR(ptr) } }
 impl From<TLSA> for RR { fn from(tlsa: TLSA) -> RR { RR::TLSA(tlsa) } }
 impl From<DnsKey> for RR { fn from(dnskey: DnsKey) -> RR { RR::DnsKey(dnskey) } }
 impl From<DS> for RR { fn from(ds: DS) -> RR { RR::DS(ds) } }
 impl From<RRSig> for RR { fn from(rrsig: RRSig) -> RR { RR::RRSig(rrsig) } }
 impl From<NSec> for RR { fn from(nsec: NSec) -> RR { RR::NSec(nsec) } }
 impl From<NSec3> for RR { fn from(nsec3: NSec3) -> RR { RR::NSec3(nsec3) } }
+impl From<SMIMEA> for RR { fn from(smimea: SMIMEA) -> RR { RR::SMIMEA(smimea) } }
+impl From<OpenPGPKey> for RR { fn from(key: OpenPGPKey) -> RR { RR::OpenPGPKey(key) } }
+impl From<URI> for RR { fn from(uri: URI) -> RR { RR::URI(uri) } }
+impl From<NAPTR> for RR { fn from(naptr: NAPTR) -> RR { RR::NAPTR(naptr) } }
+impl From<CERT> for RR { fn from(cert: CERT) -> RR { RR::CERT(cert) } }
+impl From<CDS> for RR { fn from(cds: CDS) -> RR { RR::CDS(cds) } }
+impl From<CDnsKey> for RR { fn from(cdnskey: CDnsKey) -> RR { RR::CDnsKey(cdnskey) } }
+impl From<ZoneMD> for RR { fn from(zonemd: ZoneMD) -> RR { RR::ZoneMD(zonemd) } }
 
 pub(crate) trait StaticRecord : Ord + Sized {
 	// http://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-4
@@ -215,14 +577,17 @@ pub(crate) trait StaticRecord : Ord + Sized {
 }
 
 /// A record that can be written to a generic [`Writer`]
+#[cfg(feature = "validation")]
 pub(crate) trait WriteableRecord : Record {
 	fn serialize_u16_len_prefixed<W: Writer>(&self, out: &mut W);
 }
+#[cfg(feature = "validation")]
 impl<RR: StaticRecord> WriteableRecord for RR {
 	fn serialize_u16_len_prefixed<W: Writer>(&self, out: &mut W) {
 		RR::write_u16_len_prefixed_data(self, out)
 	}
 }
+#[cfg(feature = "validation")]
 impl WriteableRecord for RR {
 	fn serialize_u16_len_prefixed<W: Writer>(&self, out: &mut W) {
 		RR::write_u16_len_prefixed_data(self, out)
@@ -260,6 +625,24 @@ impl Record for RR {
 	}
 }
 
+#[cfg(dnssec_prover_fuzzing)]
+/// Read some input and parse it as JSON and as a stream of records, checking that any records
+/// round-trip through [`RR::json`] and [`RR::from_json`], for fuzzing.
+pub fn fuzz_json_round_trip(data: &[u8]) {
+	if let Ok(json) = core::str::from_utf8(data) {
+		if let Ok(rr) = RR::from_json(json) {
+			assert_eq!(RR::from_json(&rr.json()), Ok(rr));
+		}
+	}
+	if let Ok(rrs) = crate::ser::parse_rr_stream(data) {
+		for rr in rrs {
+			let json = rr.json();
+			let parsed = RR::from_json(&json).expect("Any JSON we write must be readable");
+			assert_eq!(parsed.json(), json);
+		}
+	}
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct TxtBytePart {
 	/// The bytes themselves.
@@ -288,7 +671,7 @@ impl TxtBytes {
 	pub fn new(bytes: &[u8]) -> Result<TxtBytes, ()> {
 		if bytes.len() > 255*255 + 254 { return Err(()); }
 		let mut chunks = Vec::with_capacity((bytes.len() + 254) / 255);
-		let mut data_write = &bytes[..];
+		let mut data_write = bytes;
 		while !data_write.is_empty() {
 			let split_pos = core::cmp::min(255, data_write.len());
 			let mut part = TxtBytePart {
@@ -312,6 +695,11 @@ impl TxtBytes {
 		res
 	}
 
+	/// Returns true if there are no bytes in this record.
+	pub fn is_empty(&self) -> bool {
+		self.chunks.is_empty()
+	}
+
 	/// The length of the bytes when serialized on the wire.
 	pub fn serialized_len(&self) -> u16 {
 		let mut len = 0u16;
@@ -355,6 +743,20 @@ impl TryFrom<&[u8]> for TxtBytes {
 	}
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for TxtBytes {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		crate::serde_util::hex::serialize(&self.as_vec(), serializer)
+	}
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TxtBytes {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let bytes = crate::serde_util::hex::deserialize(deserializer)?;
+		TxtBytes::new(&bytes).map_err(|()| serde::de::Error::custom("too many bytes for a TXT record"))
+	}
+}
+
 /// An iterator over the bytes in a [`TxtBytes`]
 pub struct TxtBytesIter<'a> {
 	bytes: &'a TxtBytes,
@@ -382,6 +784,7 @@ impl<'a> Iterator for TxtBytesIter<'a> {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A text resource record, containing arbitrary text data
 pub struct Txt {
 	/// The name this record is at.
@@ -426,10 +829,11 @@ impl StaticRecord for Txt {
 	const TYPE: u16 = TXT_TYPE;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		let mut res = format!("{{\"type\":\"txt\",\"name\":\"{}\",\"contents\":", self.name.0);
-		if self.data.iter().all(|b| b >= 0x20 && b <= 0x7e) {
+		let mut res = format!("{{\"type\":\"txt\",{},\"contents\":", self.name.json_owner_fields());
+		if self.data.iter().all(|b| (0x20..=0x7e).contains(&b)) {
 			res += "\"";
 			for b in self.data.iter() {
+				if b == b'"' || b == b'\\' { res.push('\\'); }
 				res.push(b as char);
 			}
 			res += "\"}";
@@ -479,6 +883,7 @@ impl StaticRecord for Txt {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A TLS Certificate Association resource record containing information about the TLS certificate
 /// which should be expected when communicating with the host at the given name.
 ///
@@ -495,6 +900,7 @@ pub struct TLSA {
 	/// The type of data included which is used to match the TLS certificate(s).
 	pub data_ty: u8,
 	/// The certificate data or hash of the certificate data itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub data: Vec<u8>,
 }
 /// The wire type for TLSA records
@@ -505,8 +911,8 @@ impl StaticRecord for TLSA {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(128+self.data.len()*2);
 		write!(&mut out,
-			"{{\"type\":\"tlsa\",\"name\":\"{}\",\"usage\":{},\"selector\":{},\"data_ty\":{},\"data\":\"",
-			self.name.0, self.cert_usage, self.selector, self.data_ty
+			"{{\"type\":\"tlsa\",{},\"usage\":{},\"selector\":{},\"data_ty\":{},\"data\":\"",
+			self.name.json_owner_fields(), self.cert_usage, self.selector, self.data_ty
 		).expect("Write to a String shouldn't fail");
 		for c in self.data.iter() {
 			write!(&mut out, "{:02X}", c)
@@ -529,25 +935,354 @@ impl StaticRecord for TLSA {
 	}
 }
 
+/// Maps an email address to the name at which per-user records for it are stored, as defined in
+/// RFC 7929 and RFC 8162.
+///
+/// The local-part is hashed with SHA-256, truncated to 28 bytes and hex-encoded, then followed by
+/// `service_label` and the (domain) part after the final `@`.
+#[cfg(feature = "validation")]
+fn email_to_hashed_name(email: &str, service_label: &str) -> Result<Name, ()> {
+	let (local_part, domain) = email.rsplit_once('@').ok_or(())?;
+	if local_part.is_empty() || domain.is_empty() || domain.starts_with('.') { return Err(()); }
+
+	let mut hasher = crate::crypto::hash::Hasher::sha256();
+	hasher.update(local_part.as_bytes());
+	let hash = hasher.finish();
+
+	let mut name = String::with_capacity(28*2 + 1 + service_label.len() + 1 + domain.len() + 1);
+	for b in hash.as_ref()[..28].iter() {
+		write!(&mut name, "{:02x}", b).expect("Write to a String shouldn't fail");
+	}
+	name += ".";
+	name += service_label;
+	name += ".";
+	name += domain;
+	if !domain.ends_with('.') { name += "."; }
+	name.try_into()
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An S/MIME Certificate Association resource record containing information about the S/MIME
+/// certificate which should be expected for a given email address.
+///
+/// The records are stored at a name derived from the email address, see [`Self::name_for_email`].
+///
+/// See [RFC 8162](https://www.rfc-editor.org/rfc/rfc8162.html) for more info.
+pub struct SMIMEA {
+	/// The name this record is at.
+	pub name: Name,
+	/// The type of constraint on the S/MIME certificate(s) used which should be enforced by this
+	/// record.
+	pub cert_usage: u8,
+	/// Whether to match on the full certificate, or only the public key.
+	pub selector: u8,
+	/// The type of data included which is used to match the S/MIME certificate(s).
+	pub data_ty: u8,
+	/// The certificate data or hash of the certificate data itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub data: Vec<u8>,
+}
+/// The wire type for SMIMEA records
+pub const SMIMEA_TYPE: u16 = 53;
+impl SMIMEA {
+	/// Gets the name at which [`SMIMEA`] records for the given email address are stored.
+	///
+	/// Fails if the email address does not contain an `@` or the resulting name is invalid.
+	#[cfg(feature = "validation")]
+	pub fn name_for_email(email: &str) -> Result<Name, ()> {
+		email_to_hashed_name(email, "_smimecert")
+	}
+}
+impl StaticRecord for SMIMEA {
+	const TYPE: u16 = SMIMEA_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.data.len()*2);
+		write!(&mut out,
+			"{{\"type\":\"smimea\",{},\"usage\":{},\"selector\":{},\"data_ty\":{},\"data\":\"",
+			self.name.json_owner_fields(), self.cert_usage, self.selector, self.data_ty
+		).expect("Write to a String shouldn't fail");
+		for c in self.data.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		Ok(SMIMEA {
+			name, cert_usage: read_u8(&mut data)?, selector: read_u8(&mut data)?,
+			data_ty: read_u8(&mut data)?, data: data.to_vec(),
+		})
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 3 + self.data.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&[self.cert_usage, self.selector, self.data_ty]);
+		out.write(&self.data);
+	}
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An OpenPGP public key resource record, containing a transferable OpenPGP public key for a
+/// given email address.
+///
+/// The records are stored at a name derived from the email address, see [`Self::name_for_email`].
+///
+/// See [RFC 7929](https://www.rfc-editor.org/rfc/rfc7929.html) for more info.
+pub struct OpenPGPKey {
+	/// The name this record is at.
+	pub name: Name,
+	/// The OpenPGP transferable public key itself, in binary (non-ASCII-armored) form.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub pubkey: Vec<u8>,
+}
+/// The wire type for OPENPGPKEY records
+pub const OPENPGPKEY_TYPE: u16 = 61;
+impl OpenPGPKey {
+	/// Gets the name at which [`OpenPGPKey`] records for the given email address are stored.
+	///
+	/// Fails if the email address does not contain an `@` or the resulting name is invalid.
+	#[cfg(feature = "validation")]
+	pub fn name_for_email(email: &str) -> Result<Name, ()> {
+		email_to_hashed_name(email, "_openpgpkey")
+	}
+}
+impl StaticRecord for OpenPGPKey {
+	const TYPE: u16 = OPENPGPKEY_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.pubkey.len()*2);
+		write!(&mut out, "{{\"type\":\"openpgpkey\",{},\"pubkey\":\"", self.name.json_owner_fields())
+			.expect("Write to a String shouldn't fail");
+		for c in self.pubkey.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		Ok(OpenPGPKey { name, pubkey: data.to_vec() })
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		out.write(&(self.pubkey.len() as u16).to_be_bytes());
+		out.write(&self.pubkey);
+	}
+}
+
+/// Writes the given bytes as a JSON string if they're all printable ASCII, otherwise as an array
+/// of integers.
+fn write_json_bytes(out: &mut String, bytes: &[u8]) {
+	if bytes.iter().all(|b| (0x20..=0x7e).contains(b)) {
+		*out += "\"";
+		for b in bytes.iter() {
+			if *b == b'"' || *b == b'\\' { out.push('\\'); }
+			out.push(*b as char);
+		}
+		*out += "\"";
+	} else {
+		write!(out, "{:?}", bytes).expect("Write to a String shouldn't fail");
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Uniform Resource Identifier resource record, mapping a (generally service-specific) name to
+/// a URI.
+///
+/// See [RFC 7553](https://www.rfc-editor.org/rfc/rfc7553.html) for more info.
+pub struct URI {
+	/// The name this record is at.
+	pub name: Name,
+	/// The priority of this target. Clients should use the target(s) with the lowest priority
+	/// that they can reach.
+	pub priority: u16,
+	/// The relative weight of this target amongst those with the same [`Self::priority`].
+	pub weight: u16,
+	/// The URI itself.
+	pub target: String,
+}
+/// The wire type for URI records
+pub const URI_TYPE: u16 = 256;
+impl StaticRecord for URI {
+	const TYPE: u16 = URI_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128 + self.target.len());
+		write!(&mut out, "{{\"type\":\"uri\",{},\"priority\":{},\"weight\":{},\"target\":",
+			self.name.json_owner_fields(), self.priority, self.weight
+		).expect("Write to a String shouldn't fail");
+		write_json_bytes(&mut out, self.target.as_bytes());
+		out += "}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		let priority = read_u16(&mut data)?;
+		let weight = read_u16(&mut data)?;
+		// RFC 7553 requires the target to be non-empty, and URIs are always ASCII.
+		if data.is_empty() || !data.is_ascii() { return Err(()); }
+		let target = core::str::from_utf8(data).map_err(|_| ())?.to_owned();
+		Ok(URI { name, priority, weight, target })
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 2 + 2 + self.target.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.priority.to_be_bytes());
+		out.write(&self.weight.to_be_bytes());
+		out.write(self.target.as_bytes());
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Naming Authority Pointer resource record, describing a rewrite rule used by Dynamic
+/// Delegation Discovery System applications such as ENUM or SIP server location.
+///
+/// See [RFC 3403](https://www.rfc-editor.org/rfc/rfc3403.html) for more info.
+pub struct NAPTR {
+	/// The name this record is at.
+	pub name: Name,
+	/// The order in which records must be processed, lowest first.
+	pub order: u16,
+	/// The order in which records with the same [`Self::order`] should be processed, lowest first.
+	pub preference: u16,
+	/// Application-specific flags which control the rewriting and interpretation of this record.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub flags: Vec<u8>,
+	/// The service(s) available via this rewrite path.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub services: Vec<u8>,
+	/// A substitution expression applied to the original string held by the client.
+	///
+	/// Mutually exclusive with [`Self::replacement`] - if this is set the replacement is `.`.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub regexp: Vec<u8>,
+	/// The next name to query for, if [`Self::regexp`] is empty.
+	pub replacement: Name,
+}
+/// The wire type for NAPTR records
+pub const NAPTR_TYPE: u16 = 35;
+impl StaticRecord for NAPTR {
+	const TYPE: u16 = NAPTR_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128 + self.regexp.len());
+		write!(&mut out, "{{\"type\":\"naptr\",{},\"order\":{},\"preference\":{},\"flags\":",
+			self.name.json_owner_fields(), self.order, self.preference
+		).expect("Write to a String shouldn't fail");
+		write_json_bytes(&mut out, &self.flags);
+		out += ",\"services\":";
+		write_json_bytes(&mut out, &self.services);
+		out += ",\"regexp\":";
+		write_json_bytes(&mut out, &self.regexp);
+		write!(&mut out, ",\"replacement\":\"{}\"}}", self.replacement.json_str())
+			.expect("Write to a String shouldn't fail");
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
+		let res = NAPTR {
+			name, order: read_u16(&mut data)?, preference: read_u16(&mut data)?,
+			flags: read_u8_len_prefixed_bytes(&mut data)?,
+			services: read_u8_len_prefixed_bytes(&mut data)?,
+			regexp: read_u8_len_prefixed_bytes(&mut data)?,
+			replacement: read_wire_packet_name(&mut data, wire_packet)?,
+		};
+		if !data.is_empty() { return Err(()); }
+		Ok(res)
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 2 + 2 + 3 + self.flags.len() + self.services.len() + self.regexp.len() +
+			name_len(&self.replacement) as usize;
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.order.to_be_bytes());
+		out.write(&self.preference.to_be_bytes());
+		out.write(&(self.flags.len() as u8).to_be_bytes());
+		out.write(&self.flags);
+		out.write(&(self.services.len() as u8).to_be_bytes());
+		out.write(&self.services);
+		out.write(&(self.regexp.len() as u8).to_be_bytes());
+		out.write(&self.regexp);
+		write_name(out, &self.replacement);
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Certificate resource record, storing a certificate (e.g. X.509 or OpenPGP) or a URL from
+/// which it can be fetched.
+///
+/// See [RFC 4398](https://www.rfc-editor.org/rfc/rfc4398.html) for more info.
+pub struct CERT {
+	/// The name this record is at.
+	pub name: Name,
+	/// The format of [`Self::cert`], e.g. 1 for X.509 (PKIX) or 3 for OpenPGP.
+	pub cert_type: u16,
+	/// The [`DnsKey::key_tag`] of the key in the certificate, or 0.
+	pub key_tag: u16,
+	/// The [`DnsKey::alg`] of the key in the certificate, or 0.
+	pub alg: u8,
+	/// The certificate (or, depending on [`Self::cert_type`], URL or fingerprint) itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub cert: Vec<u8>,
+}
+/// The wire type for CERT records
+pub const CERT_TYPE: u16 = 37;
+impl StaticRecord for CERT {
+	const TYPE: u16 = CERT_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.cert.len()*2);
+		write!(&mut out,
+			"{{\"type\":\"cert\",{},\"cert_type\":{},\"key_tag\":{},\"alg\":{},\"cert\":\"",
+			self.name.json_owner_fields(), self.cert_type, self.key_tag, self.alg
+		).expect("Write to a String shouldn't fail");
+		for c in self.cert.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		Ok(CERT {
+			name, cert_type: read_u16(&mut data)?, key_tag: read_u16(&mut data)?,
+			alg: read_u8(&mut data)?, cert: data.to_vec(),
+		})
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 2 + 2 + 1 + self.cert.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.cert_type.to_be_bytes());
+		out.write(&self.key_tag.to_be_bytes());
+		out.write(&self.alg.to_be_bytes());
+		out.write(&self.cert);
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Canonical Name resource record, referring all queries for this name to another name.
 pub struct CName {
 	/// The name this record is at.
 	pub name: Name,
 	/// The canonical name.
 	///
-	/// A resolver should use this name when looking up any further records for [`self.name`].
+	/// A resolver should use this name when looking up any further records for [`Self::name`](field@Self::name).
 	pub canonical_name: Name,
 }
 impl StaticRecord for CName {
 	const TYPE: u16 = 5;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		format!("{{\"type\":\"cname\",\"name\":\"{}\",\"canonical_name\":\"{}\"}}",
-			self.name.0, self.canonical_name.0)
+		format!("{{\"type\":\"cname\",{},\"canonical_name\":\"{}\"}}",
+			self.name.json_owner_fields(), self.canonical_name.json_str())
 	}
 	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
 		let res = CName { name, canonical_name: read_wire_packet_name(&mut data, wire_packet)? };
+		if !data.is_empty() { return Err(()); }
 		Ok(res)
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
@@ -558,6 +1293,7 @@ impl StaticRecord for CName {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Delegation Name resource record, referring all queries for subdomains of this name to another
 /// subtree of the DNS.
 pub struct DName {
@@ -566,18 +1302,19 @@ pub struct DName {
 	/// The delegation name.
 	///
 	/// A resolver should use this domain name tree when looking up any further records for
-	/// subdomains of [`self.name`].
+	/// subdomains of [`Self::name`](field@Self::name).
 	pub delegation_name: Name,
 }
 impl StaticRecord for DName {
 	const TYPE: u16 = 39;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		format!("{{\"type\":\"dname\",\"name\":\"{}\",\"delegation_name\":\"{}\"}}",
-			self.name.0, self.delegation_name.0)
+		format!("{{\"type\":\"dname\",{},\"delegation_name\":\"{}\"}}",
+			self.name.json_owner_fields(), self.delegation_name.json_str())
 	}
 	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
 		let res = DName { name, delegation_name: read_wire_packet_name(&mut data, wire_packet)? };
+		if !data.is_empty() { return Err(()); }
 		Ok(res)
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
@@ -587,8 +1324,66 @@ impl StaticRecord for DName {
 	}
 }
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Pointer resource record, pointing to another name. Generally used for reverse lookups,
+/// mapping an IP address (see [`PTR::name_for_ip`]) to a hostname.
+pub struct PTR {
+	/// The name this record is at.
+	pub name: Name,
+	/// The name this record points to.
+	///
+	/// For reverse lookups, this is the hostname of the host with the IP address described by
+	/// [`Self::name`](field@Self::name).
+	pub ptr_name: Name,
+}
+/// The wire type for PTR records
+pub const PTR_TYPE: u16 = 12;
+impl PTR {
+	/// Gets the name in `in-addr.arpa.` (for IPv4) or `ip6.arpa.` (for IPv6) at which the
+	/// [`PTR`] record for a reverse lookup of the given IP address is stored.
+	#[cfg(feature = "std")]
+	pub fn name_for_ip(ip: std::net::IpAddr) -> Name {
+		let mut name = String::with_capacity(73);
+		match ip {
+			std::net::IpAddr::V4(v4) => {
+				for b in v4.octets().iter().rev() {
+					write!(&mut name, "{}.", b).expect("Write to a String shouldn't fail");
+				}
+				name += "in-addr.arpa.";
+			},
+			std::net::IpAddr::V6(v6) => {
+				for b in v6.octets().iter().rev() {
+					write!(&mut name, "{:x}.{:x}.", b & 0xf, b >> 4)
+						.expect("Write to a String shouldn't fail");
+				}
+				name += "ip6.arpa.";
+			},
+		}
+		name.try_into().expect("Reverse lookup names are always valid")
+	}
+}
+impl StaticRecord for PTR {
+	const TYPE: u16 = PTR_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		format!("{{\"type\":\"ptr\",{},\"ptr_name\":\"{}\"}}",
+			self.name.json_owner_fields(), self.ptr_name.json_str())
+	}
+	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
+		let res = PTR { name, ptr_name: read_wire_packet_name(&mut data, wire_packet)? };
+		if !data.is_empty() { return Err(()); }
+		Ok(res)
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		out.write(&name_len(&self.ptr_name).to_be_bytes());
+		write_name(out, &self.ptr_name);
+	}
+}
+
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A public key resource record which can be used to validate [`RRSig`]s.
 pub struct DnsKey {
 	/// The name this record is at.
@@ -600,6 +1395,7 @@ pub struct DnsKey {
 	/// The algorithm which this public key uses to sign data.
 	pub alg: u8,
 	/// The public key itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub pubkey: Vec<u8>,
 }
 impl StaticRecord for DnsKey {
@@ -608,8 +1404,8 @@ impl StaticRecord for DnsKey {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(128+self.pubkey.len()*2);
 		write!(&mut out,
-			"{{\"type\":\"dnskey\",\"name\":\"{}\",\"flags\":{},\"protocol\":{},\"alg\":{},\"pubkey\":\"",
-			self.name.0, self.flags, self.protocol, self.alg
+			"{{\"type\":\"dnskey\",{},\"flags\":{},\"protocol\":{},\"alg\":{},\"pubkey\":\"",
+			self.name.json_owner_fields(), self.flags, self.protocol, self.alg
 		).expect("Write to a String shouldn't fail");
 		for c in self.pubkey.iter() {
 			write!(&mut out, "{:02X}", c)
@@ -636,24 +1432,29 @@ impl StaticRecord for DnsKey {
 impl DnsKey {
 	/// A short (non-cryptographic) digest which can be used to refer to this [`DnsKey`].
 	pub fn key_tag(&self) -> u16 {
-		let mut res = u32::from(self.flags);
-		res += u32::from(self.protocol) << 8;
-		res += u32::from(self.alg);
-		for (idx, b) in self.pubkey.iter().enumerate() {
-			if idx % 2 == 0 {
-				res += u32::from(*b) << 8;
-			} else {
-				res += u32::from(*b);
-			}
+		dnskey_key_tag(self.flags, self.protocol, self.alg, &self.pubkey)
+	}
+}
+/// Calculates the [`DnsKey::key_tag`] of a [`DnsKey`] with the given fields.
+pub(crate) fn dnskey_key_tag(flags: u16, protocol: u8, alg: u8, pubkey: &[u8]) -> u16 {
+	let mut res = u32::from(flags);
+	res += u32::from(protocol) << 8;
+	res += u32::from(alg);
+	for (idx, b) in pubkey.iter().enumerate() {
+		if idx % 2 == 0 {
+			res += u32::from(*b) << 8;
+		} else {
+			res += u32::from(*b);
 		}
-		res += (res >> 16) & 0xffff;
-		(res & 0xffff) as u16
 	}
+	res += (res >> 16) & 0xffff;
+	(res & 0xffff) as u16
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Delegation Signer resource record which indicates that some alternative [`DnsKey`] can sign
-/// for records in the zone which matches [`self.name`].
+/// for records in the zone which matches [`Self::name`](field@Self::name).
 pub struct DS {
 	/// The name this record is at.
 	///
@@ -671,6 +1472,7 @@ pub struct DS {
 	/// The type of digest used to hash the referred-to [`DnsKey`].
 	pub digest_type: u8,
 	/// The digest itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub digest: Vec<u8>,
 }
 impl StaticRecord for DS {
@@ -679,8 +1481,8 @@ impl StaticRecord for DS {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(128+self.digest.len()*2);
 		write!(&mut out,
-			"{{\"type\":\"ds\",\"name\":\"{}\",\"key_tag\":{},\"alg\":{},\"digest_type\":{},\"digest\":\"",
-			self.name.0, self.key_tag, self.alg, self.digest_type
+			"{{\"type\":\"ds\",{},\"key_tag\":{},\"alg\":{},\"digest_type\":{},\"digest\":\"",
+			self.name.json_owner_fields(), self.key_tag, self.alg, self.digest_type
 		).expect("Write to a String shouldn't fail");
 		for c in self.digest.iter() {
 			write!(&mut out, "{:02X}", c)
@@ -706,16 +1508,201 @@ impl StaticRecord for DS {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-/// A Resource Record (set) Signature resource record. This contains a signature over all the
-/// resources records of the given type at the given name.
-pub struct RRSig {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Child DS resource record, published by a child zone to indicate the [`DS`] record(s) it
+/// wishes its parent zone to publish for it.
+///
+/// See [RFC 7344](https://www.rfc-editor.org/rfc/rfc7344.html) and
+/// [RFC 8078](https://www.rfc-editor.org/rfc/rfc8078.html) for more info.
+pub struct CDS {
 	/// The name this record is at.
-	///
-	/// This is also the name of any records which this signature is covering (ignoring wildcards).
 	pub name: Name,
-	/// The resource record type which this [`RRSig`] is signing.
+	/// A short tag which describes the matching [`DnsKey`].
+	///
+	/// This matches the [`DnsKey::key_tag`] for the [`DnsKey`] which is referred to by this
+	/// [`CDS`].
+	pub key_tag: u16,
+	/// The algorithm which the [`DnsKey`] referred to by this [`CDS`] uses.
+	pub alg: u8,
+	/// The type of digest used to hash the referred-to [`DnsKey`].
+	pub digest_type: u8,
+	/// The digest itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub digest: Vec<u8>,
+}
+/// The wire type for CDS records
+pub const CDS_TYPE: u16 = 59;
+impl StaticRecord for CDS {
+	const TYPE: u16 = CDS_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.digest.len()*2);
+		write!(&mut out,
+			"{{\"type\":\"cds\",{},\"key_tag\":{},\"alg\":{},\"digest_type\":{},\"digest\":\"",
+			self.name.json_owner_fields(), self.key_tag, self.alg, self.digest_type
+		).expect("Write to a String shouldn't fail");
+		for c in self.digest.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		Ok(CDS {
+			name, key_tag: read_u16(&mut data)?, alg: read_u8(&mut data)?,
+			digest_type: read_u8(&mut data)?, digest: data.to_vec(),
+		})
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 2 + 1 + 1 + self.digest.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.key_tag.to_be_bytes());
+		out.write(&self.alg.to_be_bytes());
+		out.write(&self.digest_type.to_be_bytes());
+		out.write(&self.digest);
+	}
+}
+impl CDS {
+	/// Returns true if this is the special `0 0 0 00` record which requests that the parent
+	/// remove all [`DS`] records for this zone (see RFC 8078 section 4).
+	pub fn is_delete(&self) -> bool {
+		self.key_tag == 0 && self.alg == 0 && self.digest_type == 0 && self.digest == [0]
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Child DNSKEY resource record, published by a child zone to indicate the [`DnsKey`]s its
+/// parent zone should publish [`DS`] records for.
+///
+/// See [RFC 7344](https://www.rfc-editor.org/rfc/rfc7344.html) and
+/// [RFC 8078](https://www.rfc-editor.org/rfc/rfc8078.html) for more info.
+pub struct CDnsKey {
+	/// The name this record is at.
+	pub name: Name,
+	/// Flags which constrain the usage of this public key.
+	pub flags: u16,
+	/// The protocol this key is used for (protocol `3` is DNSSEC).
+	pub protocol: u8,
+	/// The algorithm which this public key uses to sign data.
+	pub alg: u8,
+	/// The public key itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub pubkey: Vec<u8>,
+}
+/// The wire type for CDNSKEY records
+pub const CDNSKEY_TYPE: u16 = 60;
+impl StaticRecord for CDnsKey {
+	const TYPE: u16 = CDNSKEY_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.pubkey.len()*2);
+		write!(&mut out,
+			"{{\"type\":\"cdnskey\",{},\"flags\":{},\"protocol\":{},\"alg\":{},\"pubkey\":\"",
+			self.name.json_owner_fields(), self.flags, self.protocol, self.alg
+		).expect("Write to a String shouldn't fail");
+		for c in self.pubkey.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		Ok(CDnsKey {
+			name, flags: read_u16(&mut data)?, protocol: read_u8(&mut data)?,
+			alg: read_u8(&mut data)?, pubkey: data.to_vec(),
+		})
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 2 + 1 + 1 + self.pubkey.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.flags.to_be_bytes());
+		out.write(&self.protocol.to_be_bytes());
+		out.write(&self.alg.to_be_bytes());
+		out.write(&self.pubkey);
+	}
+}
+impl CDnsKey {
+	/// Returns true if this is the special `0 3 0 AA==` record which requests that the parent
+	/// remove all [`DS`] records for this zone (see RFC 8078 section 4).
+	pub fn is_delete(&self) -> bool {
+		self.flags == 0 && self.protocol == 3 && self.alg == 0 && self.pubkey == [0]
+	}
+	/// Returns true if this [`CDnsKey`] refers to the given [`DnsKey`].
+	pub fn matches(&self, dnskey: &DnsKey) -> bool {
+		self.flags == dnskey.flags && self.protocol == dnskey.protocol &&
+			self.alg == dnskey.alg && self.pubkey == dnskey.pubkey
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Zone Message Digest resource record, providing a digest over the full contents of a zone.
+///
+/// See [RFC 8976](https://www.rfc-editor.org/rfc/rfc8976.html) for more info.
+pub struct ZoneMD {
+	/// The name this record is at, which is always the zone apex.
+	pub name: Name,
+	/// The serial of the zone's [`SOA`] record at the time the digest was calculated.
+	pub serial: u32,
+	/// The method by which the zone was collated for hashing (1 is SIMPLE).
+	pub scheme: u8,
+	/// The hash algorithm used to calculate [`Self::digest`] (1 is SHA-384, 2 is SHA-512).
+	pub hash_alg: u8,
+	/// The digest itself, which is always at least 12 bytes.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
+	pub digest: Vec<u8>,
+}
+/// The wire type for ZONEMD records
+pub const ZONEMD_TYPE: u16 = 63;
+impl StaticRecord for ZoneMD {
+	const TYPE: u16 = ZONEMD_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		let mut out = String::with_capacity(128+self.digest.len()*2);
+		write!(&mut out,
+			"{{\"type\":\"zonemd\",{},\"serial\":{},\"scheme\":{},\"hash_alg\":{},\"digest\":\"",
+			self.name.json_owner_fields(), self.serial, self.scheme, self.hash_alg
+		).expect("Write to a String shouldn't fail");
+		for c in self.digest.iter() {
+			write!(&mut out, "{:02X}", c)
+				.expect("Write to a String shouldn't fail");
+		}
+		out += "\"}";
+		out
+	}
+	fn read_from_data(name: Name, mut data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
+		let serial = read_u32(&mut data)?;
+		let scheme = read_u8(&mut data)?;
+		let hash_alg = read_u8(&mut data)?;
+		// RFC 8976 section 2.2.4: "the Digest field MUST NOT be shorter than 12 octets"
+		if data.len() < 12 { return Err(()); }
+		Ok(ZoneMD { name, serial, scheme, hash_alg, digest: data.to_vec() })
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = 4 + 1 + 1 + self.digest.len();
+		out.write(&(len as u16).to_be_bytes());
+		out.write(&self.serial.to_be_bytes());
+		out.write(&self.scheme.to_be_bytes());
+		out.write(&self.hash_alg.to_be_bytes());
+		out.write(&self.digest);
+	}
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Resource Record (set) Signature resource record. This contains a signature over all the
+/// resources records of the given type at the given name.
+pub struct RRSig {
+	/// The name this record is at.
 	///
-	/// All resources records of this type at the same name as [`self.name`] must be signed by
+	/// This is also the name of any records which this signature is covering (ignoring wildcards).
+	pub name: Name,
+	/// The resource record type which this [`RRSig`] is signing.
+	///
+	/// All resources records of this type at the same name as [`Self::name`](field@Self::name) must be signed by
 	/// this [`RRSig`].
 	pub ty: u16,
 	/// The algorithm which is being used to sign.
@@ -724,7 +1711,7 @@ pub struct RRSig {
 	pub alg: u8,
 	/// The number of labels in the name of the records that this signature is signing.
 	///
-	/// If this is less than the number of labels in [`self.name`], this signature is covering a
+	/// If this is less than the number of labels in [`Self::name`](field@Self::name), this signature is covering a
 	/// wildcard entry.
 	pub labels: u8,
 	/// The TTL of the records which this [`RRSig`] is signing.
@@ -739,13 +1726,14 @@ pub struct RRSig {
 	pub key_tag: u16,
 	/// The [`DnsKey::name`] in the [`DnsKey`] which created this signature.
 	///
-	/// This must be a parent of [`self.name`].
+	/// This must be a parent of [`Self::name`](field@Self::name).
 	///
 	/// [`DnsKey::name`]: Record::name
 	// We'd like to just link to the `DnsKey` member variable called `name`, but there doesn't
 	// appear to be a way to actually do that, so instead we have to link to the trait method.
 	pub key_name: Name,
 	/// The signature itself.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub signature: Vec<u8>,
 }
 impl StaticRecord for RRSig {
@@ -754,8 +1742,8 @@ impl StaticRecord for RRSig {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(256 + self.signature.len()*2);
 		write!(&mut out,
-			"{{\"type\":\"ds\",\"name\":\"{}\",\"signed_record_type\":{},\"alg\":{},\"signed_labels\":{},\"orig_ttl\":{},\"expiration\"{},\"inception\":{},\"key_tag\":{},\"key_name\":\"{}\",\"signature\":\"",
-			self.name.0, self.ty, self.alg, self.labels, self.orig_ttl, self.expiration, self.inception, self.key_tag, self.key_name.0
+			"{{\"type\":\"rrsig\",{},\"signed_record_type\":{},\"alg\":{},\"signed_labels\":{},\"orig_ttl\":{},\"expiration\":{},\"inception\":{},\"key_tag\":{},\"key_name\":\"{}\",\"signature\":\"",
+			self.name.json_owner_fields(), self.ty, self.alg, self.labels, self.orig_ttl, self.expiration, self.inception, self.key_tag, self.key_name.json_str()
 		).expect("Write to a String shouldn't fail");
 		for c in self.signature.iter() {
 			write!(&mut out, "{:02X}", c)
@@ -792,29 +1780,99 @@ impl StaticRecord for RRSig {
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 /// A mask used in [`NSec`] and [`NSec3`] records which indicates the resource record types which
 /// exist at the (hash of the) name described in [`Record::name`].
-pub struct NSecTypeMask([u8; 8192]);
+pub struct NSecTypeMask {
+	/// The mask in its canonical RFC 4034 wire encoding - a series of window blocks (in ascending
+	/// order) each made up of the window number, the length of the bitmap and the bitmap itself,
+	/// with windows which are empty and bitmap bytes which are trailing zeros omitted.
+	windows: Vec<u8>,
+}
 impl NSecTypeMask {
 	/// Constructs a new, empty, type mask.
-	pub fn new() -> Self { Self([0; 8192]) }
+	pub fn new() -> Self { Self { windows: Vec::new() } }
 	/// Builds a new type mask with the given types set
 	pub fn from_types(types: &[u16]) -> Self {
-		let mut flags = [0; 8192];
+		let mut windows = Vec::new();
 		for t in types {
-			flags[*t as usize >> 3] |= 1 << (7 - (*t as usize % 8));
+			let bitmap = Self::get_window(&mut windows, (*t >> 8) as u8);
+			bitmap[(*t as usize & 0xff) >> 3] |= 1 << (7 - (*t as usize % 8));
 		}
-		let res = Self(flags);
+		let res = Self::from_windows(&windows);
 		for t in types {
 			debug_assert!(res.contains_type(*t));
 		}
 		res
 	}
+	/// Gets the (full-length) bitmap for the given window in a sorted list of windows, inserting an
+	/// empty one if it's not present.
+	fn get_window(windows: &mut Vec<(u8, [u8; 32])>, window: u8) -> &mut [u8; 32] {
+		let idx = match windows.binary_search_by_key(&window, |(w, _)| *w) {
+			Ok(idx) => idx,
+			Err(idx) => {
+				windows.insert(idx, (window, [0; 32]));
+				idx
+			},
+		};
+		&mut windows[idx].1
+	}
+	/// Builds a type mask from a sorted list of windows and their (full-length) bitmaps.
+	fn from_windows(windows: &[(u8, [u8; 32])]) -> Self {
+		debug_assert!(windows.windows(2).all(|pair| pair[0].0 < pair[1].0));
+		let mut res = Vec::new();
+		for (window, bitmap) in windows.iter() {
+			if let Some(last_nonzero_idx) = bitmap.iter().rposition(|flags| *flags != 0) {
+				res.push(*window);
+				res.push(last_nonzero_idx as u8 + 1);
+				res.extend_from_slice(&bitmap[..last_nonzero_idx + 1]);
+			}
+		}
+		Self { windows: res }
+	}
+	/// Builds a type mask from its canonical RFC 4034 wire encoding, which must have already been
+	/// checked by [`read_nsec_types_bitmap`].
+	pub(crate) fn from_canonical_wire(windows: Vec<u8>) -> Self {
+		Self { windows }
+	}
+	/// Gets the RFC 4034 wire encoding of this type mask.
+	pub(crate) fn wire_bytes(&self) -> &[u8] { &self.windows }
+	/// Iterates over the window number and (trimmed) bitmap of each non-empty window.
+	fn window_blocks(&self) -> impl Iterator<Item = (u8, &[u8])> {
+		Self::wire_window_blocks(&self.windows)
+	}
+	/// Iterates over the window blocks in the given canonical wire encoding of a type mask.
+	fn wire_window_blocks(mut data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+		core::iter::from_fn(move || {
+			let (window, len) = (*data.get(0)?, *data.get(1)? as usize);
+			let bitmap = &data[2..2 + len];
+			data = &data[2 + len..];
+			Some((window, bitmap))
+		})
+	}
 	/// Checks if the given type (from [`Record::ty`]) is set, indicating a record of this type
 	/// exists.
 	pub fn contains_type(&self, ty: u16) -> bool {
-		let f = self.0[(ty >> 3) as usize];
+		Self::wire_contains_type(&self.windows, ty)
+	}
+	/// Checks if the given type is set in the given canonical wire encoding of a type mask (e.g.
+	/// as checked by [`read_nsec_types_bitmap`]).
+	pub(crate) fn wire_contains_type(windows: &[u8], ty: u16) -> bool {
+		let window = (ty >> 8) as u8;
+		let bitmap = match Self::wire_window_blocks(windows).find(|(w, _)| *w == window) {
+			Some((_, bitmap)) => bitmap,
+			None => return false,
+		};
+		let f = bitmap.get((ty as usize & 0xff) >> 3).copied().unwrap_or(0);
 		// DNSSEC's bit fields are in wire order, so the high bit is type 0, etc.
 		f & (1 << (7 - (ty % 8))) != 0
 	}
+	/// Iterates over the types which are set in this mask, in ascending order.
+	pub fn types(&self) -> impl Iterator<Item = u16> + '_ {
+		self.window_blocks().flat_map(|(window, bitmap)| {
+			bitmap.iter().enumerate().flat_map(move |(idx, flags)| {
+				let base = ((window as u16) << 8) | ((idx as u16) << 3);
+				(0..8).filter(move |b| flags & (0x80 >> b) != 0).map(move |b| base | b)
+			})
+		})
+	}
 	fn write_json(&self, s: &mut String) {
 		*s += "[";
 		write!(s, "{:?}", self).expect("Writes to a string shouldn't fail");
@@ -824,24 +1882,40 @@ impl NSecTypeMask {
 impl fmt::Debug for NSecTypeMask {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		let mut have_written = false;
-		for (idx, mask) in self.0.iter().enumerate() {
-			if *mask == 0 { continue; }
-			for b in 0..8 {
-				if *mask & (1 << b) != 0 {
-					let ty = ((idx as u16) << 3) | (7 - b);
-					match RR::ty_to_rr_name(ty) {
-						Some(name) => write!(f, "{}\"{}\"", if have_written { "," } else { "" }, name)?,
-						_ => write!(f, "{}{}", if have_written { "," } else { "" }, ty)?,
+		for (window, bitmap) in self.window_blocks() {
+			for (idx, mask) in bitmap.iter().enumerate() {
+				if *mask == 0 { continue; }
+				for b in 0..8 {
+					if *mask & (1 << b) != 0 {
+						let ty = ((window as u16) << 8) | ((idx as u16) << 3) | (7 - b);
+						match RR::ty_to_rr_name(ty) {
+							Some(name) => write!(f, "{}\"{}\"", if have_written { "," } else { "" }, name)?,
+							_ => write!(f, "{}{}", if have_written { "," } else { "" }, ty)?,
+						}
+						have_written = true;
 					}
-					have_written = true;
 				}
 			}
 		}
 		Ok(())
 	}
 }
+#[cfg(feature = "serde")]
+impl serde::Serialize for NSecTypeMask {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.types())
+	}
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NSecTypeMask {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let types = <Vec<u16> as serde::Deserialize>::deserialize(deserializer)?;
+		Ok(NSecTypeMask::from_types(&types))
+	}
+}
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Next Secure Record resource record. This indicates a range of possible names for which there
 /// is no such record.
 pub struct NSec {
@@ -860,8 +1934,8 @@ impl StaticRecord for NSec {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(256 + self.next_name.len());
 		write!(&mut out,
-			"{{\"type\":\"nsec\",\"name\":\"{}\",\"next_name\":\"{}\",\"types\":",
-			self.name.0, self.next_name.0,
+			"{{\"type\":\"nsec\",{},\"next_name\":\"{}\",\"types\":",
+			self.name.json_owner_fields(), self.next_name.json_str(),
 		).expect("Write to a String shouldn't fail");
 		self.types.write_json(&mut out);
 		out += "}";
@@ -870,20 +1944,21 @@ impl StaticRecord for NSec {
 	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
 		let res = NSec {
 			name, next_name: read_wire_packet_name(&mut data, wire_packet)?,
-			types: NSecTypeMask(read_nsec_types_bitmap(&mut data)?),
+			types: read_nsec_types_bitmap(&mut data)?,
 		};
 		debug_assert!(data.is_empty());
 		Ok(res)
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
-		let len = name_len(&self.next_name) + nsec_types_bitmap_len(&self.types.0);
+		let len = name_len(&self.next_name) + nsec_types_bitmap_len(&self.types);
 		out.write(&len.to_be_bytes());
 		write_name(out, &self.next_name);
-		write_nsec_types_bitmap(out, &self.types.0);
+		write_nsec_types_bitmap(out, &self.types);
 	}
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Next Secure Record resource record. This indicates a range of possible names for which there
 /// is no such record.
 pub struct NSec3 {
@@ -902,9 +1977,11 @@ pub struct NSec3 {
 	/// The salt included in the hash.
 	///
 	/// As of RFC 9276 this SHOULD be empty, but often isn't in the wild.
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub salt: Vec<u8>,
 	/// The hash of the next name which contains a record. There are no records who's name's hash
 	/// lies between `name` and [`Self::next_name_hash`].
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_util::hex"))]
 	pub next_name_hash: Vec<u8>,
 	/// The set of record types which exist at `name`. Any other record types do not exist at
 	/// `name`.
@@ -916,8 +1993,8 @@ impl StaticRecord for NSec3 {
 	fn json(&self) -> String {
 		let mut out = String::with_capacity(256);
 		write!(&mut out,
-			"{{\"type\":\"nsec3\",\"name\":\"{}\",\"hash_algo\":{},\"flags\":{},\"hash_iterations\":{},\"salt\":{:?},\"next_name_hash\":{:?},\"types\":",
-			self.name.0, self.hash_algo, self.flags, self.hash_iterations, &self.salt[..], &self.next_name_hash[..]
+			"{{\"type\":\"nsec3\",{},\"hash_algo\":{},\"flags\":{},\"hash_iterations\":{},\"salt\":{:?},\"next_name_hash\":{:?},\"types\":",
+			self.name.json_owner_fields(), self.hash_algo, self.flags, self.hash_iterations, &self.salt[..], &self.next_name_hash[..]
 		).expect("Write to a String shouldn't fail");
 		self.types.write_json(&mut out);
 		out += "}";
@@ -928,14 +2005,14 @@ impl StaticRecord for NSec3 {
 			name, hash_algo: read_u8(&mut data)?, flags: read_u8(&mut data)?,
 			hash_iterations: read_u16(&mut data)?, salt: read_u8_len_prefixed_bytes(&mut data)?,
 			next_name_hash: read_u8_len_prefixed_bytes(&mut data)?,
-			types: NSecTypeMask(read_nsec_types_bitmap(&mut data)?),
+			types: read_nsec_types_bitmap(&mut data)?,
 		};
 		debug_assert!(data.is_empty());
 		Ok(res)
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
 		let len = 4 + 2 + self.salt.len() as u16 + self.next_name_hash.len() as u16 +
-			nsec_types_bitmap_len(&self.types.0);
+			nsec_types_bitmap_len(&self.types);
 		out.write(&len.to_be_bytes());
 		out.write(&self.hash_algo.to_be_bytes());
 		out.write(&self.flags.to_be_bytes());
@@ -944,11 +2021,12 @@ impl StaticRecord for NSec3 {
 		out.write(&self.salt);
 		out.write(&(self.next_name_hash.len() as u8).to_be_bytes());
 		out.write(&self.next_name_hash);
-		write_nsec_types_bitmap(out, &self.types.0);
+		write_nsec_types_bitmap(out, &self.types);
 	}
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An IPv4 Address resource record
 pub struct A {
 	/// The name this record is at.
@@ -962,7 +2040,7 @@ impl StaticRecord for A {
 	const TYPE: u16 = A_TYPE;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		format!("{{\"type\":\"a\",\"name\":\"{}\",\"address\":{:?}}}", self.name.0, self.address)
+		format!("{{\"type\":\"a\",{},\"address\":{:?}}}", self.name.json_owner_fields(), self.address)
 	}
 	fn read_from_data(name: Name, data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
 		if data.len() != 4 { return Err(()); }
@@ -977,6 +2055,7 @@ impl StaticRecord for A {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An IPv6 Address resource record
 pub struct AAAA {
 	/// The name this record is at.
@@ -990,7 +2069,7 @@ impl StaticRecord for AAAA {
 	const TYPE: u16 = AAAA_TYPE;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		format!("{{\"type\":\"aaaa\",\"name\":\"{}\",\"address\":{:?}}}", self.name.0, self.address)
+		format!("{{\"type\":\"aaaa\",{},\"address\":{:?}}}", self.name.json_owner_fields(), self.address)
 	}
 	fn read_from_data(name: Name, data: &[u8], _wire_packet: &[u8]) -> Result<Self, ()> {
 		if data.len() != 16 { return Err(()); }
@@ -1005,6 +2084,7 @@ impl StaticRecord for AAAA {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A Name Server resource record, which indicates the server responsible for handling queries for
 /// a zone.
 pub struct NS {
@@ -1013,7 +2093,7 @@ pub struct NS {
 	/// This is also the zone which the server at [`Self::name_server`] is responsible for handling
 	/// queries for.
 	pub name: Name,
-	/// The name of the server which is responsible for handling queries for the [`self.name`]
+	/// The name of the server which is responsible for handling queries for the [`Self::name`](field@Self::name)
 	/// zone.
 	pub name_server: Name,
 }
@@ -1021,10 +2101,11 @@ impl StaticRecord for NS {
 	const TYPE: u16 = 2;
 	fn name(&self) -> &Name { &self.name }
 	fn json(&self) -> String {
-		format!("{{\"type\":\"ns\",\"name\":\"{}\",\"ns\":\"{}\"}}", self.name.0, self.name_server.0)
+		format!("{{\"type\":\"ns\",{},\"ns\":\"{}\"}}", self.name.json_owner_fields(), self.name_server.json_str())
 	}
 	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
 		let res = NS { name, name_server: read_wire_packet_name(&mut data, wire_packet)? };
+		if !data.is_empty() { return Err(()); }
 		Ok(res)
 	}
 	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
@@ -1032,3 +2113,716 @@ impl StaticRecord for NS {
 		write_name(out, &self.name_server);
 	}
 }
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A Start of Authority resource record, which appears at the apex of every zone and describes
+/// the zone's primary server, its serial number, and the timers which control caching of the
+/// zone's data (including negative caching).
+pub struct SOA {
+	/// The name this record is at.
+	///
+	/// This is also the apex of the zone which this record describes.
+	pub name: Name,
+	/// The name of the primary name server for this zone.
+	pub primary_ns: Name,
+	/// The mailbox of the person responsible for this zone, encoded as a name (with the `@`
+	/// replaced by a `.`).
+	pub mailbox: Name,
+	/// The version number of the zone, which is incremented on each change.
+	pub serial: u32,
+	/// The number of seconds after which secondary name servers should refresh the zone.
+	pub refresh: u32,
+	/// The number of seconds after which secondary name servers should retry a failed refresh.
+	pub retry: u32,
+	/// The number of seconds after which secondary name servers should stop answering for the zone
+	/// if they have been unable to refresh it.
+	pub expire: u32,
+	/// The TTL which should be used when caching negative responses from this zone.
+	///
+	/// Per RFC 2308, the actual negative caching TTL is the minimum of this and the TTL of the
+	/// [`SOA`] record itself.
+	pub minimum: u32,
+}
+/// The wire type for SOA records
+pub const SOA_TYPE: u16 = 6;
+impl StaticRecord for SOA {
+	const TYPE: u16 = SOA_TYPE;
+	fn name(&self) -> &Name { &self.name }
+	fn json(&self) -> String {
+		format!("{{\"type\":\"soa\",{},\"primary_ns\":\"{}\",\"mailbox\":\"{}\",\"serial\":{},\"refresh\":{},\"retry\":{},\"expire\":{},\"minimum\":{}}}",
+			self.name.json_owner_fields(), self.primary_ns.json_str(), self.mailbox.json_str(), self.serial, self.refresh, self.retry,
+			self.expire, self.minimum)
+	}
+	fn read_from_data(name: Name, mut data: &[u8], wire_packet: &[u8]) -> Result<Self, ()> {
+		let res = SOA {
+			name, primary_ns: read_wire_packet_name(&mut data, wire_packet)?,
+			mailbox: read_wire_packet_name(&mut data, wire_packet)?,
+			serial: read_u32(&mut data)?, refresh: read_u32(&mut data)?, retry: read_u32(&mut data)?,
+			expire: read_u32(&mut data)?, minimum: read_u32(&mut data)?,
+		};
+		if !data.is_empty() { return Err(()); }
+		Ok(res)
+	}
+	fn write_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let len = name_len(&self.primary_ns) + name_len(&self.mailbox) + 4*5;
+		out.write(&len.to_be_bytes());
+		write_name(out, &self.primary_ns);
+		write_name(out, &self.mailbox);
+		out.write(&self.serial.to_be_bytes());
+		out.write(&self.refresh.to_be_bytes());
+		out.write(&self.retry.to_be_bytes());
+		out.write(&self.expire.to_be_bytes());
+		out.write(&self.minimum.to_be_bytes());
+	}
+}
+
+/// Record types which don't have an [`RR`] variant but which are common enough that we use their
+/// mnemonic in the presentation format (e.g. when listing the types in an [`NSec`] bitmap).
+const OTHER_TYPE_MNEMONICS: [(u16, &str); 22] = [
+	(13, "HINFO"), (15, "MX"), (17, "RP"), (18, "AFSDB"), (24, "SIG"), (25, "KEY"), (29, "LOC"),
+	(33, "SRV"), (36, "KX"), (42, "APL"), (44, "SSHFP"), (45, "IPSECKEY"), (51, "NSEC3PARAM"),
+	(55, "HIP"), (62, "CSYNC"), (64, "SVCB"), (65, "HTTPS"), (99, "SPF"), (108, "EUI48"),
+	(109, "EUI64"), (250, "TSIG"), (257, "CAA"),
+];
+/// The types of all the records in [`RR`].
+const RR_TYPES: [u16; 22] = [
+	A::TYPE, AAAA::TYPE, NS::TYPE, SOA::TYPE, Txt::TYPE, CName::TYPE, DName::TYPE, PTR::TYPE,
+	TLSA::TYPE, DnsKey::TYPE, DS::TYPE, RRSig::TYPE, NSec::TYPE, NSec3::TYPE, SMIMEA::TYPE,
+	OpenPGPKey::TYPE, URI::TYPE, NAPTR::TYPE, CERT::TYPE, CDS::TYPE, CDnsKey::TYPE, ZoneMD::TYPE,
+];
+
+/// Gets the mnemonic for the given record type, if we know it.
+fn type_mnemonic(ty: u16) -> Option<&'static str> {
+	RR::ty_to_rr_name(ty)
+		.or_else(|| OTHER_TYPE_MNEMONICS.iter().find(|(t, _)| *t == ty).map(|(_, name)| *name))
+}
+
+/// Parses a record type from its mnemonic or RFC 3597 `TYPEnnn` form (ignoring case).
+pub(crate) fn type_from_mnemonic(s: &str) -> Option<u16> {
+	if s.len() > 4 && s[..4].eq_ignore_ascii_case("TYPE") {
+		if let Ok(ty) = s[4..].parse() { return Some(ty); }
+	}
+	RR::ty_from_name(s)
+		.or_else(|| OTHER_TYPE_MNEMONICS.iter().find(|(_, name)| name.eq_ignore_ascii_case(s)).map(|(ty, _)| *ty))
+}
+
+fn write_type(f: &mut fmt::Formatter, ty: u16) -> fmt::Result {
+	match type_mnemonic(ty) {
+		Some(name) => f.write_str(name),
+		None => write!(f, "TYPE{}", ty),
+	}
+}
+
+/// A record which can be written in RFC 1035 presentation ("zone file") format.
+trait PresentationRecord {
+	/// Writes this record in presentation format with the given TTL.
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result;
+}
+
+/// Writes the `owner TTL IN TYPE ` prefix of a record's presentation format.
+fn write_presentation_prefix<R: StaticRecord>(f: &mut fmt::Formatter, record: &R, ttl: u32) -> fmt::Result {
+	write!(f, "{} {} IN ", record.name(), ttl)?;
+	write_type(f, R::TYPE)?;
+	f.write_str(" ")
+}
+
+fn write_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+	for b in data.iter() { write!(f, "{:02X}", b)?; }
+	Ok(())
+}
+
+/// Writes the given bytes as a quoted RFC 1035 character-string.
+fn write_char_string(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+	f.write_str("\"")?;
+	for b in data.iter() {
+		if *b == b'"' || *b == b'\\' {
+			write!(f, "\\{}", *b as char)?;
+		} else if (0x20..=0x7e).contains(b) {
+			write!(f, "{}", *b as char)?;
+		} else {
+			write!(f, "\\{:03}", b)?;
+		}
+	}
+	f.write_str("\"")
+}
+
+fn write_types_bitmap(f: &mut fmt::Formatter, types: &NSecTypeMask) -> fmt::Result {
+	for ty in types.types() {
+		f.write_str(" ")?;
+		write_type(f, ty)?;
+	}
+	Ok(())
+}
+
+/// Converts a number of days since 1970-01-01 into a (year, month, day) triple.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+	(yoe + era * 400 + if month <= 2 { 1 } else { 0 }, month, day)
+}
+
+/// Converts a (year, month, day) triple into a number of days since 1970-01-01.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let yoe = year.rem_euclid(400);
+	let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+	let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	era * 146097 + doe - 719468
+}
+
+/// Writes an [`RRSig`] inception/expiration time in `YYYYMMDDHHmmSS` form.
+fn write_sig_time(f: &mut fmt::Formatter, time: u32) -> fmt::Result {
+	let (year, month, day) = civil_from_days(time as i64 / 86400);
+	let secs = time % 86400;
+	write!(f, "{:04}{:02}{:02}{:02}{:02}{:02}", year, month, day, secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+/// Writes an IPv6 address in RFC 5952 form.
+fn write_ipv6(f: &mut fmt::Formatter, address: &[u8; 16]) -> fmt::Result {
+	let mut groups = [0u16; 8];
+	for (group, bytes) in groups.iter_mut().zip(address.chunks(2)) {
+		*group = u16::from_be_bytes([bytes[0], bytes[1]]);
+	}
+	// Find the longest run of (at least two) zero groups, which we replace with "::"
+	let (mut best_start, mut best_len, mut run_start, mut run_len) = (0, 0, 0, 0);
+	for (idx, group) in groups.iter().enumerate() {
+		if *group == 0 {
+			if run_len == 0 { run_start = idx; }
+			run_len += 1;
+			if run_len > best_len { best_start = run_start; best_len = run_len; }
+		} else {
+			run_len = 0;
+		}
+	}
+	if best_len < 2 { best_len = 0; }
+	let mut idx = 0;
+	while idx < 8 {
+		if best_len != 0 && idx == best_start {
+			f.write_str("::")?;
+			idx += best_len;
+			continue;
+		}
+		if idx != 0 && !(best_len != 0 && idx == best_start + best_len) { f.write_str(":")?; }
+		write!(f, "{:x}", groups[idx])?;
+		idx += 1;
+	}
+	Ok(())
+}
+
+impl PresentationRecord for A {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}.{}.{}.{}", self.address[0], self.address[1], self.address[2], self.address[3])
+	}
+}
+impl PresentationRecord for AAAA {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write_ipv6(f, &self.address)
+	}
+}
+impl PresentationRecord for NS {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}", self.name_server)
+	}
+}
+impl PresentationRecord for SOA {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} {} {} {} {}", self.primary_ns, self.mailbox, self.serial, self.refresh,
+			self.retry, self.expire, self.minimum)
+	}
+}
+impl PresentationRecord for Txt {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		// `""` would encode as a single zero-length character-string rather than the empty RDATA we
+		// have, so write it in RFC 3597 generic form instead.
+		if self.data.chunks.is_empty() { return f.write_str("\\# 0"); }
+		for (idx, chunk) in self.data.chunks.iter().enumerate() {
+			if idx != 0 { f.write_str(" ")?; }
+			write_char_string(f, &chunk.bytes[..chunk.len.get() as usize])?;
+		}
+		Ok(())
+	}
+}
+impl PresentationRecord for TLSA {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.cert_usage, self.selector, self.data_ty)?;
+		write_hex(f, &self.data)
+	}
+}
+impl PresentationRecord for SMIMEA {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.cert_usage, self.selector, self.data_ty)?;
+		write_hex(f, &self.data)
+	}
+}
+impl PresentationRecord for OpenPGPKey {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		f.write_str(&crate::base64::encode(&self.pubkey))
+	}
+}
+impl PresentationRecord for URI {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} ", self.priority, self.weight)?;
+		write_char_string(f, self.target.as_bytes())
+	}
+}
+impl PresentationRecord for NAPTR {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} ", self.order, self.preference)?;
+		write_char_string(f, &self.flags)?;
+		f.write_str(" ")?;
+		write_char_string(f, &self.services)?;
+		f.write_str(" ")?;
+		write_char_string(f, &self.regexp)?;
+		write!(f, " {}", self.replacement)
+	}
+}
+impl PresentationRecord for CERT {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} {}", self.cert_type, self.key_tag, self.alg, crate::base64::encode(&self.cert))
+	}
+}
+impl PresentationRecord for CName {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}", self.canonical_name)
+	}
+}
+impl PresentationRecord for DName {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}", self.delegation_name)
+	}
+}
+impl PresentationRecord for PTR {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}", self.ptr_name)
+	}
+}
+impl PresentationRecord for DnsKey {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} {}", self.flags, self.protocol, self.alg, crate::base64::encode(&self.pubkey))
+	}
+}
+impl PresentationRecord for CDnsKey {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} {}", self.flags, self.protocol, self.alg, crate::base64::encode(&self.pubkey))
+	}
+}
+impl PresentationRecord for DS {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.key_tag, self.alg, self.digest_type)?;
+		write_hex(f, &self.digest)
+	}
+}
+impl PresentationRecord for CDS {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.key_tag, self.alg, self.digest_type)?;
+		write_hex(f, &self.digest)
+	}
+}
+impl PresentationRecord for ZoneMD {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.serial, self.scheme, self.hash_alg)?;
+		write_hex(f, &self.digest)
+	}
+}
+impl PresentationRecord for RRSig {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write_type(f, self.ty)?;
+		write!(f, " {} {} {} ", self.alg, self.labels, self.orig_ttl)?;
+		write_sig_time(f, self.expiration)?;
+		f.write_str(" ")?;
+		write_sig_time(f, self.inception)?;
+		write!(f, " {} {} {}", self.key_tag, self.key_name, crate::base64::encode(&self.signature))
+	}
+}
+impl PresentationRecord for NSec {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{}", self.next_name)?;
+		write_types_bitmap(f, &self.types)
+	}
+}
+impl PresentationRecord for NSec3 {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		write_presentation_prefix(f, self, ttl)?;
+		write!(f, "{} {} {} ", self.hash_algo, self.flags, self.hash_iterations)?;
+		if self.salt.is_empty() { f.write_str("-")?; } else { write_hex(f, &self.salt)?; }
+		write!(f, " {}", crate::base32::encode(&self.next_name_hash))?;
+		write_types_bitmap(f, &self.types)
+	}
+}
+impl PresentationRecord for RR {
+	fn write_presentation(&self, f: &mut fmt::Formatter, ttl: u32) -> fmt::Result {
+		match self {
+			RR::A(rr) => rr.write_presentation(f, ttl),
+			RR::AAAA(rr) => rr.write_presentation(f, ttl),
+			RR::NS(rr) => rr.write_presentation(f, ttl),
+			RR::SOA(rr) => rr.write_presentation(f, ttl),
+			RR::Txt(rr) => rr.write_presentation(f, ttl),
+			RR::CName(rr) => rr.write_presentation(f, ttl),
+			RR::DName(rr) => rr.write_presentation(f, ttl),
+			RR::PTR(rr) => rr.write_presentation(f, ttl),
+			RR::TLSA(rr) => rr.write_presentation(f, ttl),
+			RR::DnsKey(rr) => rr.write_presentation(f, ttl),
+			RR::DS(rr) => rr.write_presentation(f, ttl),
+			RR::RRSig(rr) => rr.write_presentation(f, ttl),
+			RR::NSec(rr) => rr.write_presentation(f, ttl),
+			RR::NSec3(rr) => rr.write_presentation(f, ttl),
+			RR::SMIMEA(rr) => rr.write_presentation(f, ttl),
+			RR::OpenPGPKey(rr) => rr.write_presentation(f, ttl),
+			RR::URI(rr) => rr.write_presentation(f, ttl),
+			RR::NAPTR(rr) => rr.write_presentation(f, ttl),
+			RR::CERT(rr) => rr.write_presentation(f, ttl),
+			RR::CDS(rr) => rr.write_presentation(f, ttl),
+			RR::CDnsKey(rr) => rr.write_presentation(f, ttl),
+			RR::ZoneMD(rr) => rr.write_presentation(f, ttl),
+		}
+	}
+}
+
+impl RR {
+	/// Gets a [`fmt::Display`] which writes this record in RFC 1035 presentation ("zone file")
+	/// format with the given TTL (rather than the TTL of 0 written by this record's
+	/// [`fmt::Display`] implementation).
+	pub fn display_with_ttl(&self, ttl: u32) -> impl fmt::Display + '_ {
+		struct WithTtl<'a>(&'a RR, u32);
+		impl fmt::Display for WithTtl<'_> {
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				self.0.write_presentation(f, self.1)
+			}
+		}
+		WithTtl(self, ttl)
+	}
+}
+
+macro_rules! impl_presentation_display {
+	($($ty: ident),*) => { $(
+		impl fmt::Display for $ty {
+			/// Writes this record in RFC 1035 presentation ("zone file") format.
+			///
+			/// As records do not carry a TTL, a TTL of 0 is written. Use [`RR::display_with_ttl`] to
+			/// write a different TTL.
+			fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+				self.write_presentation(f, 0)
+			}
+		}
+	)* }
+}
+impl_presentation_display!(A, AAAA, NS, SOA, Txt, TLSA, SMIMEA, OpenPGPKey, URI, NAPTR, CERT, CName, DName,
+	PTR, DnsKey, CDnsKey, DS, CDS, ZoneMD, RRSig, NSec, NSec3, RR);
+
+#[cfg(all(feature = "validation", test))]
+mod tests {
+	use super::*;
+
+	use alloc::vec;
+
+	#[test]
+	fn escaped_names() {
+		let name: Name = "a\\.b\\032C\\\\.Example.".try_into().unwrap();
+		assert_eq!(name.as_str(), "a\\.b\\032c\\\\.example.");
+		assert_eq!(name.wire(), b"\x06a.b c\\\x07example\x00");
+		assert_eq!(name.labels(), 2);
+		assert_eq!(name.trailing_n_labels(1), Some("example."));
+		assert_eq!(name.trailing_n_labels_wire(1), Some(&b"\x07example\x00"[..]));
+		assert_eq!(name.json_str(), "a\\\\.b\\\\032c\\\\\\\\.example.");
+
+		// Arbitrary bytes in labels are supported, but only ASCII letters are lowercased
+		let binary = Name::from_wire(b"\x03\xc3\x89A\x00").unwrap();
+		assert_eq!(binary.as_str(), "\\195\\137a.");
+		assert_eq!(Name::try_from(binary.as_str()).unwrap(), binary);
+		let mut stream = Vec::new();
+		write_rr(&A { name: binary.clone(), address: [0; 4] }, 3600, &mut stream);
+		assert_eq!(parse_rr_stream(&stream).unwrap(), vec![A { name: binary, address: [0; 4] }.into()]);
+
+		for invalid in ["", "a", "a\\.", "a..b.", ".a.", "a\\256.", "a\\01.", "a b.", "\"a\".", "é."] {
+			assert!(Name::try_from(invalid).is_err(), "{}", invalid);
+		}
+		assert!(Name::try_from("a".repeat(63) + ".").is_ok());
+		assert!(Name::try_from("\\000".repeat(64) + ".").is_err());
+		assert!(Name::try_from(("a".repeat(62) + ".").repeat(5)).is_err());
+	}
+
+	#[test]
+	fn rfc4034_sort_test() {
+		// Names sort in canonical order, based on RFC 4034 section 6.1's example
+		let v: Vec<Name> = ["example.", "a.example.", "yljkjljk.a.example.", "Z.a.example.",
+			"zABC.a.EXAMPLE.", "z.example.", "\\001.z.example.", "*.z.example.", "\\200.z.example."]
+			.iter().map(|n| (*n).try_into().unwrap()).collect();
+		let mut sorted = v.clone();
+		sorted.reverse();
+		sorted.sort_unstable();
+		assert_eq!(sorted, v);
+	}
+
+	#[test]
+	fn name_hierarchy() {
+		let name: Name = "www.Example.com.".try_into().unwrap();
+		let com: Name = "com.".try_into().unwrap();
+		let root: Name = ".".try_into().unwrap();
+		assert_eq!(name.iter_labels().collect::<Vec<_>>(), vec![&b"www"[..], b"example", b"com"]);
+		assert_eq!(root.iter_labels().count(), 0);
+
+		assert_eq!(name.parent().unwrap().as_str(), "example.com.");
+		assert_eq!(com.parent(), Some(root.clone()));
+		assert_eq!(root.parent(), None);
+
+		assert!(name.is_subdomain_of(&name));
+		assert!(name.is_subdomain_of(&com));
+		assert!(name.is_subdomain_of(&root));
+		assert!(!com.is_subdomain_of(&name));
+		let xample: Name = "xample.com.".try_into().unwrap();
+		assert!(!xample.is_subdomain_of(&"ample.com.".try_into().unwrap()));
+		let escaped: Name = "a\\.example.com.".try_into().unwrap();
+		assert_eq!(escaped.labels(), 2);
+		assert!(!escaped.is_subdomain_of(&"example.com.".try_into().unwrap()));
+		assert!(escaped.is_subdomain_of(&com));
+
+		let www: Name = "www.".try_into().unwrap();
+		assert_eq!(www.join(&com).unwrap().as_str(), "www.com.");
+		assert_eq!(root.join(&com).unwrap(), com);
+		let long: Name = ("a".repeat(63) + ".").repeat(3).as_str().try_into().unwrap();
+		assert!(long.join(&long).is_err());
+
+		assert_eq!(com.wildcard_of().unwrap().as_str(), "*.com.");
+		assert_eq!(root.wildcard_of().unwrap().as_str(), "*.");
+		assert_eq!(com.wildcard_of().unwrap().parent(), Some(com));
+	}
+
+	#[test]
+	fn unicode_names() {
+		let name = Name::from_unicode("M\u{fc}nchen.de").unwrap();
+		assert_eq!(name.as_str(), "xn--mnchen-3ya.de.");
+		assert_eq!(name.to_unicode(), "m\u{fc}nchen.de.");
+		assert_eq!(Name::from_unicode(&name.to_unicode()).unwrap(), name);
+		assert_eq!(Name::from_unicode(".").unwrap().to_unicode(), ".");
+
+		// Labels which aren't valid punycode are left as-is
+		let name: Name = "xn--abc-.a\\.b.".try_into().unwrap();
+		assert_eq!(name.to_unicode(), "xn--abc-.a\\.b.");
+
+		let rr: RR = A { name: "_x.xn--mnchen-3ya.de.".try_into().unwrap(), address: [0; 4] }.into();
+		assert_eq!(rr.json(), "{\"type\":\"a\",\"name\":\"_x.xn--mnchen-3ya.de.\",\"unicode_name\":\"_x.m\u{fc}nchen.de.\",\"address\":[0, 0, 0, 0]}");
+	}
+
+	#[test]
+	fn email_name_hashing() {
+		// Test vector from RFC 7929 section 3
+		assert_eq!(OpenPGPKey::name_for_email("hugh@example.com").unwrap().as_str(),
+			"c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._openpgpkey.example.com.");
+		assert_eq!(SMIMEA::name_for_email("hugh@Example.com.").unwrap().as_str(),
+			"c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._smimecert.example.com.");
+		assert!(OpenPGPKey::name_for_email("example.com").is_err());
+		assert!(OpenPGPKey::name_for_email("@example.com").is_err());
+		assert!(OpenPGPKey::name_for_email("hugh@").is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn reverse_lookup_names() {
+		let v4: std::net::IpAddr = "192.0.2.1".parse().unwrap();
+		assert_eq!(PTR::name_for_ip(v4).as_str(), "1.2.0.192.in-addr.arpa.");
+		// Example from RFC 3596 section 2.5
+		let v6: std::net::IpAddr = "4321:0:1:2:3:4:567:89ab".parse().unwrap();
+		assert_eq!(PTR::name_for_ip(v6).as_str(),
+			"b.a.9.8.7.6.5.0.4.0.0.0.3.0.0.0.2.0.0.0.1.0.0.0.0.0.0.0.1.2.3.4.ip6.arpa.");
+	}
+
+	#[test]
+	fn uri_naptr_cert_round_trip() {
+		let rrs: Vec<RR> = vec![URI {
+			name: "_sip._udp.example.com.".try_into().unwrap(), priority: 10, weight: 1,
+			target: "sip:alice@example.com".to_owned(),
+		}.into(), NAPTR {
+			name: "4.3.2.1.5.5.5.0.0.8.1.e164.arpa.".try_into().unwrap(), order: 100, preference: 10,
+			flags: b"u".to_vec(), services: b"E2U+sip".to_vec(),
+			regexp: b"!^.*$!sip:info@example.com!".to_vec(), replacement: ".".try_into().unwrap(),
+		}.into(), CERT {
+			name: "example.com.".try_into().unwrap(), cert_type: 1, key_tag: 0, alg: 0,
+			cert: vec![0x30, 0x82, 0x01, 0x0a],
+		}.into()];
+		let mut stream = Vec::new();
+		for rr in rrs.iter() { write_rr(rr, 3600, &mut stream); }
+		assert_eq!(parse_rr_stream(&stream).unwrap(), rrs);
+
+		assert_eq!(rrs[0].json(), "{\"type\":\"uri\",\"name\":\"_sip._udp.example.com.\",\"priority\":10,\"weight\":1,\"target\":\"sip:alice@example.com\"}");
+		assert_eq!(rrs[1].json(), "{\"type\":\"naptr\",\"name\":\"4.3.2.1.5.5.5.0.0.8.1.e164.arpa.\",\"order\":100,\"preference\":10,\"flags\":\"u\",\"services\":\"E2U+sip\",\"regexp\":\"!^.*$!sip:info@example.com!\",\"replacement\":\".\"}");
+		assert_eq!(rrs[2].json(), "{\"type\":\"cert\",\"name\":\"example.com.\",\"cert_type\":1,\"key_tag\":0,\"alg\":0,\"cert\":\"3082010A\"}");
+
+		// URIs must be non-empty
+		let mut empty_uri = Vec::new();
+		write_rr(&URI { name: "example.com.".try_into().unwrap(), priority: 1, weight: 1,
+			target: String::new() }, 3600, &mut empty_uri);
+		assert!(parse_rr_stream(&empty_uri).is_err());
+	}
+
+	#[test]
+	fn cds_cdnskey_zonemd_round_trip() {
+		let rrs: Vec<RR> = vec![CDS {
+			name: "example.com.".try_into().unwrap(), key_tag: 0, alg: 0, digest_type: 0,
+			digest: vec![0],
+		}.into(), CDnsKey {
+			name: "example.com.".try_into().unwrap(), flags: 0, protocol: 3, alg: 0,
+			pubkey: vec![0],
+		}.into(), ZoneMD {
+			name: "example.com.".try_into().unwrap(), serial: 2018031900, scheme: 1, hash_alg: 1,
+			digest: vec![0xc6; 48],
+		}.into()];
+		let mut stream = Vec::new();
+		for rr in rrs.iter() { write_rr(rr, 3600, &mut stream); }
+		assert_eq!(parse_rr_stream(&stream).unwrap(), rrs);
+
+		if let RR::CDS(cds) = &rrs[0] { assert!(cds.is_delete()); } else { panic!(); }
+		if let RR::CDnsKey(key) = &rrs[1] { assert!(key.is_delete()); } else { panic!(); }
+		assert_eq!(rrs[1].json(), "{\"type\":\"cdnskey\",\"name\":\"example.com.\",\"flags\":0,\"protocol\":3,\"alg\":0,\"pubkey\":\"00\"}");
+
+		// ZONEMD digests must be at least 12 bytes
+		let mut short_zonemd = Vec::new();
+		write_rr(&ZoneMD { name: "example.com.".try_into().unwrap(), serial: 1, scheme: 1,
+			hash_alg: 1, digest: vec![0; 11] }, 3600, &mut short_zonemd);
+		assert!(parse_rr_stream(&short_zonemd).is_err());
+	}
+
+	#[test]
+	fn soa_compressed_round_trip() {
+		// A packet with "example.com." at offset 0, followed by an SOA record at the same name
+		// whose owner and RDATA names are compressed against it.
+		let mut packet = Vec::new();
+		write_name(&mut packet, &"example.com.".try_into().unwrap());
+		let rr_start = packet.len();
+		packet.extend_from_slice(&[0xc0, 0]); // example.com.
+		packet.extend_from_slice(&SOA::TYPE.to_be_bytes());
+		packet.extend_from_slice(&1u16.to_be_bytes());
+		packet.extend_from_slice(&3600u32.to_be_bytes());
+		packet.extend_from_slice(&(6 + 13 + 4*5u16).to_be_bytes());
+		packet.extend_from_slice(b"\x03ns1\xc0\x00"); // ns1.example.com.
+		packet.extend_from_slice(b"\x0aHostmaster\xc0\x00"); // hostmaster.example.com.
+		for v in [2024010101u32, 7200, 3600, 1209600, 300] {
+			packet.extend_from_slice(&v.to_be_bytes());
+		}
+
+		let (rr, ttl) = parse_wire_packet_rr(&mut &packet[rr_start..], &packet).unwrap();
+		assert_eq!(ttl, 3600);
+		let soa = if let RR::SOA(soa) = &rr { soa } else { panic!(); };
+		assert_eq!(soa.name.as_str(), "example.com.");
+		assert_eq!(soa.primary_ns.as_str(), "ns1.example.com.");
+		assert_eq!(soa.mailbox.as_str(), "hostmaster.example.com.");
+		assert_eq!((soa.serial, soa.refresh, soa.retry, soa.expire, soa.minimum),
+			(2024010101, 7200, 3600, 1209600, 300));
+
+		// When written, the names must be expanded and lowercased.
+		let mut canonical = Vec::new();
+		write_rr(&rr, ttl, &mut canonical);
+		assert_eq!(parse_rr_stream(&canonical).unwrap(), vec![rr]);
+		let mut expected = Vec::new();
+		write_name(&mut expected, &"hostmaster.example.com.".try_into().unwrap());
+		assert!(canonical.windows(expected.len()).any(|w| w == &expected[..]));
+	}
+
+	#[test]
+	fn nsec_type_mask_encoding() {
+		// The encoding previously used a flat bitmap of all 65536 types, which we compare against
+		fn flat_bitmap_write(types: &[u8; 8192]) -> Vec<u8> {
+			let mut res = Vec::new();
+			for (idx, flags) in types.chunks(32).enumerate() {
+				if let Some(last_nonzero_idx) = flags.iter().rposition(|flag| *flag != 0) {
+					res.push(idx as u8);
+					res.push(last_nonzero_idx as u8 + 1);
+					res.extend_from_slice(&flags[..last_nonzero_idx + 1]);
+				}
+			}
+			res
+		}
+
+		let mut seed = 42u64;
+		let mut rand = move || { seed ^= seed << 13; seed ^= seed >> 7; seed ^= seed << 17; seed };
+		for _ in 0..1000 {
+			let types: Vec<u16> = (0..rand() % 16).map(|_| match rand() % 3 {
+				0 => (rand() % 64) as u16, 1 => (rand() % 1024) as u16, _ => rand() as u16,
+			}).collect();
+			let mut flat = [0u8; 8192];
+			for ty in types.iter() { flat[*ty as usize >> 3] |= 1 << (7 - (ty % 8)); }
+			let mask = NSecTypeMask::from_types(&types);
+
+			let mut written = Vec::new();
+			write_nsec_types_bitmap(&mut written, &mask);
+			assert_eq!(written, flat_bitmap_write(&flat));
+			assert_eq!(nsec_types_bitmap_len(&mask) as usize, written.len());
+			assert_eq!(read_nsec_types_bitmap(&mut &written[..]).unwrap(), mask);
+
+			let mut sorted = types.clone();
+			sorted.sort_unstable();
+			sorted.dedup();
+			assert_eq!(mask.types().collect::<Vec<_>>(), sorted);
+			for _ in 0..16 {
+				let ty = if rand() % 2 == 0 && !types.is_empty() { types[rand() as usize % types.len()] } else { rand() as u16 };
+				assert_eq!(mask.contains_type(ty), flat[ty as usize >> 3] & (1 << (7 - (ty % 8))) != 0);
+			}
+		}
+
+		// Non-canonical encodings are rejected
+		assert_eq!(read_nsec_types_bitmap(&mut &b"\x00\x01\x40\x01\x01\x40"[..]).unwrap(),
+			NSecTypeMask::from_types(&[1, 257]));
+		assert_eq!(read_nsec_types_bitmap(&mut &b""[..]).unwrap(), NSecTypeMask::new());
+		for invalid in [
+			&b"\x01\x01\x40\x00\x01\x40"[..], // Out of order windows
+			&b"\x00\x01\x40\x00\x01\x20"[..], // Repeated windows
+			&b"\x00\x01\x40\x01\x00"[..], // Empty bitmap
+			&b"\x00\x02\x40\x00"[..], // Trailing zero byte
+			&b"\x00\x21\x40\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01"[..],
+			&b"\x00\x02\x40"[..], &b"\x00"[..],
+		] {
+			assert!(read_nsec_types_bitmap(&mut &invalid[..]).is_err());
+		}
+
+		// The mask no longer carries a full bitmap inline
+		assert!(core::mem::size_of::<NSec3>() < 256);
+		assert!(core::mem::size_of::<RR>() < 256);
+	}
+
+	#[test]
+	fn type_names() {
+		for ty in RR_TYPES {
+			assert_eq!(RR::ty_from_name(RR::ty_to_rr_name(ty).unwrap()), Some(ty));
+		}
+		assert_eq!(RR::ty_from_name("tXt"), Some(Txt::TYPE));
+		assert_eq!(RR::ty_from_name("OpenPGPKey"), Some(OpenPGPKey::TYPE));
+		// Types without an `RR` variant are only understood by the presentation format parser
+		assert_eq!(RR::ty_from_name("MX"), None);
+		assert_eq!(RR::ty_from_name("TYPE16"), None);
+		assert_eq!(type_from_mnemonic("MX"), Some(15));
+		assert_eq!(type_from_mnemonic("type16"), Some(Txt::TYPE));
+	}
+}
@@ -1,7 +1,9 @@
 //! Logic to read and write resource record (streams)
 
 use alloc::vec::Vec;
-use alloc::string::String;
+
+use core::cmp::Ordering;
+use core::fmt;
 
 use crate::rr::*;
 use crate::query::QueryBuf;
@@ -26,59 +28,55 @@ pub(crate) fn read_u32(inp: &mut &[u8]) -> Result<u32, ()> {
 	Ok(u32::from_be_bytes(bytes))
 }
 
-pub(crate) fn read_u8_len_prefixed_bytes(inp: &mut &[u8]) -> Result<Vec<u8>, ()> {
+pub(crate) fn read_u8_len_prefixed_slice<'a>(inp: &mut &'a [u8]) -> Result<&'a [u8], ()> {
 	let len = *inp.first().ok_or(())?;
 	*inp = &inp[1..];
 	if inp.len() < len.into() { return Err(()); }
-	let mut res = Vec::with_capacity(len.into());
-	res.extend_from_slice(&inp[..len.into()]);
+	let res = &inp[..len.into()];
 	*inp = &inp[len.into()..];
 	Ok(res)
 }
+pub(crate) fn read_u8_len_prefixed_bytes(inp: &mut &[u8]) -> Result<Vec<u8>, ()> {
+	read_u8_len_prefixed_slice(inp).map(|bytes| bytes.to_vec())
+}
 
-pub(crate) fn write_nsec_types_bitmap<W: Writer>(out: &mut W, types: &[u8; 8192]) {
-	for (idx, flags) in types.chunks(32).enumerate() {
-		debug_assert_eq!(flags.len(), 32);
-		if flags != [0; 32] {
-			let last_nonzero_idx = flags.iter().rposition(|flag| *flag != 0)
-				.unwrap_or_else(|| { debug_assert!(false); 0 });
-			out.write(&(idx as u8).to_be_bytes());
-			out.write(&(last_nonzero_idx as u8 + 1).to_be_bytes());
-			out.write(&flags[..last_nonzero_idx + 1]);
-		}
-	}
+pub(crate) fn write_nsec_types_bitmap<W: Writer>(out: &mut W, types: &NSecTypeMask) {
+	out.write(types.wire_bytes());
 }
-pub(crate) fn nsec_types_bitmap_len(types: &[u8; 8192]) -> u16 {
-	let mut total_len = 0;
-	for flags in types.chunks(32) {
-		debug_assert_eq!(flags.len(), 32);
-		if flags != [0; 32] {
-			total_len += 3 + flags.iter().rposition(|flag| *flag != 0)
-				.unwrap_or_else(|| { debug_assert!(false); 0 }) as u16;
-		}
-	}
-	total_len
+pub(crate) fn nsec_types_bitmap_len(types: &NSecTypeMask) -> u16 {
+	// At most 256 windows of 34 bytes each
+	types.wire_bytes().len() as u16
 }
 
-pub(crate) fn read_nsec_types_bitmap(inp: &mut &[u8]) -> Result<[u8; 8192], ()> {
-	let mut res = [0; 8192];
+/// Reads an RFC 4034 type bitmap, which must be in canonical form (as required by RFC 4034
+/// section 4.1.2) - windows must be in increasing order, and their bitmaps must be non-empty and
+/// not end in a zero byte.
+pub(crate) fn read_nsec_types_bitmap(inp: &mut &[u8]) -> Result<NSecTypeMask, ()> {
+	let bitmap = read_nsec_types_bitmap_slice(inp)?;
+	Ok(NSecTypeMask::from_canonical_wire(bitmap.to_vec()))
+}
+/// Reads an RFC 4034 type bitmap as in [`read_nsec_types_bitmap`], without copying it.
+fn read_nsec_types_bitmap_slice<'a>(inp: &mut &'a [u8]) -> Result<&'a [u8], ()> {
+	let bitmap = *inp;
+	let mut last_block = None;
 	while !inp.is_empty() {
 		let block = *inp.get(0).ok_or(())?;
-		let len = *inp.get(1).ok_or(())?;
+		let len = *inp.get(1).ok_or(())? as usize;
 		*inp = &inp[2..];
-		if inp.len() < block as usize * 32 + len as usize { return Err(()); }
-		res[block as usize * 32..block as usize * 32 + len as usize]
-			.copy_from_slice(&inp[..len as usize]);
-		*inp = &inp[len as usize..];
+		if last_block.map(|last| block <= last).unwrap_or(false) { return Err(()); }
+		if len == 0 || len > 32 || inp.len() < len { return Err(()); }
+		if inp[len - 1] == 0 { return Err(()); }
+		last_block = Some(block);
+		*inp = &inp[len..];
 	}
-	Ok(res)
+	Ok(bitmap)
 }
 
-fn do_read_wire_packet_labels(inp: &mut &[u8], wire_packet: &[u8], name: &mut String, recursion_limit: usize) -> Result<(), ()> {
+fn do_read_wire_packet_labels(inp: &mut &[u8], wire_packet: &[u8], name: &mut Vec<u8>, recursion_limit: usize) -> Result<(), ()> {
 	loop {
 		let len = read_u8(inp)? as usize;
 		if len == 0 {
-			if name.is_empty() { *name += "."; }
+			name.push(0);
 			break;
 		} else if len >= 0xc0 && recursion_limit > 0 {
 			let offs = ((len & !0xc0) << 8) | read_u8(inp)? as usize;
@@ -86,23 +84,23 @@ fn do_read_wire_packet_labels(inp: &mut &[u8], wire_packet: &[u8], name: &mut St
 			do_read_wire_packet_labels(&mut &wire_packet[offs..], wire_packet, name, recursion_limit - 1)?;
 			break;
 		}
-		if inp.len() <= len { return Err(()); }
-		*name += core::str::from_utf8(&inp[..len]).map_err(|_| ())?;
-		*name += ".";
+		if len > 63 || inp.len() <= len { return Err(()); }
+		name.push(len as u8);
+		name.extend_from_slice(&inp[..len]);
 		*inp = &inp[len..];
 		if name.len() > 255 { return Err(()); }
 	}
 	Ok(())
 }
 
-fn read_wire_packet_labels(inp: &mut &[u8], wire_packet: &[u8], name: &mut String) -> Result<(), ()> {
+fn read_wire_packet_labels(inp: &mut &[u8], wire_packet: &[u8], name: &mut Vec<u8>) -> Result<(), ()> {
 	do_read_wire_packet_labels(inp, wire_packet, name, 255)
 }
 
 pub(crate) fn read_wire_packet_name(inp: &mut &[u8], wire_packet: &[u8]) -> Result<Name, ()> {
-	let mut name = String::with_capacity(1024);
+	let mut name = Vec::with_capacity(256);
 	read_wire_packet_labels(inp, wire_packet, &mut name)?;
-	name.try_into()
+	Name::from_wire(&name)
 }
 
 pub(crate) trait Writer { fn write(&mut self, buf: &[u8]); }
@@ -110,27 +108,11 @@ impl Writer for Vec<u8> { fn write(&mut self, buf: &[u8]) { self.extend_from_sli
 impl Writer for QueryBuf { fn write(&mut self, buf: &[u8]) { self.extend_from_slice(buf); } }
 #[cfg(feature = "validation")]
 impl Writer for crate::crypto::hash::Hasher { fn write(&mut self, buf: &[u8]) { self.update(buf); } }
-pub(crate) fn write_name<W: Writer>(out: &mut W, name: &str) {
-	let canonical_name = name.to_ascii_lowercase();
-	if canonical_name == "." {
-		out.write(&[0]);
-	} else {
-		for label in canonical_name.split('.') {
-			out.write(&(label.len() as u8).to_be_bytes());
-			out.write(label.as_bytes());
-		}
-	}
+pub(crate) fn write_name<W: Writer>(out: &mut W, name: &Name) {
+	out.write(name.wire());
 }
 pub(crate) fn name_len(name: &Name) -> u16 {
-	if name.as_str() == "." {
-		1
-	} else {
-		let mut res = 0;
-		for label in name.split('.') {
-			res += 1 + label.len();
-		}
-		res as u16
-	}
+	name.wire().len() as u16
 }
 
 pub(crate) fn parse_wire_packet_rr(inp: &mut &[u8], wire_packet: &[u8]) -> Result<(RR, u32), ()> {
@@ -144,14 +126,29 @@ pub(crate) fn parse_wire_packet_rr(inp: &mut &[u8], wire_packet: &[u8]) -> Resul
 	let data = &inp[..data_len];
 	*inp = &inp[data_len..];
 
+	Ok((parse_rdata(name, ty, data, wire_packet)?, ttl))
+}
+
+/// Parses the RDATA of a record of the given type into an [`RR`].
+pub(crate) fn parse_rdata(name: Name, ty: u16, data: &[u8], wire_packet: &[u8]) -> Result<RR, ()> {
 	let rr = match ty {
 		A::TYPE => RR::A(A::read_from_data(name, data, wire_packet)?),
 		AAAA::TYPE => RR::AAAA(AAAA::read_from_data(name, data, wire_packet)?),
 		NS::TYPE => RR::NS(NS::read_from_data(name, data, wire_packet)?),
+		SOA::TYPE => RR::SOA(SOA::read_from_data(name, data, wire_packet)?),
 		Txt::TYPE => RR::Txt(Txt::read_from_data(name, data, wire_packet)?),
 		CName::TYPE => RR::CName(CName::read_from_data(name, data, wire_packet)?),
 		DName::TYPE => RR::DName(DName::read_from_data(name, data, wire_packet)?),
+		PTR::TYPE => RR::PTR(PTR::read_from_data(name, data, wire_packet)?),
 		TLSA::TYPE => RR::TLSA(TLSA::read_from_data(name, data, wire_packet)?),
+		SMIMEA::TYPE => RR::SMIMEA(SMIMEA::read_from_data(name, data, wire_packet)?),
+		OpenPGPKey::TYPE => RR::OpenPGPKey(OpenPGPKey::read_from_data(name, data, wire_packet)?),
+		URI::TYPE => RR::URI(URI::read_from_data(name, data, wire_packet)?),
+		NAPTR::TYPE => RR::NAPTR(NAPTR::read_from_data(name, data, wire_packet)?),
+		CERT::TYPE => RR::CERT(CERT::read_from_data(name, data, wire_packet)?),
+		CDS::TYPE => RR::CDS(CDS::read_from_data(name, data, wire_packet)?),
+		CDnsKey::TYPE => RR::CDnsKey(CDnsKey::read_from_data(name, data, wire_packet)?),
+		ZoneMD::TYPE => RR::ZoneMD(ZoneMD::read_from_data(name, data, wire_packet)?),
 		DnsKey::TYPE => RR::DnsKey(DnsKey::read_from_data(name, data, wire_packet)?),
 		DS::TYPE => RR::DS(DS::read_from_data(name, data, wire_packet)?),
 		RRSig::TYPE => RR::RRSig(RRSig::read_from_data(name, data, wire_packet)?),
@@ -159,7 +156,7 @@ pub(crate) fn parse_wire_packet_rr(inp: &mut &[u8], wire_packet: &[u8]) -> Resul
 		NSec3::TYPE => RR::NSec3(NSec3::read_from_data(name, data, wire_packet)?),
 		_ => return Err(()),
 	};
-	Ok((rr, ttl))
+	Ok(rr)
 }
 
 pub(crate) fn parse_rr(inp: &mut &[u8]) -> Result<RR, ()> {
@@ -169,7 +166,8 @@ pub(crate) fn parse_rr(inp: &mut &[u8]) -> Result<RR, ()> {
 /// Parse a stream of [`RR`]s from the format described in [RFC 9102](https://www.rfc-editor.org/rfc/rfc9102.html).
 ///
 /// Note that this is only the series of `AuthenticationChain` records, and does not read the
-/// `ExtSupportLifetime` field at the start of a `DnssecChainExtension`.
+/// `ExtSupportLifetime` field at the start of a `DnssecChainExtension` - see
+/// [`DnssecChainExtension`] for that.
 pub fn parse_rr_stream(mut inp: &[u8]) -> Result<Vec<RR>, ()> {
 	let mut res = Vec::with_capacity(32);
 	while !inp.is_empty() {
@@ -181,6 +179,367 @@ pub fn parse_rr_stream(mut inp: &[u8]) -> Result<Vec<RR>, ()> {
 	Ok(res)
 }
 
+/// A borrowed, wire-encoded domain name, pointing into the proof it was read from.
+///
+/// Unlike a [`Name`], ASCII letters are not lowercased, so comparisons between [`NameRef`]s are
+/// case-insensitive.
+#[derive(Clone, Copy)]
+pub struct NameRef<'a> {
+	wire: &'a [u8],
+}
+impl<'a> NameRef<'a> {
+	#[cfg(feature = "validation")]
+	/// The root name.
+	pub(crate) const ROOT: NameRef<'static> = NameRef { wire: &[0] };
+
+	/// Reads a name (which may not contain compression pointers) from the front of `inp`.
+	pub(crate) fn read(inp: &mut &'a [u8]) -> Result<Self, ()> {
+		let start = *inp;
+		loop {
+			let len = read_u8(inp)? as usize;
+			if len == 0 { break; }
+			if len > 63 || inp.len() <= len { return Err(()); }
+			*inp = &inp[len..];
+		}
+		let wire = &start[..start.len() - inp.len()];
+		if wire.len() > 255 { return Err(()); }
+		Ok(NameRef { wire })
+	}
+	#[cfg(feature = "validation")]
+	/// Gets the wire encoding of this name, which may contain uppercase letters.
+	pub(crate) fn wire(&self) -> &'a [u8] { self.wire }
+	/// Gets an iterator over the (raw, unescaped) labels in this name, from the leftmost label up
+	/// to (but not including) the empty root label.
+	pub(crate) fn iter_labels(&self) -> impl Iterator<Item = &'a [u8]> {
+		let mut remaining = self.wire;
+		core::iter::from_fn(move || {
+			let len = *remaining.first()? as usize;
+			if len == 0 { return None; }
+			let label = &remaining[1..len + 1];
+			remaining = &remaining[len + 1..];
+			Some(label)
+		})
+	}
+	/// Gets the number of labels in this name
+	pub fn labels(&self) -> u8 {
+		self.iter_labels().count() as u8
+	}
+	/// Gets the last `n` labels in this name (which are also a valid name).
+	pub fn trailing_n_labels(&self, n: u8) -> Option<NameRef<'a>> {
+		let labels = self.labels();
+		if n > labels { return None; }
+		let mut wire = self.wire;
+		for _ in 0..labels - n {
+			wire = &wire[wire[0] as usize + 1..];
+		}
+		Some(NameRef { wire })
+	}
+	/// Returns true if this name is equal to or below `zone` in the DNS hierarchy.
+	pub fn is_subdomain_of(&self, zone: NameRef) -> bool {
+		self.trailing_n_labels(zone.labels()) == Some(zone)
+	}
+	#[cfg(feature = "validation")]
+	/// Writes the canonical (i.e. lowercased) wire encoding of this name.
+	pub(crate) fn write_canonical<W: Writer>(&self, out: &mut W) {
+		let mut buf = [0; 255];
+		let buf = &mut buf[..self.wire.len()];
+		buf.copy_from_slice(self.wire);
+		buf.make_ascii_lowercase();
+		out.write(buf);
+	}
+	/// Copies this name into an owned [`Name`].
+	pub fn to_name(&self) -> Name {
+		Name::from_wire(self.wire).expect("NameRefs are always valid names")
+	}
+}
+impl<'a> From<&'a Name> for NameRef<'a> {
+	fn from(name: &'a Name) -> NameRef<'a> { NameRef { wire: name.wire() } }
+}
+impl PartialEq for NameRef<'_> {
+	fn eq(&self, o: &NameRef) -> bool { self.wire.eq_ignore_ascii_case(o.wire) }
+}
+impl Eq for NameRef<'_> {}
+impl Ord for NameRef<'_> {
+	fn cmp(&self, o: &NameRef) -> Ordering {
+		self.wire.iter().map(|b| b.to_ascii_lowercase()).cmp(o.wire.iter().map(|b| b.to_ascii_lowercase()))
+	}
+}
+impl PartialOrd for NameRef<'_> {
+	fn partial_cmp(&self, o: &NameRef) -> Option<Ordering> { Some(self.cmp(o)) }
+}
+impl fmt::Debug for NameRef<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		f.debug_tuple("NameRef").field(&self.to_name().as_str()).finish()
+	}
+}
+
+/// A borrowed resource record, pointing into the proof it was parsed from by
+/// [`parse_rr_stream_ref`].
+///
+/// Its RDATA is checked to be valid for its type when it is parsed, but is otherwise left in its
+/// wire encoding until converted to an [`RR`] with [`RRRef::to_rr`].
+#[derive(Clone, Copy)]
+pub struct RRRef<'a> {
+	name: NameRef<'a>,
+	ty: u16,
+	data: &'a [u8],
+	/// The range in `data` which contains domain names, which are lowercased in the record's
+	/// canonical form.
+	names: (u16, u16),
+}
+impl<'a> RRRef<'a> {
+	/// Gets the name this record is at.
+	pub fn name(&self) -> NameRef<'a> { self.name }
+	/// Gets the type of this record (i.e. one of the [`Record::ty`]s of the records supported by
+	/// [`RR`]).
+	pub fn ty(&self) -> u16 { self.ty }
+	/// Gets the RDATA of this record in its wire encoding.
+	///
+	/// Note that, unlike the canonical encoding used when signing records, domain names in the
+	/// RDATA may contain uppercase letters.
+	pub fn rdata(&self) -> &'a [u8] { self.data }
+	/// Copies this record into an owned [`RR`].
+	pub fn to_rr(&self) -> RR {
+		parse_rdata(self.name.to_name(), self.ty, self.data, &[])
+			.expect("RRRef RDATA is checked when parsing")
+	}
+	/// Gets the bytes of the canonical encoding of this record's RDATA.
+	fn canonical_rdata(&self) -> impl Iterator<Item = u8> + 'a {
+		let (names_start, names_end) = (self.names.0 as usize, self.names.1 as usize);
+		self.data.iter().enumerate().map(move |(idx, b)| {
+			if idx >= names_start && idx < names_end { b.to_ascii_lowercase() } else { *b }
+		})
+	}
+	#[cfg(feature = "validation")]
+	/// Writes the canonical encoding of this record's RDATA, prefixed by a u16 length.
+	pub(crate) fn write_canonical_u16_len_prefixed_data<W: Writer>(&self, out: &mut W) {
+		let (names_start, names_end) = (self.names.0 as usize, self.names.1 as usize);
+		out.write(&(self.data.len() as u16).to_be_bytes());
+		out.write(&self.data[..names_start]);
+		// Names are at most 255 bytes and no records have more than two
+		let mut buf = [0; 255 * 2];
+		let names = &mut buf[..names_end - names_start];
+		names.copy_from_slice(&self.data[names_start..names_end]);
+		names.make_ascii_lowercase();
+		out.write(names);
+		out.write(&self.data[names_end..]);
+	}
+}
+impl PartialEq for RRRef<'_> {
+	fn eq(&self, o: &RRRef) -> bool {
+		self.name == o.name && self.ty == o.ty && self.canonical_rdata().eq(o.canonical_rdata())
+	}
+}
+impl Eq for RRRef<'_> {}
+impl Ord for RRRef<'_> {
+	fn cmp(&self, o: &RRRef) -> Ordering {
+		self.name.cmp(&o.name)
+			.then_with(|| self.ty.cmp(&o.ty))
+			.then_with(|| self.canonical_rdata().cmp(o.canonical_rdata()))
+	}
+}
+impl PartialOrd for RRRef<'_> {
+	fn partial_cmp(&self, o: &RRRef) -> Option<Ordering> { Some(self.cmp(o)) }
+}
+impl fmt::Debug for RRRef<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		f.debug_struct("RRRef")
+			.field("name", &self.name).field("ty", &self.ty).field("data", &self.data)
+			.finish()
+	}
+}
+
+/// Checks that the given RDATA is valid for the given record type, accepting exactly what the
+/// corresponding [`StaticRecord::read_from_data`] accepts (without compression pointers).
+///
+/// Returns the range of the RDATA which contains domain names.
+fn check_rdata(ty: u16, data: &[u8]) -> Result<(usize, usize), ()> {
+	let mut inp = data;
+	let offset = |inp: &[u8]| data.len() - inp.len();
+	match ty {
+		A::TYPE => if data.len() != 4 { return Err(()); },
+		AAAA::TYPE => if data.len() != 16 { return Err(()); },
+		NS::TYPE|CName::TYPE|DName::TYPE|PTR::TYPE => {
+			NameRef::read(&mut inp)?;
+			if !inp.is_empty() { return Err(()); }
+			return Ok((0, data.len()));
+		},
+		SOA::TYPE => {
+			NameRef::read(&mut inp)?;
+			NameRef::read(&mut inp)?;
+			if inp.len() != 4*5 { return Err(()); }
+			return Ok((0, offset(inp)));
+		},
+		Txt::TYPE => {
+			while !inp.is_empty() {
+				if read_u8_len_prefixed_slice(&mut inp)?.is_empty() { return Err(()); }
+			}
+		},
+		TLSA::TYPE|SMIMEA::TYPE => if data.len() < 3 { return Err(()); },
+		OpenPGPKey::TYPE => {},
+		URI::TYPE => if data.len() < 5 || !data[4..].is_ascii() { return Err(()); },
+		NAPTR::TYPE => {
+			read_u16(&mut inp)?;
+			read_u16(&mut inp)?;
+			for _ in 0..3 { read_u8_len_prefixed_slice(&mut inp)?; }
+			let names_start = offset(inp);
+			NameRef::read(&mut inp)?;
+			if !inp.is_empty() { return Err(()); }
+			return Ok((names_start, data.len()));
+		},
+		CERT::TYPE => if data.len() < 5 { return Err(()); },
+		DnsKey::TYPE|CDnsKey::TYPE|DS::TYPE|CDS::TYPE => if data.len() < 4 { return Err(()); },
+		ZoneMD::TYPE => if data.len() < 6 + 12 { return Err(()); },
+		RRSig::TYPE => {
+			if data.len() < 18 { return Err(()); }
+			inp = &data[18..];
+			NameRef::read(&mut inp)?;
+			return Ok((18, offset(inp)));
+		},
+		NSec::TYPE => {
+			NameRef::read(&mut inp)?;
+			let names_end = offset(inp);
+			read_nsec_types_bitmap_slice(&mut inp)?;
+			return Ok((0, names_end));
+		},
+		NSec3::TYPE => {
+			if data.len() < 4 { return Err(()); }
+			inp = &data[4..];
+			read_u8_len_prefixed_slice(&mut inp)?;
+			read_u8_len_prefixed_slice(&mut inp)?;
+			read_nsec_types_bitmap_slice(&mut inp)?;
+		},
+		_ => return Err(()),
+	}
+	Ok((0, 0))
+}
+
+fn parse_rr_ref<'a>(inp: &mut &'a [u8]) -> Result<RRRef<'a>, ()> {
+	let name = NameRef::read(inp)?;
+	let ty = read_u16(inp)?;
+	let class = read_u16(inp)?;
+	if class != 1 { return Err(()); } // We only support the INternet
+	let _ttl = read_u32(inp)?;
+	let data_len = read_u16(inp)? as usize;
+	if inp.len() < data_len { return Err(()); }
+	let data = &inp[..data_len];
+	*inp = &inp[data_len..];
+
+	let (names_start, names_end) = check_rdata(ty, data)?;
+	Ok(RRRef { name, ty, data, names: (names_start as u16, names_end as u16) })
+}
+
+/// Parse a stream of [`RRRef`]s from the format described in
+/// [RFC 9102](https://www.rfc-editor.org/rfc/rfc9102.html), as in [`parse_rr_stream`].
+///
+/// Rather than copying each record's name and data, the returned [`RRRef`]s point into `inp`,
+/// avoiding any per-record allocations. They can be validated with
+/// [`crate::validation::verify_rr_stream_ref`].
+///
+/// Exactly the same records are accepted as by [`parse_rr_stream`].
+pub fn parse_rr_stream_ref<'a>(mut inp: &'a [u8]) -> Result<Vec<RRRef<'a>>, ()> {
+	let mut res = Vec::with_capacity(32);
+	while !inp.is_empty() {
+		res.push(parse_rr_ref(&mut inp)?);
+	}
+	Ok(res)
+}
+
+/// The TLS extension type of the RFC 9102 `dnssec_chain` extension, whose body is a
+/// [`DnssecChainExtension`].
+pub const DNSSEC_CHAIN_EXTENSION_TYPE: u16 = 59;
+
+/// An [RFC 9102](https://www.rfc-editor.org/rfc/rfc9102.html) `DnssecChainExtension`, i.e. the
+/// body of a TLS `dnssec_chain` extension (see [`DNSSEC_CHAIN_EXTENSION_TYPE`]).
+///
+/// This is made up of the `ExtSupportLifetime` followed by the u16-length-prefixed
+/// `AuthenticationChain`, a series of records as written by [`write_rr`] (e.g. a proof built with
+/// [`crate::query::ProofBuilder`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnssecChainExtension {
+	/// The number of hours for which the TLS server commits to continue serving this extension.
+	///
+	/// A value of zero indicates that the server makes no such commitment, and clients should
+	/// clear any pin they have for the server to serve this extension.
+	pub ext_support_lifetime: u16,
+	authentication_chain: Vec<u8>,
+}
+impl DnssecChainExtension {
+	/// Builds a new extension from the given `ExtSupportLifetime` and `AuthenticationChain`.
+	///
+	/// Fails if the `authentication_chain` is empty, too long to be encoded, or can't be parsed
+	/// by [`parse_rr_stream`].
+	pub fn new(ext_support_lifetime: u16, authentication_chain: Vec<u8>) -> Result<Self, ()> {
+		if authentication_chain.is_empty() || authentication_chain.len() > u16::MAX as usize {
+			return Err(());
+		}
+		let mut inp = &authentication_chain[..];
+		while !inp.is_empty() {
+			parse_rr_ref(&mut inp)?;
+		}
+		Ok(DnssecChainExtension { ext_support_lifetime, authentication_chain })
+	}
+	/// Gets the `AuthenticationChain`, which can be read with [`parse_rr_stream`] or
+	/// [`parse_rr_stream_ref`].
+	pub fn authentication_chain(&self) -> &[u8] { &self.authentication_chain }
+	/// Parses an extension from the body of a TLS `dnssec_chain` extension.
+	pub fn parse(mut data: &[u8]) -> Result<Self, ()> {
+		let ext_support_lifetime = read_u16(&mut data)?;
+		let chain_len = read_u16(&mut data)? as usize;
+		if data.len() != chain_len { return Err(()); }
+		Self::new(ext_support_lifetime, data.to_vec())
+	}
+	/// Writes this extension, as it should appear in the body of a TLS `dnssec_chain` extension,
+	/// to the given `Vec`.
+	pub fn write(&self, out: &mut Vec<u8>) {
+		out.extend_from_slice(&self.ext_support_lifetime.to_be_bytes());
+		out.extend_from_slice(&(self.authentication_chain.len() as u16).to_be_bytes());
+		out.extend_from_slice(&self.authentication_chain);
+	}
+}
+
+#[cfg(dnssec_prover_fuzzing)]
+/// Read some input and parse it as a stream of records, checking that the records are written
+/// back out byte-for-byte identically (other than names being lowercased), for fuzzing.
+pub fn fuzz_rr_round_trip(data: &[u8]) {
+	let refs = parse_rr_stream_ref(data);
+	let mut inp = data;
+	let mut written = Vec::with_capacity(data.len());
+	let mut rrs = Vec::new();
+	while !inp.is_empty() {
+		match parse_wire_packet_rr(&mut inp, &[]) {
+			Ok((rr, ttl)) => {
+				write_rr(&rr, ttl, &mut written);
+				rrs.push(rr);
+			},
+			Err(()) => {
+				assert!(refs.is_err());
+				return;
+			},
+		}
+	}
+	assert!(written.eq_ignore_ascii_case(data));
+
+	let refs = refs.expect("Borrowed parsing should accept the same records as owned parsing");
+	assert_eq!(refs.len(), rrs.len());
+	for (rr_ref, rr) in refs.iter().zip(rrs.iter()) {
+		assert_eq!(rr_ref.to_rr(), *rr);
+		let mut canonical_data = Vec::new();
+		rr_ref.write_canonical_u16_len_prefixed_data(&mut canonical_data);
+		let mut rr_data = Vec::new();
+		rr.write_u16_len_prefixed_data(&mut rr_data);
+		assert_eq!(canonical_data, rr_data);
+	}
+
+	let mut rewritten = Vec::with_capacity(written.len());
+	let mut inp = &written[..];
+	while !inp.is_empty() {
+		let (rr, ttl) = parse_wire_packet_rr(&mut inp, &[]).expect("Records we wrote should always be parseable");
+		write_rr(&rr, ttl, &mut rewritten);
+	}
+	assert_eq!(rewritten, written);
+}
+
 /// Writes the given resource record in its wire encoding to the given `Vec`.
 ///
 /// An [RFC 9102](https://www.rfc-editor.org/rfc/rfc9102.html) `AuthenticationChain` is simply a
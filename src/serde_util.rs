@@ -0,0 +1,95 @@
+//! Helpers for implementing `serde` support for our types.
+
+/// (De)serializes binary data as an uppercase hex string, matching the encoding used by
+/// [`crate::rr::RR::json`].
+pub(crate) mod hex {
+	use alloc::string::String;
+	use alloc::vec::Vec;
+	use core::fmt::Write;
+
+	use serde::{Deserialize, Deserializer, Serializer};
+	use serde::de::Error;
+
+	pub(crate) fn encode(bytes: &[u8]) -> String {
+		let mut res = String::with_capacity(bytes.len() * 2);
+		for b in bytes.iter() {
+			write!(&mut res, "{:02X}", b).expect("Write to a String shouldn't fail");
+		}
+		res
+	}
+
+	pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&encode(bytes))
+	}
+
+	pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+		let hex = String::deserialize(deserializer)?;
+		crate::hex::decode(&hex).map_err(|()| D::Error::custom("invalid hex string"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use alloc::string::String;
+	use alloc::vec;
+	use alloc::vec::Vec;
+
+	use crate::rr::*;
+	use crate::query::ProofBuildingError;
+	#[cfg(feature = "validation")]
+	use crate::validation::*;
+
+	fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + core::fmt::Debug>(val: T) -> String {
+		let json = serde_json::to_string(&val).unwrap();
+		assert_eq!(serde_json::from_str::<T>(&json).unwrap(), val);
+		json
+	}
+
+	#[test]
+	fn serde_round_trip() {
+		let name: Name = "a\\.b.example.".try_into().unwrap();
+		assert_eq!(round_trip(name), "\"a\\\\.b.example.\"");
+		assert!(serde_json::from_str::<Name>("\"a..b.\"").is_err());
+
+		let dnskey = DnsKey { name: ".".try_into().unwrap(), flags: 257, protocol: 3, alg: 8, pubkey: vec![0xab, 1] };
+		assert_eq!(round_trip(dnskey.clone()),
+			"{\"name\":\".\",\"flags\":257,\"protocol\":3,\"alg\":8,\"pubkey\":\"AB01\"}");
+		assert!(serde_json::from_str::<DnsKey>("{\"name\":\".\",\"flags\":257,\"protocol\":3,\"alg\":8,\"pubkey\":\"AB0\"}").is_err());
+
+		let rrs: Vec<RR> = vec![
+			dnskey.into(),
+			A { name: "a.".try_into().unwrap(), address: [192, 0, 2, 1] }.into(),
+			Txt { name: "a.".try_into().unwrap(), data: TxtBytes::new(&[0x42; 300]).unwrap() }.into(),
+			NAPTR {
+				name: "a.".try_into().unwrap(), order: 1, preference: 2, flags: b"u".to_vec(),
+				services: b"E2U+sip".to_vec(), regexp: Vec::new(), replacement: ".".try_into().unwrap(),
+			}.into(),
+			RRSig {
+				name: "a.".try_into().unwrap(), ty: Txt::TYPE, alg: 13, labels: 1, orig_ttl: 3600,
+				expiration: 1710201600, inception: 0, key_tag: 1234, key_name: "a.".try_into().unwrap(),
+				signature: vec![42; 64],
+			}.into(),
+			NSec3 {
+				name: "2vptu5timamqttgl4luu9kg21e0aor3s.a.".try_into().unwrap(), hash_algo: 1, flags: 0,
+				hash_iterations: 0, salt: vec![1, 2], next_name_hash: vec![0x42; 20],
+				types: NSecTypeMask::from_types(&[Txt::TYPE, RRSig::TYPE, 1234]),
+			}.into(),
+		];
+		for rr in rrs.iter() { round_trip(rr.clone()); }
+		let nsec3_json = serde_json::to_string(&rrs[5]).unwrap();
+		assert!(nsec3_json.contains("\"salt\":\"0102\""));
+		assert!(nsec3_json.contains("\"types\":[16,46,1234]"));
+
+		assert_eq!(round_trip(ProofBuildingError::NoSuchName), "\"NoSuchName\"");
+		#[cfg(feature = "validation")] {
+			assert_eq!(round_trip(ValidationError::Invalid), "\"Invalid\"");
+			let stream = VerifiedRRStream {
+				verified_rrs: rrs.iter().collect(), valid_from: 1, expires: 2, max_cache_ttl: 3,
+			};
+			let owned: OwnedVerifiedRRStream = serde_json::from_str(&serde_json::to_string(&stream).unwrap()).unwrap();
+			assert_eq!(owned.verified_rrs, rrs);
+			assert_eq!(round_trip(owned.clone()), serde_json::to_string(&stream).unwrap());
+			assert_eq!(owned.as_verified_rr_stream().verified_rrs, stream.verified_rrs);
+		}
+	}
+}
@@ -64,7 +64,7 @@ fn test_ecdsa<
 	Validate: Fn(&[u8], &[u8], &[u8]) -> Result<(), ()> + Clone,
 	Hash: Fn(&[u8]) -> HashResult,
 >(v: Value, int_len: usize, validate_fn: Validate, hash_fn: Hash) {
-	for (group_idx, group) in v["testGroups"].as_array().unwrap().into_iter().enumerate() {
+	for (group_idx, group) in v["testGroups"].as_array().unwrap().iter().enumerate() {
 		let pk_str = group["publicKey"]["uncompressed"].as_str().unwrap();
 		assert_eq!(&pk_str[..2], "04"); // OpenSSL uncompressed encoding flag
 		let pk = Vec::from_hex(&pk_str[2..]).unwrap();
@@ -131,7 +131,7 @@ fn test_ecdsa_384r1_sha384() {
 }
 
 fn test_rsa<Hash: Fn(&[u8]) -> HashResult>(v: Value, pk_len: usize, hash_fn: Hash) {
-	for (group_idx, group) in v["testGroups"].as_array().unwrap().into_iter().enumerate() {
+	for (group_idx, group) in v["testGroups"].as_array().unwrap().iter().enumerate() {
 		let pk_str = group["publicKey"]["modulus"].as_str().unwrap();
 		assert_eq!(&pk_str[..2], "00"); // No idea why this is here
 		let pk = Vec::from_hex(&pk_str[2..]).unwrap();
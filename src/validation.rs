@@ -1,6 +1,5 @@
 //! Utilities to deserialize and validate RFC 9102 proofs
 
-use alloc::borrow::ToOwned;
 use alloc::vec::Vec;
 use alloc::vec;
 use core::cmp::{self, Ordering};
@@ -8,7 +7,7 @@ use core::cmp::{self, Ordering};
 use crate::base32;
 use crate::crypto;
 use crate::rr::*;
-use crate::ser::write_name;
+use crate::ser::*;
 use crate::unhex::unhex;
 use crate::MAX_PROOF_STEPS;
 
@@ -41,6 +40,7 @@ pub fn root_hints() -> Vec<DS> {
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An error when validating DNSSEC signatures or other data
 pub enum ValidationError {
 	/// An algorithm used in signing was not supported.
@@ -57,13 +57,222 @@ pub enum ValidationError {
 	ValidationCountLimited,
 }
 
-fn verify_rrsig<'a, RR: WriteableRecord, Keys>(sig: &RRSig, dnskeys: Keys, mut records: Vec<&RR>)
+/// A borrowed view of an [`RRSig`], either from an owned [`RRSig`] or an [`RRRef`].
+#[derive(Clone, Copy)]
+struct RRSigRef<'a> {
+	name: NameRef<'a>,
+	ty: u16,
+	alg: u8,
+	labels: u8,
+	orig_ttl: u32,
+	expiration: u32,
+	inception: u32,
+	key_tag: u16,
+	key_name: NameRef<'a>,
+	signature: &'a [u8],
+}
+impl<'a> RRSigRef<'a> {
+	fn from_rr(rr: &RRRef<'a>) -> Option<Self> {
+		if rr.ty() != RRSig::TYPE { return None; }
+		// The RDATA was checked when the RRRef was parsed, so none of the reads here can fail.
+		let mut data = rr.rdata();
+		Some(RRSigRef {
+			name: rr.name(), ty: read_u16(&mut data).ok()?, alg: read_u8(&mut data).ok()?,
+			labels: read_u8(&mut data).ok()?, orig_ttl: read_u32(&mut data).ok()?,
+			expiration: read_u32(&mut data).ok()?, inception: read_u32(&mut data).ok()?,
+			key_tag: read_u16(&mut data).ok()?, key_name: NameRef::read(&mut data).ok()?,
+			signature: data,
+		})
+	}
+}
+impl<'a> From<&'a RRSig> for RRSigRef<'a> {
+	fn from(sig: &'a RRSig) -> Self {
+		RRSigRef {
+			name: (&sig.name).into(), ty: sig.ty, alg: sig.alg, labels: sig.labels,
+			orig_ttl: sig.orig_ttl, expiration: sig.expiration, inception: sig.inception,
+			key_tag: sig.key_tag, key_name: (&sig.key_name).into(), signature: &sig.signature,
+		}
+	}
+}
+
+/// A borrowed view of a [`DnsKey`], either from an owned [`DnsKey`] or an [`RRRef`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct DnsKeyRef<'a> {
+	name: NameRef<'a>,
+	flags: u16,
+	protocol: u8,
+	alg: u8,
+	pubkey: &'a [u8],
+}
+impl<'a> DnsKeyRef<'a> {
+	fn from_rr(rr: &RRRef<'a>) -> Option<Self> {
+		if rr.ty() != DnsKey::TYPE { return None; }
+		let mut data = rr.rdata();
+		Some(DnsKeyRef {
+			name: rr.name(), flags: read_u16(&mut data).ok()?, protocol: read_u8(&mut data).ok()?,
+			alg: read_u8(&mut data).ok()?, pubkey: data,
+		})
+	}
+	fn key_tag(&self) -> u16 {
+		dnskey_key_tag(self.flags, self.protocol, self.alg, self.pubkey)
+	}
+}
+impl<'a> From<&'a DnsKey> for DnsKeyRef<'a> {
+	fn from(key: &'a DnsKey) -> Self {
+		DnsKeyRef {
+			name: (&key.name).into(), flags: key.flags, protocol: key.protocol, alg: key.alg,
+			pubkey: &key.pubkey,
+		}
+	}
+}
+
+/// A borrowed view of a [`DS`], either from an owned [`DS`] or an [`RRRef`].
+#[derive(Clone, Copy)]
+struct DSRef<'a> {
+	key_tag: u16,
+	alg: u8,
+	digest_type: u8,
+	digest: &'a [u8],
+}
+impl<'a> DSRef<'a> {
+	fn from_rr(rr: &RRRef<'a>) -> Option<Self> {
+		if rr.ty() != DS::TYPE { return None; }
+		let mut data = rr.rdata();
+		Some(DSRef {
+			key_tag: read_u16(&mut data).ok()?, alg: read_u8(&mut data).ok()?,
+			digest_type: read_u8(&mut data).ok()?, digest: data,
+		})
+	}
+}
+impl<'a> From<&'a DS> for DSRef<'a> {
+	fn from(ds: &'a DS) -> Self {
+		DSRef { key_tag: ds.key_tag, alg: ds.alg, digest_type: ds.digest_type, digest: &ds.digest }
+	}
+}
+
+/// A borrowed view of an [`NSec`], either from an owned [`NSec`] or an [`RRRef`].
+struct NSecRef<'a> {
+	name: NameRef<'a>,
+	next_name: NameRef<'a>,
+	/// The type bitmap, in its (canonical) wire encoding.
+	types: &'a [u8],
+}
+impl<'a> NSecRef<'a> {
+	fn from_rr(rr: &RRRef<'a>) -> Option<Self> {
+		if rr.ty() != NSec::TYPE { return None; }
+		let mut data = rr.rdata();
+		Some(NSecRef { name: rr.name(), next_name: NameRef::read(&mut data).ok()?, types: data })
+	}
+}
+
+/// A borrowed view of an [`NSec3`], either from an owned [`NSec3`] or an [`RRRef`].
+#[derive(Clone, Copy)]
+struct NSec3Ref<'a> {
+	name: NameRef<'a>,
+	hash_algo: u8,
+	flags: u8,
+	hash_iterations: u16,
+	salt: &'a [u8],
+	next_name_hash: &'a [u8],
+	/// The type bitmap, in its (canonical) wire encoding.
+	types: &'a [u8],
+}
+impl<'a> NSec3Ref<'a> {
+	fn from_rr(rr: &RRRef<'a>) -> Option<Self> {
+		if rr.ty() != NSec3::TYPE { return None; }
+		let mut data = rr.rdata();
+		Some(NSec3Ref {
+			name: rr.name(), hash_algo: read_u8(&mut data).ok()?, flags: read_u8(&mut data).ok()?,
+			hash_iterations: read_u16(&mut data).ok()?,
+			salt: read_u8_len_prefixed_slice(&mut data).ok()?,
+			next_name_hash: read_u8_len_prefixed_slice(&mut data).ok()?, types: data,
+		})
+	}
+}
+
+/// A record which can be signed by an [`RRSig`].
+trait SignedRecord: Ord {
+	fn signed_name(&self) -> NameRef<'_>;
+	fn signed_ty(&self) -> u16;
+	/// Writes the canonical encoding of the record's RDATA, prefixed by a u16 length.
+	fn write_signed_data<W: Writer>(&self, out: &mut W);
+}
+impl<R: WriteableRecord> SignedRecord for R {
+	fn signed_name(&self) -> NameRef<'_> { self.name().into() }
+	fn signed_ty(&self) -> u16 { self.ty() }
+	fn write_signed_data<W: Writer>(&self, out: &mut W) { self.serialize_u16_len_prefixed(out) }
+}
+impl SignedRecord for RRRef<'_> {
+	fn signed_name(&self) -> NameRef<'_> { self.name() }
+	fn signed_ty(&self) -> u16 { self.ty() }
+	fn write_signed_data<W: Writer>(&self, out: &mut W) {
+		self.write_canonical_u16_len_prefixed_data(out)
+	}
+}
+impl SignedRecord for DnsKeyRef<'_> {
+	fn signed_name(&self) -> NameRef<'_> { self.name }
+	fn signed_ty(&self) -> u16 { DnsKey::TYPE }
+	fn write_signed_data<W: Writer>(&self, out: &mut W) {
+		out.write(&(4 + self.pubkey.len() as u16).to_be_bytes());
+		out.write(&self.flags.to_be_bytes());
+		out.write(&[self.protocol, self.alg]);
+		out.write(self.pubkey);
+	}
+}
+
+/// A record (either an owned [`RR`] or an [`RRRef`]) which can be checked by [`verify_records`].
+trait ValidatableRecord: SignedRecord + PartialEq {
+	fn as_rrsig(&self) -> Option<RRSigRef<'_>>;
+	fn as_dnskey(&self) -> Option<DnsKeyRef<'_>>;
+	fn as_ds(&self) -> Option<DSRef<'_>>;
+	fn as_nsec(&self) -> Option<NSecRef<'_>>;
+	fn as_nsec3(&self) -> Option<NSec3Ref<'_>>;
+}
+impl ValidatableRecord for RR {
+	fn as_rrsig(&self) -> Option<RRSigRef<'_>> {
+		if let RR::RRSig(sig) = self { Some(sig.into()) } else { None }
+	}
+	fn as_dnskey(&self) -> Option<DnsKeyRef<'_>> {
+		if let RR::DnsKey(key) = self { Some(key.into()) } else { None }
+	}
+	fn as_ds(&self) -> Option<DSRef<'_>> {
+		if let RR::DS(ds) = self { Some(ds.into()) } else { None }
+	}
+	fn as_nsec(&self) -> Option<NSecRef<'_>> {
+		if let RR::NSec(nsec) = self {
+			Some(NSecRef {
+				name: (&nsec.name).into(), next_name: (&nsec.next_name).into(),
+				types: nsec.types.wire_bytes(),
+			})
+		} else { None }
+	}
+	fn as_nsec3(&self) -> Option<NSec3Ref<'_>> {
+		if let RR::NSec3(nsec3) = self {
+			Some(NSec3Ref {
+				name: (&nsec3.name).into(), hash_algo: nsec3.hash_algo, flags: nsec3.flags,
+				hash_iterations: nsec3.hash_iterations, salt: &nsec3.salt,
+				next_name_hash: &nsec3.next_name_hash, types: nsec3.types.wire_bytes(),
+			})
+		} else { None }
+	}
+}
+impl ValidatableRecord for RRRef<'_> {
+	fn as_rrsig(&self) -> Option<RRSigRef<'_>> { RRSigRef::from_rr(self) }
+	fn as_dnskey(&self) -> Option<DnsKeyRef<'_>> { DnsKeyRef::from_rr(self) }
+	fn as_ds(&self) -> Option<DSRef<'_>> { DSRef::from_rr(self) }
+	fn as_nsec(&self) -> Option<NSecRef<'_>> { NSecRef::from_rr(self) }
+	fn as_nsec3(&self) -> Option<NSec3Ref<'_>> { NSec3Ref::from_rr(self) }
+}
+
+fn verify_rrsig<'a, 'k, S, R, Keys, K>(sig: S, dnskeys: Keys, mut records: Vec<&R>)
 -> Result<(), ValidationError>
-where Keys: IntoIterator<Item = &'a DnsKey> {
+where S: Into<RRSigRef<'a>>, R: SignedRecord, Keys: IntoIterator<Item = K>, K: Into<DnsKeyRef<'k>> {
+	let sig = sig.into();
 	for record in records.iter() {
-		if sig.ty != record.ty() { return Err(ValidationError::Invalid); }
+		if sig.ty != record.signed_ty() { return Err(ValidationError::Invalid); }
 	}
 	for dnskey in dnskeys.into_iter() {
+		let dnskey = dnskey.into();
 		if dnskey.key_tag() == sig.key_tag {
 			// Protocol must be 3, otherwise its not DNSSEC
 			if dnskey.protocol != 3 { continue; }
@@ -87,7 +296,7 @@ where Keys: IntoIterator<Item = &'a DnsKey> {
 			hash_ctx.update(&sig.expiration.to_be_bytes());
 			hash_ctx.update(&sig.inception.to_be_bytes());
 			hash_ctx.update(&sig.key_tag.to_be_bytes());
-			write_name(&mut hash_ctx, &sig.key_name);
+			sig.key_name.write_canonical(&mut hash_ctx);
 
 			records.sort_unstable();
 
@@ -97,36 +306,36 @@ where Keys: IntoIterator<Item = &'a DnsKey> {
 			records.dedup();
 
 			for record in records.iter() {
-				let record_labels = record.name().labels() as usize;
+				let record_labels = record.signed_name().labels() as usize;
 				let labels = sig.labels.into();
 				// For NSec types, the name should already match the wildcard, so we don't do any
 				// filtering here. This is relied upon in `verify_rr_stream` to check whether an
 				// NSec record is matching via wildcard (as otherwise we'd allow a resolver to
 				// change the name out from under us and change the wildcard to something else).
-				if record.ty() != NSec::TYPE && record_labels != labels {
+				if record.signed_ty() != NSec::TYPE && record_labels != labels {
 					if record_labels < labels { return Err(ValidationError::Invalid); }
-					let signed_name = record.name().trailing_n_labels(sig.labels);
+					let signed_name = record.signed_name().trailing_n_labels(sig.labels);
 					debug_assert!(signed_name.is_some());
 					if let Some(name) = signed_name {
 						hash_ctx.update(b"\x01*");
-						write_name(&mut hash_ctx, name);
+						name.write_canonical(&mut hash_ctx);
 					} else { return Err(ValidationError::Invalid); }
 				} else {
-					write_name(&mut hash_ctx, record.name());
+					record.signed_name().write_canonical(&mut hash_ctx);
 				}
-				hash_ctx.update(&record.ty().to_be_bytes());
+				hash_ctx.update(&record.signed_ty().to_be_bytes());
 				hash_ctx.update(&1u16.to_be_bytes()); // The INternet class
 				hash_ctx.update(&sig.orig_ttl.to_be_bytes());
-				record.serialize_u16_len_prefixed(&mut hash_ctx);
+				record.write_signed_data(&mut hash_ctx);
 			}
 
 			let hash = hash_ctx.finish();
 			let sig_validation = match sig.alg {
-				8|10 => crypto::rsa::validate_rsa(&dnskey.pubkey, &sig.signature, hash.as_ref())
+				8|10 => crypto::rsa::validate_rsa(dnskey.pubkey, sig.signature, hash.as_ref())
 					.map_err(|_| ValidationError::Invalid),
-				13 => crypto::secp256r1::validate_ecdsa(&dnskey.pubkey, &sig.signature, hash.as_ref())
+				13 => crypto::secp256r1::validate_ecdsa(dnskey.pubkey, sig.signature, hash.as_ref())
 					.map_err(|_| ValidationError::Invalid),
-				14 => crypto::secp384r1::validate_ecdsa(&dnskey.pubkey, &sig.signature, hash.as_ref())
+				14 => crypto::secp384r1::validate_ecdsa(dnskey.pubkey, sig.signature, hash.as_ref())
 					.map_err(|_| ValidationError::Invalid),
 				// TODO: 15 => ED25519
 				_ => return Err(ValidationError::UnsupportedAlgorithm),
@@ -152,13 +361,32 @@ where Keys: IntoIterator<Item = &'a DnsKey> {
 	Err(ValidationError::Invalid)
 }
 
-/// Verify [`RRSig`]s over [`DnsKey`], returning a reference to the [`RRSig`] that matched, if any.
-fn verify_dnskeys<'r, 'd, RI, R, DI, D>(sigs: RI, dses: DI, records: Vec<&DnsKey>)
--> Result<&'r RRSig, ValidationError>
-where RI: IntoIterator<IntoIter = R>, R: Iterator<Item = &'r RRSig>,
-      DI: IntoIterator<IntoIter = D>, D: Iterator<Item = &'d DS> + Clone {
+/// Calculates the digest of the given [`DnsKey`] as it would appear in a [`DS`] (or [`CDS`]) record
+/// with the given `digest_type`, returning `None` if the digest type is not supported.
+fn dnskey_digest(dnskey: DnsKeyRef, digest_type: u8) -> Option<crypto::hash::HashResult> {
+	let mut ctx = match digest_type {
+		1 => crypto::hash::Hasher::sha1(),
+		2 => crypto::hash::Hasher::sha256(),
+		4 => crypto::hash::Hasher::sha384(),
+		_ => return None,
+	};
+	dnskey.name.write_canonical(&mut ctx);
+	ctx.update(&dnskey.flags.to_be_bytes());
+	ctx.update(&dnskey.protocol.to_be_bytes());
+	ctx.update(&dnskey.alg.to_be_bytes());
+	ctx.update(dnskey.pubkey);
+	Some(ctx.finish())
+}
+
+/// Verify [`RRSig`]s over [`DnsKey`], returning the [`RRSig`] that matched, if any.
+fn verify_dnskeys<'r, 'd, 'k, RI, S, DI, D, K>(sigs: RI, dses: DI, records: Vec<K>)
+-> Result<RRSigRef<'r>, ValidationError>
+where RI: IntoIterator<Item = S>, S: Into<RRSigRef<'r>>,
+      DI: IntoIterator<IntoIter = D>, D: Iterator + Clone, D::Item: Into<DSRef<'d>>,
+      K: Into<DnsKeyRef<'k>> {
+	let records = records.into_iter().map(Into::into).collect::<Vec<DnsKeyRef>>();
 	let mut validated_dnskeys = Vec::with_capacity(records.len());
-	let dses = dses.into_iter();
+	let dses = dses.into_iter().map(Into::<DSRef>::into);
 
 	let mut had_known_digest_type = false;
 	let mut had_ds = false;
@@ -178,18 +406,8 @@ where RI: IntoIterator<IntoIter = R>, R: Iterator<Item = &'r RRSig>,
 		for ds in dses.clone() {
 			if ds.alg != dnskey.alg { continue; }
 			if dnskey.key_tag() == ds.key_tag {
-				let mut ctx = match ds.digest_type {
-					1 if trust_sha1 => crypto::hash::Hasher::sha1(),
-					2 => crypto::hash::Hasher::sha256(),
-					4 => crypto::hash::Hasher::sha384(),
-					_ => continue,
-				};
-				write_name(&mut ctx, &dnskey.name);
-				ctx.update(&dnskey.flags.to_be_bytes());
-				ctx.update(&dnskey.protocol.to_be_bytes());
-				ctx.update(&dnskey.alg.to_be_bytes());
-				ctx.update(&dnskey.pubkey);
-				let hash = ctx.finish();
+				if ds.digest_type == 1 && !trust_sha1 { continue; }
+				let hash = if let Some(hash) = dnskey_digest(*dnskey, ds.digest_type) { hash } else { continue };
 				if hash.as_ref() == ds.digest {
 					validated_dnskeys.push(*dnskey);
 					break;
@@ -200,13 +418,14 @@ where RI: IntoIterator<IntoIter = R>, R: Iterator<Item = &'r RRSig>,
 
 	let mut found_unsupported_alg = false;
 	for sig in sigs {
+		let sig = sig.into();
 		if !validated_dnskeys.iter().any(|key| key.key_tag() == sig.key_tag) {
 			// Some DNS servers include spurious RRSig records signed by the ZSK covering the
 			// DNSKEY set (looking at you OVH). This is harmless (but wasteful) and we should
 			// ignore such signatures rather than immediately failing.
 			continue;
 		}
-		match verify_rrsig(sig, validated_dnskeys.iter().copied(), records.clone()) {
+		match verify_rrsig(sig, validated_dnskeys.iter().copied(), records.iter().collect()) {
 			Ok(()) => return Ok(sig),
 			Err(ValidationError::UnsupportedAlgorithm) => {
 				// There may be redundant signatures by different keys, where one we don't
@@ -232,12 +451,91 @@ where RI: IntoIterator<IntoIter = R>, R: Iterator<Item = &'r RRSig>,
 	}
 }
 
+/// The change to its parent's [`DS`] set which a child zone requested via its [`CDS`] and/or
+/// [`CDnsKey`] records, as returned by [`verify_cds_cdnskey`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DSUpdateRequest {
+	/// The parent should replace its [`DS`] set with one matching the [`CDS`]/[`CDnsKey`] records.
+	Update,
+	/// The parent should remove all of its [`DS`] records, making the child zone insecure.
+	Delete,
+}
+
+/// Checks that the [`CDS`] and [`CDnsKey`] records a child zone publishes (see RFC 7344) are
+/// consistent with its current [`DnsKey`] set, i.e. that each either refers to a key in the set
+/// or that the records, together, form an RFC 8078 request to delete the parent's [`DS`] set.
+///
+/// This does not check any signatures - the records (including the [`DnsKey`] set) should be
+/// taken from a proof which was accepted by [`verify_rr_stream`].
+///
+/// If a [`CDS`] uses a digest type we do not support, but is otherwise consistent,
+/// [`ValidationError::UnsupportedAlgorithm`] is returned.
+pub fn verify_cds_cdnskey<'a, KI, K, CI, NI>(dnskeys: KI, cdses: CI, cdnskeys: NI)
+-> Result<DSUpdateRequest, ValidationError>
+where KI: IntoIterator<IntoIter = K>, K: Iterator<Item = &'a DnsKey> + Clone,
+      CI: IntoIterator<Item = &'a CDS>, NI: IntoIterator<Item = &'a CDnsKey> {
+	let dnskeys = dnskeys.into_iter();
+	let cdses = cdses.into_iter().collect::<Vec<_>>();
+	let cdnskeys = cdnskeys.into_iter().collect::<Vec<_>>();
+
+	let zone = if let Some(cds) = cdses.first() { &cds.name }
+		else if let Some(cdnskey) = cdnskeys.first() { &cdnskey.name }
+		else { return Err(ValidationError::Invalid); };
+	if cdses.iter().any(|cds| cds.name != *zone) || cdnskeys.iter().any(|key| key.name != *zone) {
+		return Err(ValidationError::Invalid);
+	}
+
+	let delete_count = cdses.iter().filter(|cds| cds.is_delete()).count() +
+		cdnskeys.iter().filter(|key| key.is_delete()).count();
+	if delete_count != 0 {
+		// RFC 8078 section 4: a delete request must be the only record in its set, and if both
+		// CDS and CDNSKEY sets are published they must agree.
+		if delete_count != cdses.len() + cdnskeys.len() || cdses.len() > 1 || cdnskeys.len() > 1 {
+			return Err(ValidationError::Invalid);
+		}
+		return Ok(DSUpdateRequest::Delete);
+	}
+
+	if dnskeys.clone().any(|key| key.name != *zone) { return Err(ValidationError::Invalid); }
+
+	for cdnskey in cdnskeys {
+		if !dnskeys.clone().any(|key| cdnskey.matches(key)) { return Err(ValidationError::Invalid); }
+	}
+
+	let mut found_unsupported_digest = false;
+	for cds in cdses {
+		let mut found_match = false;
+		for dnskey in dnskeys.clone() {
+			if dnskey.alg != cds.alg || dnskey.key_tag() != cds.key_tag { continue; }
+			if let Some(hash) = dnskey_digest(dnskey.into(), cds.digest_type) {
+				if hash.as_ref() == cds.digest {
+					found_match = true;
+					break;
+				}
+			} else {
+				found_unsupported_digest = true;
+				found_match = true;
+				break;
+			}
+		}
+		if !found_match { return Err(ValidationError::Invalid); }
+	}
+
+	if found_unsupported_digest {
+		Err(ValidationError::UnsupportedAlgorithm)
+	} else {
+		Ok(DSUpdateRequest::Update)
+	}
+}
+
 /// Given a set of [`RR`]s, [`verify_rr_stream`] checks what it can and returns the set of
 /// non-[`RRSig`]/[`DnsKey`]/[`DS`] records which it was able to verify using this struct.
 ///
 /// It also contains signing and expiry times, which must be validated before considering the
 /// contained records verified.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VerifiedRRStream<'a> {
 	/// The set of verified [`RR`]s, not including [`DnsKey`], [`RRSig`], [`NSec`], and [`NSec3`]
 	/// records.
@@ -268,6 +566,24 @@ pub struct VerifiedRRStream<'a> {
 	pub max_cache_ttl: u32,
 }
 
+/// The records which [`verify_rr_stream_ref`] was able to verify, which are the same as those in
+/// [`VerifiedRRStream`] but refer to the original proof as [`RRRef`]s.
+///
+/// It also contains signing and expiry times, which must be validated before considering the
+/// contained records verified.
+#[derive(Debug, Clone)]
+pub struct VerifiedRRRefStream<'a> {
+	/// The set of verified [`RRRef`]s, not including [`DnsKey`], [`RRSig`], [`NSec`], and
+	/// [`NSec3`] records. See [`VerifiedRRStream::verified_rrs`].
+	pub verified_rrs: Vec<RRRef<'a>>,
+	/// See [`VerifiedRRStream::valid_from`].
+	pub valid_from: u64,
+	/// See [`VerifiedRRStream::expires`].
+	pub expires: u64,
+	/// See [`VerifiedRRStream::max_cache_ttl`].
+	pub max_cache_ttl: u32,
+}
+
 fn resolve_time(time: u32) -> u64 {
 	// RFC 2065 was published in January 1997, so we arbitrarily use that as a cutoff and assume
 	// any timestamps before then are actually past 2106 instead.
@@ -279,51 +595,8 @@ fn resolve_time(time: u32) -> u64 {
 	}
 }
 
-fn nsec_ord(a: &str, b: &str) -> Ordering {
-	let mut a_label_iter = a.rsplit('.');
-	let mut b_label_iter = b.rsplit('.');
-	loop {
-		match (a_label_iter.next(), b_label_iter.next()) {
-			(Some(_), None) => return Ordering::Greater,
-			(None, Some(_)) => return Ordering::Less,
-			(Some(a_label), Some(b_label)) => {
-				let mut a_bytes = a_label.bytes();
-				let mut b_bytes = b_label.bytes();
-				loop {
-					match (a_bytes.next(), b_bytes.next()) {
-						(Some(_), None) => return Ordering::Greater,
-						(None, Some(_)) => return Ordering::Less,
-						(Some(mut a), Some(mut b)) => {
-							if a.is_ascii_uppercase() {
-								a += b'a' - b'A';
-							}
-							if b.is_ascii_uppercase() {
-								b += b'a' - b'A';
-							}
-							if a != b { return a.cmp(&b); }
-						},
-						(None, None) => break,
-					}
-				}
-			},
-			(None, None) => return Ordering::Equal,
-		}
-	}
-}
-fn nsec_ord_extra<T, U>(a: &(&str, T, U), b: &(&str, T, U)) -> Ordering {
-	nsec_ord(a.0, b.0)
-}
-
-#[cfg(test)]
-#[test]
-fn rfc4034_sort_test() {
-	// Test nsec_ord based on RFC 4034 section 6.1's example
-	// Note that we replace the \200 example  with \7f as I have no idea what \200 is
-	let v = vec!["example.", "a.example.", "yljkjljk.a.example.", "Z.a.example.",
-		"zABC.a.EXAMPLE.", "z.example.", "\001.z.example.", "*.z.example.", "\x7f.z.example."];
-	let mut sorted = v.clone();
-	sorted.sort_unstable_by(|a, b| nsec_ord(*a, *b));
-	assert_eq!(sorted, v);
+fn nsec_ord_extra<T, U>(a: &(NameRef, T, U), b: &(NameRef, T, U)) -> Ordering {
+	canonical_name_cmp(a.0.wire(), b.0.wire())
 }
 
 /// Verifies the given set of resource records.
@@ -337,21 +610,121 @@ fn rfc4034_sort_test() {
 /// You MUST check that the current UNIX time is between [`VerifiedRRStream::valid_from`] and
 /// [`VerifiedRRStream::expires`].
 pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, ValidationError> {
-	let mut zone = ".";
-	let mut res = Vec::new();
+	let verified = verify_records(inp)?;
+	Ok(VerifiedRRStream {
+		verified_rrs: verified.verified_idxs.iter().map(|idx| &inp[*idx]).collect(),
+		valid_from: verified.valid_from, expires: verified.expires,
+		max_cache_ttl: verified.max_cache_ttl,
+	})
+}
+
+/// Verifies the given set of borrowed resource records, as parsed by [`parse_rr_stream_ref`].
+///
+/// This is equivalent to [`verify_rr_stream`], but works on (and returns) [`RRRef`]s pointing
+/// into the original proof, avoiding copying each record onto the heap.
+///
+/// You MUST check that the current UNIX time is between [`VerifiedRRRefStream::valid_from`] and
+/// [`VerifiedRRRefStream::expires`].
+pub fn verify_rr_stream_ref<'a>(inp: &[RRRef<'a>]) -> Result<VerifiedRRRefStream<'a>, ValidationError> {
+	let verified = verify_records(inp)?;
+	Ok(VerifiedRRRefStream {
+		verified_rrs: verified.verified_idxs.iter().map(|idx| inp[*idx]).collect(),
+		valid_from: verified.valid_from, expires: verified.expires,
+		max_cache_ttl: verified.max_cache_ttl,
+	})
+}
+
+/// Parses and verifies an RFC 9102 `DnssecChainExtension`, i.e. the body of a TLS `dnssec_chain`
+/// extension, returning its [`DnssecChainExtension::ext_support_lifetime`] and the records which
+/// could be verified, as in [`verify_rr_stream_ref`].
+///
+/// Note that this does not check that the verified records are relevant to the TLS connection,
+/// e.g. that they include a [`TLSA`] record for the server's name which matches its certificate.
+///
+/// You MUST check that the current UNIX time is between [`VerifiedRRRefStream::valid_from`] and
+/// [`VerifiedRRRefStream::expires`].
+pub fn verify_dnssec_chain_extension<'a>(mut extension: &'a [u8])
+-> Result<(u16, VerifiedRRRefStream<'a>), ValidationError> {
+	let ext_support_lifetime = read_u16(&mut extension).map_err(|()| ValidationError::Invalid)?;
+	let chain_len = read_u16(&mut extension).map_err(|()| ValidationError::Invalid)?;
+	if chain_len == 0 || extension.len() != chain_len as usize {
+		return Err(ValidationError::Invalid);
+	}
+	let rrs = parse_rr_stream_ref(extension).map_err(|()| ValidationError::Invalid)?;
+	Ok((ext_support_lifetime, verify_rr_stream_ref(&rrs)?))
+}
+
+/// Minimizes and canonicalizes the given proof, i.e. a stream of records as built by
+/// [`crate::query`], returning a proof containing only the records needed for it to verify (with
+/// [`verify_rr_stream`]) to the same records.
+///
+/// Duplicate records, [`RRSig`]s over [`DnsKey`] sets other than the one used to validate them,
+/// and any records which aren't part of the chain from the root to a verified record are dropped.
+/// The remaining records are sorted (by name, in canonical order, then type, then data) and
+/// written in canonical form with the original TTL from the [`RRSig`] covering them, so the
+/// output depends only on the set of records in the input, making it suitable for caching and
+/// hashing.
+///
+/// Fails if the proof could not be verified.
+pub fn minimize_proof(proof: &[u8]) -> Result<Vec<u8>, ValidationError> {
+	let mut rrs = parse_rr_stream_ref(proof).map_err(|()| ValidationError::Invalid)?;
+	// Sort first so that we always pick the same signature when there are several over one
+	// DNSKEY set.
+	rrs.sort_unstable();
+	rrs.dedup();
+	let verified = verify_records(&rrs)?;
+
+	let used_rrs = verified.used_idxs.iter().map(|idx| rrs[*idx]).collect::<Vec<_>>();
+	let mut records = used_rrs.iter().map(|rr| {
+		let ttl = match RRSigRef::from_rr(rr) {
+			Some(rrsig) => rrsig.orig_ttl,
+			None => used_rrs.iter().filter_map(RRSigRef::from_rr)
+				.filter(|rrsig| rrsig.name == rr.name() && rrsig.ty == rr.ty())
+				.map(|rrsig| rrsig.orig_ttl)
+				.min().unwrap_or(0),
+		};
+		let mut record = Vec::new();
+		write_rr(&rr.to_rr(), ttl, &mut record);
+		(rr.name(), rr.ty(), record)
+	}).collect::<Vec<_>>();
+	records.sort_unstable_by(|a, b| {
+		canonical_name_cmp(a.0.wire(), b.0.wire()).then(a.1.cmp(&b.1)).then_with(|| a.2.cmp(&b.2))
+	});
+	Ok(records.into_iter().flat_map(|(_, _, record)| record).collect())
+}
+
+/// The result of [`verify_records`], with verified records referred to by their index.
+struct VerifiedRecords {
+	verified_idxs: Vec<usize>,
+	/// The indexes of all the records which were needed to verify the records in `verified_idxs`
+	/// (including those records themselves).
+	used_idxs: Vec<usize>,
+	valid_from: u64,
+	expires: u64,
+	max_cache_ttl: u32,
+}
+
+fn verify_records<R: ValidatableRecord>(inp: &[R]) -> Result<VerifiedRecords, ValidationError> {
+	let root = NameRef::ROOT;
+	let mut zone = root;
+	let mut res: Vec<usize> = Vec::new();
+	let mut used: Vec<usize> = Vec::new();
 	let mut rrs_needing_non_existence_proofs = Vec::new();
+	// Verified NSEC/NSEC3 sets, which are only used if they're needed for a non-existence proof.
+	let mut nsec_sets = Vec::new();
 	let mut pending_ds_sets = Vec::with_capacity(1);
 	let mut latest_inception = 0;
 	let mut earliest_expiry = u64::MAX;
 	let mut min_ttl = u32::MAX;
 	let mut rrsig_sets_validated = 0;
-	'next_zone: while zone == "." || !pending_ds_sets.is_empty() {
+	let rrsigs = inp.iter().filter_map(R::as_rrsig);
+	'next_zone: while zone == root || !pending_ds_sets.is_empty() {
 		let next_ds_set;
 		if let Some((next_zone, ds_set)) = pending_ds_sets.pop() {
 			next_ds_set = Some(ds_set);
 			zone = next_zone;
 		} else {
-			debug_assert_eq!(zone, ".");
+			debug_assert!(zone == root);
 			next_ds_set = None;
 		}
 
@@ -360,14 +733,13 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 			return Err(ValidationError::ValidationCountLimited);
 		}
 
-		let dnskey_rrsigs = inp.iter()
-			.filter_map(|rr| if let RR::RRSig(sig) = rr { Some(sig) } else { None })
-			.filter(|rrsig| rrsig.name.as_str() == zone && rrsig.ty == DnsKey::TYPE);
+		let dnskey_rrsigs = rrsigs.clone()
+			.filter(|rrsig| rrsig.name == zone && rrsig.ty == DnsKey::TYPE);
 		let dnskeys = inp.iter()
-			.filter_map(|rr| if let RR::DnsKey(dnskey) = rr { Some(dnskey) } else { None })
-			.filter(move |dnskey| dnskey.name.as_str() == zone);
+			.filter_map(R::as_dnskey)
+			.filter(move |dnskey| dnskey.name == zone);
 		let root_hints = root_hints();
-		let verified_dnskey_rrsig = if zone == "." {
+		let verified_dnskey_rrsig = if zone == root {
 			verify_dnskeys(dnskey_rrsigs, &root_hints, dnskeys.clone().collect())?
 		} else {
 			debug_assert!(next_ds_set.is_some());
@@ -377,20 +749,25 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 		latest_inception = cmp::max(latest_inception, resolve_time(verified_dnskey_rrsig.inception));
 		earliest_expiry = cmp::min(earliest_expiry, resolve_time(verified_dnskey_rrsig.expiration));
 		min_ttl = cmp::min(min_ttl, verified_dnskey_rrsig.orig_ttl);
-
-		for rrsig in inp.iter()
-			.filter_map(|rr| if let RR::RRSig(sig) = rr { Some(sig) } else { None })
-			.filter(move |rrsig| rrsig.key_name.as_str() == zone && rrsig.ty != DnsKey::TYPE)
+		used.extend(inp.iter().enumerate().filter(|(_, rr)| match rr.as_rrsig() {
+			Some(rrsig) => rrsig.name == zone && rrsig.ty == DnsKey::TYPE
+				&& rrsig.key_tag == verified_dnskey_rrsig.key_tag
+				&& rrsig.signature == verified_dnskey_rrsig.signature,
+			None => rr.signed_ty() == DnsKey::TYPE && rr.signed_name() == zone,
+		}).map(|(idx, _)| idx));
+
+		for rrsig in rrsigs.clone()
+			.filter(move |rrsig| rrsig.key_name == zone && rrsig.ty != DnsKey::TYPE)
 		{
 			rrsig_sets_validated += 1;
 			if rrsig_sets_validated > MAX_PROOF_STEPS {
 				return Err(ValidationError::ValidationCountLimited);
 			}
 
-			if !rrsig.name.ends_with(zone) { return Err(ValidationError::Invalid); }
-			let signed_records = inp.iter()
-				.filter(|rr| rr.name() == &rrsig.name && rr.ty() == rrsig.ty);
-			match verify_rrsig(rrsig, dnskeys.clone(), signed_records.clone().collect()) {
+			if !rrsig.name.is_subdomain_of(zone) { return Err(ValidationError::Invalid); }
+			let signed_records = inp.iter().enumerate()
+				.filter(move |(_, rr)| rr.signed_name() == rrsig.name && rr.signed_ty() == rrsig.ty);
+			match verify_rrsig(rrsig, dnskeys.clone(), signed_records.clone().map(|(_, rr)| rr).collect()) {
 				Ok(()) => {},
 				Err(ValidationError::UnsupportedAlgorithm) => continue,
 				Err(ValidationError::ValidationCountLimited) => {
@@ -405,16 +782,27 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 			latest_inception = cmp::max(latest_inception, resolve_time(rrsig.inception));
 			earliest_expiry = cmp::min(earliest_expiry, resolve_time(rrsig.expiration));
 			min_ttl = cmp::min(min_ttl, rrsig.orig_ttl);
+			let rrsig_idx = inp.iter().position(|rr| rr.as_rrsig().map_or(false, |sig|
+				sig.name == rrsig.name && sig.ty == rrsig.ty && sig.signature == rrsig.signature));
+			if rrsig.ty == NSec::TYPE || rrsig.ty == NSec3::TYPE {
+				let signed_idxs = signed_records.clone().map(|(idx, _)| idx).collect::<Vec<_>>();
+				nsec_sets.push((rrsig.name, rrsig.ty, rrsig_idx, signed_idxs));
+			} else {
+				used.extend(rrsig_idx);
+				used.extend(signed_records.clone().map(|(idx, _)| idx));
+			}
 			match rrsig.ty {
 				// RRSigs shouldn't cover child `DnsKey`s or other `RRSig`s
 				RRSig::TYPE|DnsKey::TYPE => return Err(ValidationError::Invalid),
 				DS::TYPE => {
-					if !pending_ds_sets.iter().any(|(pending_zone, _)| pending_zone == &rrsig.name.as_str()) {
+					if !pending_ds_sets.iter().any(|(pending_zone, _)| *pending_zone == rrsig.name) {
 						pending_ds_sets.push((
-							&rrsig.name,
-							signed_records.filter_map(|rr|
-								if let RR::DS(ds) = rr { Some(ds) }
-								else { debug_assert!(false, "We already filtered by type"); None })
+							rrsig.name,
+							signed_records.filter_map(|(_, rr)| {
+								let ds = rr.as_ds();
+								debug_assert!(ds.is_some(), "We already filtered by type");
+								ds
+							})
 						));
 					}
 				},
@@ -433,10 +821,10 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 						// a.b.c.
 						let proof_name = rrsig.name.trailing_n_labels(rrsig.labels + 1)
 							.ok_or(ValidationError::Invalid)?;
-						rrs_needing_non_existence_proofs.push((proof_name, &rrsig.key_name, rrsig.ty));
+						rrs_needing_non_existence_proofs.push((proof_name, rrsig.key_name, rrsig.ty));
 					}
-					for record in signed_records {
-						if !res.contains(&record) { res.push(record); }
+					for (idx, record) in signed_records {
+						if !res.iter().any(|res_idx| inp[*res_idx] == *record) { res.push(idx); }
 					}
 				},
 			}
@@ -448,23 +836,33 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 
 	// First sort the proofs we're looking for so that the retains below avoid shifting.
 	rrs_needing_non_existence_proofs.sort_unstable_by(nsec_ord_extra);
+	let mut use_nsec_set = |name, ty| {
+		for (set_name, set_ty, rrsig_idx, idxs) in nsec_sets.iter() {
+			if *set_name == name && *set_ty == ty {
+				used.extend(rrsig_idx);
+				used.extend(idxs);
+			}
+		}
+	};
 	'proof_search_loop: while let Some((name, zone, ty)) = rrs_needing_non_existence_proofs.pop() {
 		let nsec_search = res.iter()
-			.filter_map(|rr| if let RR::NSec(nsec) = rr { Some(nsec) } else { None })
-			.filter(|nsec| nsec.name.ends_with(zone.as_str()));
+			.filter_map(|idx| inp[*idx].as_nsec())
+			.filter(|nsec| nsec.name.is_subdomain_of(zone));
 		for nsec in nsec_search {
-			let name_matches = nsec.name.as_str() == name;
-			let name_contained = nsec_ord(&nsec.name,  name) != Ordering::Greater &&
-				nsec_ord(&nsec.next_name, name) == Ordering::Greater;
-			if (name_matches && !nsec.types.contains_type(ty)) || name_contained {
+			let name_matches = nsec.name == name;
+			let name_contained = canonical_name_cmp(nsec.name.wire(), name.wire()) != Ordering::Greater &&
+				canonical_name_cmp(nsec.next_name.wire(), name.wire()) == Ordering::Greater;
+			let contains_type = |ty| NSecTypeMask::wire_contains_type(nsec.types, ty);
+			if (name_matches && !contains_type(ty)) || name_contained {
+				use_nsec_set(nsec.name, NSec::TYPE);
 				rrs_needing_non_existence_proofs
-					.retain(|(n, _, t)| *n != name || (name_matches && nsec.types.contains_type(*t)));
+					.retain(|(n, _, t)| *n != name || (name_matches && contains_type(*t)));
 				continue 'proof_search_loop;
 			}
 		}
 		let nsec3_search = res.iter()
-			.filter_map(|rr| if let RR::NSec3(nsec3) = rr { Some(nsec3) } else { None })
-			.filter(|nsec3| nsec3.name.ends_with(zone.as_str()));
+			.filter_map(|idx| inp[*idx].as_nsec3())
+			.filter(|nsec3| nsec3.name.is_subdomain_of(zone));
 
 		// Because we will only ever have two entries, a Vec is simpler than a map here.
 		let mut nsec3params_to_name_hash = Vec::new();
@@ -476,19 +874,19 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 			}
 			if nsec3.hash_algo != 1 { continue; }
 			if nsec3params_to_name_hash.iter()
-				.any(|(iterations, salt, _)| *iterations == nsec3.hash_iterations && *salt == &nsec3.salt)
+				.any(|(iterations, salt, _)| *iterations == nsec3.hash_iterations && *salt == nsec3.salt)
 			{ continue; }
 
 			let mut hasher = crypto::hash::Hasher::sha1();
-			write_name(&mut hasher, name);
-			hasher.update(&nsec3.salt);
+			name.write_canonical(&mut hasher);
+			hasher.update(nsec3.salt);
 			for _ in 0..nsec3.hash_iterations {
 				let res = hasher.finish();
 				hasher = crypto::hash::Hasher::sha1();
 				hasher.update(res.as_ref());
-				hasher.update(&nsec3.salt);
+				hasher.update(nsec3.salt);
 			}
-			nsec3params_to_name_hash.push((nsec3.hash_iterations, &nsec3.salt, hasher.finish()));
+			nsec3params_to_name_hash.push((nsec3.hash_iterations, nsec3.salt, hasher.finish()));
 
 			if nsec3params_to_name_hash.len() >= 2 {
 				// We only allow for up to two sets of hash_iterations/salt per zone. Beyond that
@@ -505,13 +903,13 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 			if nsec3.hash_algo != 1 { continue; }
 			let name_hash = if let Some((_, _, hash)) =
 				nsec3params_to_name_hash.iter()
-				.find(|(iterations, salt, _)| *iterations == nsec3.hash_iterations && *salt == &nsec3.salt)
+				.find(|(iterations, salt, _)| *iterations == nsec3.hash_iterations && *salt == nsec3.salt)
 			{
 				hash
 			} else { continue };
 
-			let (start_hash_base32, _) = nsec3.name.split_once('.')
-				.unwrap_or_else(|| { debug_assert!(false); ("", "")});
+			let start_hash_base32 = nsec3.name.iter_labels().next()
+				.and_then(|label| core::str::from_utf8(label).ok()).unwrap_or("");
 			let start_hash = if let Ok(start_hash) = base32::decode(start_hash_base32) {
 				start_hash
 			} else { continue };
@@ -519,20 +917,24 @@ pub fn verify_rr_stream<'a>(inp: &'a [RR]) -> Result<VerifiedRRStream<'a>, Valid
 
 			let hash_matches = &start_hash[..] == name_hash.as_ref();
 			let hash_contained =
-				&start_hash[..] <= name_hash.as_ref() && &nsec3.next_name_hash[..] > name_hash.as_ref();
-			if (hash_matches && !nsec3.types.contains_type(ty)) || hash_contained {
+				&start_hash[..] <= name_hash.as_ref() && nsec3.next_name_hash > name_hash.as_ref();
+			let contains_type = |ty| NSecTypeMask::wire_contains_type(nsec3.types, ty);
+			if (hash_matches && !contains_type(ty)) || hash_contained {
+				use_nsec_set(nsec3.name, NSec3::TYPE);
 				rrs_needing_non_existence_proofs
-					.retain(|(n, _, t)| *n != name || (hash_matches && nsec3.types.contains_type(*t)));
+					.retain(|(n, _, t)| *n != name || (hash_matches && contains_type(*t)));
 				continue 'proof_search_loop;
 			}
 		}
 		return Err(ValidationError::Invalid);
 	}
 
-	res.retain(|rr| rr.ty() != NSec::TYPE && rr.ty() != NSec3::TYPE);
+	res.retain(|idx| inp[*idx].signed_ty() != NSec::TYPE && inp[*idx].signed_ty() != NSec3::TYPE);
+	used.sort_unstable();
+	used.dedup();
 
-	Ok(VerifiedRRStream {
-		verified_rrs: res, valid_from: latest_inception, expires: earliest_expiry,
+	Ok(VerifiedRecords {
+		verified_idxs: res, used_idxs: used, valid_from: latest_inception, expires: earliest_expiry,
 		max_cache_ttl: min_ttl,
 	})
 }
@@ -559,13 +961,15 @@ impl<'a> VerifiedRRStream<'a> {
 				continue;
 			}
 
+			// Note that DNAMEs only redirect names strictly below their owner (RFC 6672 section 2.3)
 			let mut dname_search = self.verified_rrs.iter()
-				.filter(|rr| name.ends_with(&**rr.name()))
+				.filter(|rr| rr.name() != name && name.is_subdomain_of(rr.name()))
 				.filter_map(|rr| if let RR::DName(dn) = rr { Some(dn) } else { None });
 			if let Some(dname) = dname_search.next() {
-				let prefix = name.strip_suffix(&*dname.name).expect("We just filtered for this");
-				let resolved_name = prefix.to_owned() + &dname.delegation_name;
-				dname_name = if let Ok(name) = resolved_name.try_into() {
+				let prefix_len = name.wire().len() - dname.name.wire().len();
+				let mut resolved_name = name.wire()[..prefix_len].to_vec();
+				resolved_name.extend_from_slice(dname.delegation_name.wire());
+				dname_name = if let Ok(name) = Name::from_wire(&resolved_name) {
 					name
 				} else {
 					// This should only happen if the combined name ended up being too long
@@ -578,10 +982,69 @@ impl<'a> VerifiedRRStream<'a> {
 			return self.verified_rrs.iter().filter(|rr| rr.name() == name).copied().collect();
 		}
 	}
+
+	/// Gets the verified hostname(s) which the given IP address maps to via reverse lookup, i.e.
+	/// the [`PTR::ptr_name`]s of any [`PTR`] records at [`PTR::name_for_ip`].
+	///
+	/// Note that this does not verify that the returned hostnames map back to the given IP
+	/// address, which is generally required before trusting a reverse lookup.
+	///
+	/// You MUST still check that the current UNIX time is between
+	/// [`VerifiedRRStream::valid_from`] and [`VerifiedRRStream::expires`] before
+	/// using any names returned here.
+	#[cfg(feature = "std")]
+	pub fn resolve_ptr(&self, ip: std::net::IpAddr) -> Vec<&'a Name> {
+		self.resolve_name(&PTR::name_for_ip(ip)).into_iter()
+			.filter_map(|rr| if let RR::PTR(ptr) = rr { Some(&ptr.ptr_name) } else { None })
+			.collect()
+	}
+}
+
+/// An owned form of [`VerifiedRRStream`], which holds its own copies of the verified [`RR`]s
+/// rather than references into the proof they were verified from.
+///
+/// This is useful for storing verified records beyond the lifetime of the proof, e.g. via its
+/// `serde` implementations (if the `serde` feature is enabled), which are compatible with the
+/// serialization of a [`VerifiedRRStream`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedVerifiedRRStream {
+	/// The set of verified [`RR`]s. See [`VerifiedRRStream::verified_rrs`].
+	pub verified_rrs: Vec<RR>,
+	/// See [`VerifiedRRStream::valid_from`].
+	pub valid_from: u64,
+	/// See [`VerifiedRRStream::expires`].
+	pub expires: u64,
+	/// See [`VerifiedRRStream::max_cache_ttl`].
+	pub max_cache_ttl: u32,
+}
+
+impl OwnedVerifiedRRStream {
+	/// Gets a [`VerifiedRRStream`] referencing the records in this stream, e.g. to call
+	/// [`VerifiedRRStream::resolve_name`].
+	pub fn as_verified_rr_stream(&self) -> VerifiedRRStream<'_> {
+		VerifiedRRStream {
+			verified_rrs: self.verified_rrs.iter().collect(),
+			valid_from: self.valid_from,
+			expires: self.expires,
+			max_cache_ttl: self.max_cache_ttl,
+		}
+	}
+}
+
+impl<'a> From<&VerifiedRRStream<'a>> for OwnedVerifiedRRStream {
+	fn from(stream: &VerifiedRRStream<'a>) -> Self {
+		OwnedVerifiedRRStream {
+			verified_rrs: stream.verified_rrs.iter().map(|rr| (*rr).clone()).collect(),
+			valid_from: stream.valid_from,
+			expires: stream.expires,
+			max_cache_ttl: stream.max_cache_ttl,
+		}
+	}
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
 	#![allow(deprecated)]
 
 	use super::*;
@@ -593,7 +1056,7 @@ mod tests {
 	use hex_conservative::FromHex;
 	use rand::seq::SliceRandom;
 
-	fn root_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
+	pub(crate) fn root_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
 		let dnskeys = vec![DnsKey {
 			name: ".".try_into().unwrap(), flags: 256, protocol: 3, alg: 8,
 			pubkey: base64::decode("AwEAAentCcIEndLh2QSK+pHFq/PkKCwioxt75d7qNOUuTPMo0Fcte/NbwDPbocvbZ/eNb5RV/xQdapaJASQ/oDLsqzD0H1+JkHNuuKc2JLtpMxg4glSE4CnRXT2CnFTW5IwOREL+zeqZHy68OXy5ngW5KALbevRYRg/q2qFezRtCSQ0knmyPwgFsghVYLKwi116oxwEU5yZ6W7npWMxt5Z+Qs8diPNWrS5aXLgJtrWUGIIuFfuZwXYziGRP/z3o1EfMo9zZU19KLopkoLXX7Ls/diCXdSEdJXTtFA8w0/OKQviuJebfKscoElCTswukVZ1VX5gbaFEo2xWhHJ9Uo63wYaTk=").unwrap(),
@@ -612,7 +1075,7 @@ mod tests {
 		(dnskeys, rrs)
 	}
 
-	fn com_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
+	pub(crate) fn com_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
 		let root_dnskeys = root_dnskey().0;
 		let mut com_ds = vec![DS {
 			name: "com.".try_into().unwrap(), key_tag: 19718, alg: 13, digest_type: 2,
@@ -642,7 +1105,7 @@ mod tests {
 		(dnskeys, rrs)
 	}
 
-	fn ninja_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
+	pub(crate) fn ninja_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
 		let root_dnskeys = root_dnskey().0;
 		let mut ninja_ds = vec![DS {
 			name: "ninja.".try_into().unwrap(), key_tag: 46082, alg: 8, digest_type: 2,
@@ -676,7 +1139,7 @@ mod tests {
 		(dnskeys, rrs)
 	}
 
-	fn mattcorallo_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
+	pub(crate) fn mattcorallo_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
 		let com_dnskeys = com_dnskey().0;
 		let mut mattcorallo_ds = vec![DS {
 			name: "mattcorallo.com.".try_into().unwrap(), key_tag: 25630, alg: 13, digest_type: 2,
@@ -710,7 +1173,7 @@ mod tests {
 		(dnskeys, rrs)
 	}
 
-	fn mattcorallo_txt_record() -> (Txt, RRSig) {
+	pub(crate) fn mattcorallo_txt_record() -> (Txt, RRSig) {
 		let txt_resp = Txt {
 			name: "matt.user._bitcoin-payment.mattcorallo.com.".try_into().unwrap(),
 			data: "bitcoin:?b12=lno1qsgqmqvgm96frzdg8m0gc6nzeqffvzsqzrxqy32afmr3jn9ggkwg3egfwch2hy0l6jut6vfd8vpsc3h89l6u3dm4q2d6nuamav3w27xvdmv3lpgklhg7l5teypqz9l53hj7zvuaenh34xqsz2sa967yzqkylfu9xtcd5ymcmfp32h083e805y7jfd236w9afhavqqvl8uyma7x77yun4ehe9pnhu2gekjguexmxpqjcr2j822xr7q34p078gzslf9wpwz5y57alxu99s0z2ql0kfqvwhzycqq45ehh58xnfpuek80hw6spvwrvttjrrq9pphh0dpydh06qqspp5uq4gpyt6n9mwexde44qv7lstzzq60nr40ff38u27un6y53aypmx0p4qruk2tf9mjwqlhxak4znvna5y".try_into().unwrap(),
@@ -724,7 +1187,7 @@ mod tests {
 		(txt_resp, txt_rrsig)
 	}
 
-	fn bitcoin_ninja_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
+	pub(crate) fn bitcoin_ninja_dnskey() -> (Vec<DnsKey>, Vec<RR>) {
 		let ninja_dnskeys = ninja_dnskey().0;
 		let mut bitcoin_ninja_ds = vec![DS {
 			name: "bitcoin.ninja.".try_into().unwrap(), key_tag: 63175, alg: 13, digest_type: 2,
@@ -754,7 +1217,7 @@ mod tests {
 		(dnskeys, rrs)
 	}
 
-	fn bitcoin_ninja_txt_record() -> (Txt, RRSig) {
+	pub(crate) fn bitcoin_ninja_txt_record() -> (Txt, RRSig) {
 		let txt_resp = Txt {
 			name: "txt_test.dnssec_proof_tests.bitcoin.ninja.".try_into().unwrap(),
 			data: "dnssec_prover_test".try_into().unwrap(),
@@ -1016,6 +1479,91 @@ mod tests {
 		assert_eq!(verified_rrs.max_cache_ttl, 3600); // The TXT record had the shortest TTL
 	}
 
+	#[test]
+	fn check_minimize_proof() {
+		let mut rrs = Vec::new();
+		rrs.extend(root_dnskey().1);
+		rrs.extend(com_dnskey().1);
+		rrs.extend(mattcorallo_dnskey().1);
+		let (txt, txt_rrsig) = mattcorallo_txt_record();
+		rrs.extend([RR::Txt(txt), RR::RRSig(txt_rrsig)]);
+		let mut minimal_proof = Vec::new();
+		for rr in rrs.iter() { write_rr(rr, 1, &mut minimal_proof); }
+		let minimal_proof = minimize_proof(&minimal_proof).unwrap();
+
+		// Add duplicates, a signature over the com. DNSKEYs by a key which isn't in the DS set, and
+		// records which aren't part of any chain (as the ninja. DS is missing) or aren't signed.
+		let mut bogus_rrsig = com_dnskey().1.pop().unwrap();
+		if let RR::RRSig(rrsig) = &mut bogus_rrsig { rrsig.key_tag = 1; } else { panic!(); }
+		let mut padded_rrs = rrs.clone();
+		padded_rrs.extend(com_dnskey().1);
+		padded_rrs.push(bogus_rrsig);
+		padded_rrs.extend(bitcoin_ninja_dnskey().1);
+		padded_rrs.push(A { name: "mattcorallo.com.".try_into().unwrap(), address: [192, 0, 2, 1] }.into());
+
+		for _ in 0..4 {
+			padded_rrs.shuffle(&mut rand::rngs::OsRng);
+			let mut proof = Vec::new();
+			for (idx, rr) in padded_rrs.iter().enumerate() { write_rr(rr, idx as u32, &mut proof); }
+			// The output only depends on the set of records needed
+			assert_eq!(minimize_proof(&proof).unwrap(), minimal_proof);
+			assert!(minimal_proof.len() < proof.len());
+		}
+
+		let mut expected_rrs = rrs.clone();
+		expected_rrs.sort_unstable();
+		let mut minimal_rrs = parse_rr_stream(&minimal_proof).unwrap();
+		minimal_rrs.sort_unstable();
+		assert_eq!(minimal_rrs, expected_rrs);
+		assert_eq!(minimize_proof(&minimal_proof).unwrap(), minimal_proof);
+
+		// The minimized proof verifies to the same records, with the same validity window
+		let verified_rrs = verify_rr_stream(&minimal_rrs).unwrap();
+		let expected_verified = verify_rr_stream(&rrs).unwrap();
+		assert_eq!(verified_rrs.verified_rrs, expected_verified.verified_rrs);
+		assert_eq!(verified_rrs.valid_from, expected_verified.valid_from);
+		assert_eq!(verified_rrs.expires, expected_verified.expires);
+		assert_eq!(verified_rrs.max_cache_ttl, expected_verified.max_cache_ttl);
+
+		// Records are written in canonical order, with the TTLs they were signed with
+		let mut read = &minimal_proof[..];
+		let first_rr = parse_rr(&mut read).unwrap();
+		assert_eq!(first_rr.name().as_str(), ".");
+		assert_eq!(&minimal_proof[1 + 4..1 + 8], &172800u32.to_be_bytes());
+
+		// Proofs which don't verify can't be minimized
+		let mut unverifiable_proof = Vec::new();
+		for rr in rrs.iter().filter(|rr| rr.ty() != Txt::TYPE) { write_rr(rr, 1, &mut unverifiable_proof); }
+		assert!(minimize_proof(&unverifiable_proof).is_err());
+		assert!(minimize_proof(&minimal_proof[..minimal_proof.len() - 1]).is_err());
+	}
+
+	#[test]
+	fn check_minimize_proof_unused_nsec() {
+		let mut rrs = Vec::new();
+		rrs.extend(root_dnskey().1);
+		rrs.extend(ninja_dnskey().1);
+		rrs.extend(bitcoin_ninja_dnskey().1);
+		rrs.extend(bitcoin_ninja_nsec_dnskey().1);
+		let (txt, txt_rrsig) = bitcoin_ninja_nsec_record();
+		rrs.extend([RR::Txt(txt), RR::RRSig(txt_rrsig)]);
+		let mut proof = Vec::new();
+		for rr in rrs.iter() { write_rr(rr, 1, &mut proof); }
+		let minimal_proof = minimize_proof(&proof).unwrap();
+
+		// A validly-signed NSEC which isn't needed for any non-existence proof is dropped...
+		let (wildcard_txt, wildcard_txt_rrsig, nsec, nsec_rrsig) = bitcoin_ninja_nsec_wildcard_record("a");
+		for rr in [RR::NSec(nsec.clone()), RR::RRSig(nsec_rrsig.clone())] { write_rr(&rr, 1, &mut proof); }
+		assert!(verify_rr_stream(&parse_rr_stream(&proof).unwrap()).is_ok());
+		assert_eq!(minimize_proof(&proof).unwrap(), minimal_proof);
+
+		// ...but kept once it's needed to prove a wildcard expansion.
+		for rr in [RR::Txt(wildcard_txt), RR::RRSig(wildcard_txt_rrsig)] { write_rr(&rr, 1, &mut proof); }
+		let minimal_rrs = parse_rr_stream(&minimize_proof(&proof).unwrap()).unwrap();
+		assert!(minimal_rrs.contains(&RR::NSec(nsec)));
+		assert!(minimal_rrs.contains(&RR::RRSig(nsec_rrsig)));
+	}
+
 	#[test]
 	fn check_txt_record_b() {
 		let dnskeys = bitcoin_ninja_dnskey().0;
@@ -1200,6 +1748,49 @@ mod tests {
 		check_proof("a.z", true).unwrap_err();
 	}
 
+	#[test]
+	fn check_cds_cdnskey() {
+		let dnskeys = com_dnskey().0;
+		let ksk = &dnskeys[1];
+		let cds = CDS {
+			name: "com.".try_into().unwrap(), key_tag: 19718, alg: 13, digest_type: 2,
+			digest: Vec::from_hex("8ACBB0CD28F41250A80A491389424D341522D946B0DA0C0291F2D3D771D7805A").unwrap(),
+		};
+		let cdnskey = CDnsKey {
+			name: ksk.name.clone(), flags: ksk.flags, protocol: ksk.protocol, alg: ksk.alg,
+			pubkey: ksk.pubkey.clone(),
+		};
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&cds], [&cdnskey]), Ok(DSUpdateRequest::Update));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&cds], []), Ok(DSUpdateRequest::Update));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [], [&cdnskey]), Ok(DSUpdateRequest::Update));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [], []), Err(ValidationError::Invalid));
+
+		// CDS/CDNSKEYs which don't match any current key are rejected
+		let mut bad_cds = cds.clone();
+		bad_cds.digest[0] ^= 1;
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&bad_cds], [&cdnskey]), Err(ValidationError::Invalid));
+		let mut bad_cdnskey = cdnskey.clone();
+		bad_cdnskey.flags = 256;
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&cds], [&bad_cdnskey]), Err(ValidationError::Invalid));
+		let mut unknown_digest_cds = cds.clone();
+		unknown_digest_cds.digest_type = 42;
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&unknown_digest_cds], []),
+			Err(ValidationError::UnsupportedAlgorithm));
+
+		// Deletion requests must stand alone
+		let delete_cds = CDS {
+			name: "com.".try_into().unwrap(), key_tag: 0, alg: 0, digest_type: 0, digest: vec![0],
+		};
+		let delete_cdnskey = CDnsKey {
+			name: "com.".try_into().unwrap(), flags: 0, protocol: 3, alg: 0, pubkey: vec![0],
+		};
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&delete_cds], [&delete_cdnskey]),
+			Ok(DSUpdateRequest::Delete));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&delete_cds], []), Ok(DSUpdateRequest::Delete));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&delete_cds, &cds], []), Err(ValidationError::Invalid));
+		assert_eq!(verify_cds_cdnskey(&dnskeys, [&delete_cds], [&cdnskey]), Err(ValidationError::Invalid));
+	}
+
 	#[test]
 	fn check_txt_sort_order() {
 		let mut rr_stream = Vec::new();
@@ -1221,11 +1812,15 @@ mod tests {
 		assert_eq!(verified_txts, txts);
 	}
 
-	#[test]
-	fn rfc9102_parse_test() {
+	fn rfc9102_test_vector() -> Vec<u8> {
 		// Note that this is the `AuthenticationChain` field only, and ignores the
 		// `ExtSupportLifetime` field (stripping the top two 0 bytes from the front).
-let rfc9102_test_vector = Vec::from_hex("045f343433045f74637003777777076578616d706c6503636f6d000034000100000e1000230301018bd1da95272f7fa4ffb24137fc0ed03aae67e5c4d8b3c50734e1050a7920b922045f343433045f74637003777777076578616d706c6503636f6d00002e000100000e10005f00340d0500000e105fc6d9005bfdda80074e076578616d706c6503636f6d00ce1d3adeb7dc7cee656d61cfb472c5977c8c9caeae9b765155c518fb107b6a1fe0355fbaaf753c192832fa621fa73a8b85ed79d374117387598fcc812e1ef3fb076578616d706c6503636f6d000030000100000e1000440101030d2670355e0c894d9cfea6c5af6eb7d458b57a50ba88272512d8241d8541fd54adf96ec956789a51ceb971094b3bb3f4ec49f64c686595be5b2e89e8799c7717cc076578616d706c6503636f6d00002e000100000e10005f00300d0200000e105fc6d9005bfdda80074e076578616d706c6503636f6d004628383075b8e34b743a209b27ae148d110d4e1a246138a91083249cb4a12a2d9bc4c2d7ab5eb3afb9f5d1037e4d5da8339c162a9298e9be180741a8ca74accc076578616d706c6503636f6d00002b00010002a3000024074e0d02e9b533a049798e900b5c29c90cd25a986e8a44f319ac3cd302bafc08f5b81e16076578616d706c6503636f6d00002e00010002a3000057002b0d020002a3005fc6d9005bfdda80861703636f6d00a203e704a6facbeb13fc9384fdd6de6b50de5659271f38ce81498684e6363172d47e2319fdb4a22a58a231edc2f1ff4fb2811a1807be72cb5241aa26fdaee03903636f6d00003000010002a30000440100030dec8204e43a25f2348c52a1d3bce3a265aa5d11b43dc2a471162ff341c49db9f50a2e1a41caf2e9cd20104ea0968f7511219f0bdc56b68012cc3995336751900b03636f6d00003000010002a30000440101030d45b91c3bef7a5d99a7a7c8d822e33896bc80a777a04234a605a4a8880ec7efa4e6d112c73cd3d4c65564fa74347c873723cc5f643370f166b43dedff836400ff03636f6d00003000010002a30000440101030db3373b6e22e8e49e0e1e591a9f5bd9ac5e1a0f86187fe34703f180a9d36c958f71c4af48ce0ebc5c792a724e11b43895937ee53404268129476eb1aed323939003636f6d00002e00010002a300005700300d010002a3005fc6d9005bfdda8049f303636f6d0018a948eb23d44f80abc99238fcb43c5a18debe57004f7343593f6deb6ed71e04654a433f7aa1972130d9bd921c73dcf63fcf665f2f05a0aaebafb059dc12c96503636f6d00002e00010002a300005700300d010002a3005fc6d9005bfdda80708903636f6d006170e6959bd9ed6e575837b6f580bd99dbd24a44682b0a359626a246b1812f5f9096b75e157e77848f068ae0085e1a609fc19298c33b736863fbccd4d81f5eb203636f6d00002b000100015180002449f30d0220f7a9db42d0e2042fbbb9f9ea015941202f9eabb94487e658c188e7bcb5211503636f6d00002b000100015180002470890d02ad66b3276f796223aa45eda773e92c6d98e70643bbde681db342a9e5cf2bb38003636f6d00002e0001000151800053002b0d01000151805fc6d9005bfdda807cae00122e276d45d9e9816f7922ad6ea2e73e82d26fce0a4b718625f314531ac92f8ae82418df9b898f989d32e80bc4deaba7c4a7c8f172adb57ced7fb5e77a784b0700003000010001518000440100030dccacfe0c25a4340fefba17a254f706aac1f8d14f38299025acc448ca8ce3f561f37fc3ec169fe847c8fcbe68e358ff7c71bb5ee1df0dbe518bc736d4ce8dfe1400003000010001518000440100030df303196789731ddc8a6787eff24cacfeddd032582f11a75bb1bcaa5ab321c1d7525c2658191aec01b3e98ab7915b16d571dd55b4eae51417110cc4cdd11d171100003000010001518000440101030dcaf5fe54d4d48f16621afb6bd3ad2155bacf57d1faad5bac42d17d948c421736d9389c4c4011666ea95cf17725bd0fa00ce5e714e4ec82cfdfacc9b1c863ad4600002e000100015180005300300d00000151805fc6d9005bfdda80b79d00de7a6740eeecba4bda1e5c2dd4899b2c965893f3786ce747f41e50d9de8c0a72df82560dfb48d714de3283ae99a49c0fcb50d3aaadb1a3fc62ee3a8a0988b6be").unwrap();
+Vec::from_hex("045f343433045f74637003777777076578616d706c6503636f6d000034000100000e1000230301018bd1da95272f7fa4ffb24137fc0ed03aae67e5c4d8b3c50734e1050a7920b922045f343433045f74637003777777076578616d706c6503636f6d00002e000100000e10005f00340d0500000e105fc6d9005bfdda80074e076578616d706c6503636f6d00ce1d3adeb7dc7cee656d61cfb472c5977c8c9caeae9b765155c518fb107b6a1fe0355fbaaf753c192832fa621fa73a8b85ed79d374117387598fcc812e1ef3fb076578616d706c6503636f6d000030000100000e1000440101030d2670355e0c894d9cfea6c5af6eb7d458b57a50ba88272512d8241d8541fd54adf96ec956789a51ceb971094b3bb3f4ec49f64c686595be5b2e89e8799c7717cc076578616d706c6503636f6d00002e000100000e10005f00300d0200000e105fc6d9005bfdda80074e076578616d706c6503636f6d004628383075b8e34b743a209b27ae148d110d4e1a246138a91083249cb4a12a2d9bc4c2d7ab5eb3afb9f5d1037e4d5da8339c162a9298e9be180741a8ca74accc076578616d706c6503636f6d00002b00010002a3000024074e0d02e9b533a049798e900b5c29c90cd25a986e8a44f319ac3cd302bafc08f5b81e16076578616d706c6503636f6d00002e00010002a3000057002b0d020002a3005fc6d9005bfdda80861703636f6d00a203e704a6facbeb13fc9384fdd6de6b50de5659271f38ce81498684e6363172d47e2319fdb4a22a58a231edc2f1ff4fb2811a1807be72cb5241aa26fdaee03903636f6d00003000010002a30000440100030dec8204e43a25f2348c52a1d3bce3a265aa5d11b43dc2a471162ff341c49db9f50a2e1a41caf2e9cd20104ea0968f7511219f0bdc56b68012cc3995336751900b03636f6d00003000010002a30000440101030d45b91c3bef7a5d99a7a7c8d822e33896bc80a777a04234a605a4a8880ec7efa4e6d112c73cd3d4c65564fa74347c873723cc5f643370f166b43dedff836400ff03636f6d00003000010002a30000440101030db3373b6e22e8e49e0e1e591a9f5bd9ac5e1a0f86187fe34703f180a9d36c958f71c4af48ce0ebc5c792a724e11b43895937ee53404268129476eb1aed323939003636f6d00002e00010002a300005700300d010002a3005fc6d9005bfdda8049f303636f6d0018a948eb23d44f80abc99238fcb43c5a18debe57004f7343593f6deb6ed71e04654a433f7aa1972130d9bd921c73dcf63fcf665f2f05a0aaebafb059dc12c96503636f6d00002e00010002a300005700300d010002a3005fc6d9005bfdda80708903636f6d006170e6959bd9ed6e575837b6f580bd99dbd24a44682b0a359626a246b1812f5f9096b75e157e77848f068ae0085e1a609fc19298c33b736863fbccd4d81f5eb203636f6d00002b000100015180002449f30d0220f7a9db42d0e2042fbbb9f9ea015941202f9eabb94487e658c188e7bcb5211503636f6d00002b000100015180002470890d02ad66b3276f796223aa45eda773e92c6d98e70643bbde681db342a9e5cf2bb38003636f6d00002e0001000151800053002b0d01000151805fc6d9005bfdda807cae00122e276d45d9e9816f7922ad6ea2e73e82d26fce0a4b718625f314531ac92f8ae82418df9b898f989d32e80bc4deaba7c4a7c8f172adb57ced7fb5e77a784b0700003000010001518000440100030dccacfe0c25a4340fefba17a254f706aac1f8d14f38299025acc448ca8ce3f561f37fc3ec169fe847c8fcbe68e358ff7c71bb5ee1df0dbe518bc736d4ce8dfe1400003000010001518000440100030df303196789731ddc8a6787eff24cacfeddd032582f11a75bb1bcaa5ab321c1d7525c2658191aec01b3e98ab7915b16d571dd55b4eae51417110cc4cdd11d171100003000010001518000440101030dcaf5fe54d4d48f16621afb6bd3ad2155bacf57d1faad5bac42d17d948c421736d9389c4c4011666ea95cf17725bd0fa00ce5e714e4ec82cfdfacc9b1c863ad4600002e000100015180005300300d00000151805fc6d9005bfdda80b79d00de7a6740eeecba4bda1e5c2dd4899b2c965893f3786ce747f41e50d9de8c0a72df82560dfb48d714de3283ae99a49c0fcb50d3aaadb1a3fc62ee3a8a0988b6be").unwrap()
+	}
+
+	#[test]
+	fn rfc9102_parse_test() {
+		let rfc9102_test_vector = rfc9102_test_vector();
 
 		let mut rrs = parse_rr_stream(&rfc9102_test_vector).unwrap();
 		rrs.shuffle(&mut rand::rngs::OsRng);
@@ -1238,4 +1833,70 @@ let rfc9102_test_vector = Vec::from_hex("045f343433045f74637003777777076578616d7
 			assert_eq!(tlsa.data, Vec::from_hex("8bd1da95272f7fa4ffb24137fc0ed03aae67e5c4d8b3c50734e1050a7920b922").unwrap());
 		} else { panic!(); }
 	}
+
+	#[test]
+	fn rfc9102_ref_test() {
+		let rfc9102_test_vector = rfc9102_test_vector();
+		let rrs = parse_rr_stream(&rfc9102_test_vector).unwrap();
+		let verified_rrs = verify_rr_stream(&rrs).unwrap();
+		let rr_refs = parse_rr_stream_ref(&rfc9102_test_vector).unwrap();
+		assert_eq!(rr_refs.iter().map(|rr| rr.to_rr()).collect::<Vec<_>>(), rrs);
+
+		let check_verified = |proof: &[u8]| {
+			let verified_refs = verify_rr_stream_ref(&parse_rr_stream_ref(proof).unwrap()).unwrap();
+			assert_eq!(verified_refs.verified_rrs.len(), 1);
+			assert_eq!(verified_refs.verified_rrs[0].to_rr(), *verified_rrs.verified_rrs[0]);
+			assert_eq!(verified_refs.valid_from, verified_rrs.valid_from);
+			assert_eq!(verified_refs.expires, verified_rrs.expires);
+			assert_eq!(verified_refs.max_cache_ttl, verified_rrs.max_cache_ttl);
+		};
+		check_verified(&rfc9102_test_vector);
+
+		// Names in proofs may not be in canonical (lowercase) form, so uppercase the owner and
+		// signer names in the proof and check it still validates.
+		let mut uppercase_proof = Vec::new();
+		for rr in rr_refs.iter() {
+			uppercase_proof.extend_from_slice(&rr.name().wire().to_ascii_uppercase());
+			uppercase_proof.extend_from_slice(&rr.ty().to_be_bytes());
+			uppercase_proof.extend_from_slice(&1u16.to_be_bytes());
+			uppercase_proof.extend_from_slice(&0u32.to_be_bytes());
+			uppercase_proof.extend_from_slice(&(rr.rdata().len() as u16).to_be_bytes());
+			let mut rdata = rr.rdata().to_vec();
+			if rr.ty() == RRSig::TYPE {
+				let signer_len = NameRef::read(&mut &rdata[18..]).unwrap().wire().len();
+				rdata[18..18 + signer_len].make_ascii_uppercase();
+			}
+			uppercase_proof.extend_from_slice(&rdata);
+		}
+		assert_ne!(uppercase_proof, rfc9102_test_vector);
+		check_verified(&uppercase_proof);
+		assert_eq!(verify_rr_stream_ref(&parse_rr_stream_ref(&uppercase_proof).unwrap()).unwrap()
+			.verified_rrs[0].name().to_name().as_str(), "_443._tcp.www.example.com.");
+
+		// The proof can be wrapped in a `DnssecChainExtension` and then verified from that
+		let extension = DnssecChainExtension::new(42, rfc9102_test_vector.clone()).unwrap();
+		let mut extension_bytes = Vec::new();
+		extension.write(&mut extension_bytes);
+		assert_eq!(&extension_bytes[..4], &[0, 42, (rfc9102_test_vector.len() >> 8) as u8, rfc9102_test_vector.len() as u8]);
+		assert_eq!(DnssecChainExtension::parse(&extension_bytes).unwrap(), extension);
+		let (lifetime, verified_ext) = verify_dnssec_chain_extension(&extension_bytes).unwrap();
+		assert_eq!(lifetime, 42);
+		assert_eq!(verified_ext.verified_rrs[0].to_rr(), *verified_rrs.verified_rrs[0]);
+
+		assert!(DnssecChainExtension::new(0, Vec::new()).is_err());
+		assert!(DnssecChainExtension::new(0, rfc9102_test_vector[1..].to_vec()).is_err());
+		assert!(DnssecChainExtension::parse(&[0, 0, 0, 0]).is_err());
+		assert!(verify_dnssec_chain_extension(&[0, 0, 0, 0]).is_err());
+		assert!(DnssecChainExtension::parse(&extension_bytes[..extension_bytes.len() - 1]).is_err());
+		assert!(verify_dnssec_chain_extension(&extension_bytes[..extension_bytes.len() - 1]).is_err());
+		extension_bytes.push(0);
+		assert!(DnssecChainExtension::parse(&extension_bytes).is_err());
+		assert!(verify_dnssec_chain_extension(&extension_bytes).is_err());
+
+		// Changing the signed data, rather than just the case of names, breaks the proof.
+		let mut tampered_proof = rfc9102_test_vector.clone();
+		tampered_proof[50] ^= 1;
+		assert_eq!(verify_rr_stream_ref(&parse_rr_stream_ref(&tampered_proof).unwrap()).unwrap_err(),
+			ValidationError::Invalid);
+	}
 }
@@ -0,0 +1,581 @@
+//! Parsing of DNS zone files (RFC 1035 section 5 "master files") into [`RR`]s.
+//!
+//! The presentation format written by the [`core::fmt::Display`] implementations of [`RR`] and
+//! each record type can be read back using [`parse_zone_file`] (or, to get the TTL written by
+//! [`RR::display_with_ttl`] back as well, [`parse_zone_file_with_ttls`]).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::rr::*;
+use crate::ser::*;
+
+/// A single token from a zone file. Its text still contains any `\` escapes.
+struct Token<'a> {
+	text: &'a str,
+	quoted: bool,
+}
+
+/// Splits zone file text into logical lines (joining lines within parentheses and dropping
+/// comments), returning each line's tokens and whether the line began with whitespace (i.e. uses
+/// the previous record's owner name).
+fn tokenize(text: &str) -> Result<Vec<(bool, Vec<Token<'_>>)>, ()> {
+	let bytes = text.as_bytes();
+	let mut lines = Vec::new();
+	let mut tokens = Vec::new();
+	let mut starts_with_space = false;
+	let mut at_line_start = true;
+	let mut paren_depth = 0;
+	let mut i = 0;
+	while i < bytes.len() {
+		if at_line_start {
+			starts_with_space = bytes[i] == b' ' || bytes[i] == b'\t';
+			at_line_start = false;
+		}
+		match bytes[i] {
+			b'\n' => {
+				if paren_depth == 0 {
+					if !tokens.is_empty() { lines.push((starts_with_space, core::mem::take(&mut tokens))); }
+					at_line_start = true;
+				}
+				i += 1;
+			},
+			b' '|b'\t'|b'\r' => i += 1,
+			b';' => {
+				while i < bytes.len() && bytes[i] != b'\n' { i += 1; }
+			},
+			b'(' => {
+				paren_depth += 1;
+				i += 1;
+			},
+			b')' => {
+				if paren_depth == 0 { return Err(()); }
+				paren_depth -= 1;
+				i += 1;
+			},
+			b'"' => {
+				let start = i + 1;
+				i = start;
+				loop {
+					match bytes.get(i) {
+						None => return Err(()),
+						Some(b'"') => break,
+						Some(b'\\') => i += 2,
+						Some(_) => i += 1,
+					}
+				}
+				tokens.push(Token { text: &text[start..i], quoted: true });
+				i += 1;
+			},
+			_ => {
+				let start = i;
+				while i < bytes.len() && !matches!(bytes[i], b' '|b'\t'|b'\r'|b'\n'|b';'|b'('|b')'|b'"') {
+					if bytes[i] == b'\\' { i += 1; }
+					i += 1;
+				}
+				if i > bytes.len() { return Err(()); }
+				tokens.push(Token { text: &text[start..i], quoted: false });
+			},
+		}
+	}
+	if paren_depth != 0 { return Err(()); }
+	if !tokens.is_empty() { lines.push((starts_with_space, tokens)); }
+	Ok(lines)
+}
+
+fn next_token<'a, 'b>(tokens: &mut core::slice::Iter<'b, Token<'a>>) -> Result<&'b Token<'a>, ()> {
+	tokens.next().ok_or(())
+}
+
+/// Decodes `\X` and `\DDD` escapes in a character-string.
+fn unescape(text: &str) -> Result<Vec<u8>, ()> {
+	let mut res = Vec::with_capacity(text.len());
+	let mut bytes = text.bytes();
+	while let Some(b) = bytes.next() {
+		if b == b'\\' {
+			res.push(read_escaped_byte(&mut bytes)?);
+		} else {
+			res.push(b);
+		}
+	}
+	Ok(res)
+}
+
+fn parse_int<T: TryFrom<u64>>(token: &Token) -> Result<T, ()> {
+	if token.quoted || token.text.is_empty() || !token.text.bytes().all(|b| b.is_ascii_digit()) {
+		return Err(());
+	}
+	let val: u64 = token.text.parse().map_err(|_| ())?;
+	T::try_from(val).map_err(|_| ())
+}
+
+/// Parses a TTL, either as a plain number of seconds or with BIND-style units (e.g. `1h30m`).
+fn parse_ttl(token: &Token) -> Result<u32, ()> {
+	if token.text.bytes().all(|b| b.is_ascii_digit()) { return parse_int(token); }
+	if token.quoted { return Err(()); }
+	let (mut total, mut cur) = (0u32, None);
+	for b in token.text.bytes() {
+		if b.is_ascii_digit() {
+			let val: u32 = cur.unwrap_or(0);
+			cur = Some(val.checked_mul(10).and_then(|v| v.checked_add((b - b'0') as u32)).ok_or(())?);
+		} else {
+			let multiplier = match b.to_ascii_lowercase() {
+				b's' => 1, b'm' => 60, b'h' => 60 * 60, b'd' => 60 * 60 * 24, b'w' => 60 * 60 * 24 * 7,
+				_ => return Err(()),
+			};
+			let val = cur.take().ok_or(())?.checked_mul(multiplier).ok_or(())?;
+			total = total.checked_add(val).ok_or(())?;
+		}
+	}
+	if cur.is_some() { return Err(()); }
+	Ok(total)
+}
+
+/// Parses an [`RRSig`] inception/expiration time, either in `YYYYMMDDHHmmSS` form or as a plain
+/// number of seconds since the UNIX epoch.
+fn parse_sig_time(token: &Token) -> Result<u32, ()> {
+	if token.text.len() != 14 { return parse_int(token); }
+	if !token.text.bytes().all(|b| b.is_ascii_digit()) { return Err(()); }
+	let field = |range: core::ops::Range<usize>| parse_int::<u32>(&Token { text: &token.text[range], quoted: token.quoted });
+	let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+	let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+	if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+		return Err(());
+	}
+	let days = days_from_civil(year as i64, month, day);
+	let time = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+	time.try_into().map_err(|_| ())
+}
+
+/// Parses a (possibly relative) name, resolving it against `origin` if needed.
+fn parse_name(token: &Token, origin: Option<&Name>) -> Result<Name, ()> {
+	if token.quoted { return Err(()); }
+	if token.text == "@" { return origin.cloned().ok_or(()); }
+	if let Ok(name) = Name::try_from(token.text) { return Ok(name); }
+	let relative = Name::try_from(String::from(token.text) + ".")?;
+	relative.join(origin.ok_or(())?)
+}
+
+/// Concatenates the given (unquoted) tokens, as is done for base64 and hex RDATA fields which may
+/// be split by whitespace.
+fn concat_tokens(tokens: &[Token]) -> Result<String, ()> {
+	if tokens.is_empty() { return Err(()); }
+	let mut res = String::new();
+	for token in tokens.iter() {
+		if token.quoted { return Err(()); }
+		res += token.text;
+	}
+	Ok(res)
+}
+
+fn parse_hex(tokens: &[Token]) -> Result<Vec<u8>, ()> {
+	crate::hex::decode(&concat_tokens(tokens)?)
+}
+
+fn parse_ipv4(token: &Token) -> Result<[u8; 4], ()> {
+	let mut res = [0; 4];
+	let mut parts = token.text.split('.');
+	for byte in res.iter_mut() {
+		*byte = parse_int(&Token { text: parts.next().ok_or(())?, quoted: token.quoted })?;
+	}
+	if parts.next().is_some() { return Err(()); }
+	Ok(res)
+}
+
+/// Parses colon-separated IPv6 groups, allowing an embedded IPv4 address at the end if
+/// `allow_ipv4` is set.
+fn parse_ipv6_groups(s: &str, allow_ipv4: bool, groups: &mut Vec<u16>) -> Result<(), ()> {
+	if s.is_empty() { return Ok(()); }
+	let mut parts = s.split(':').peekable();
+	while let Some(part) = parts.next() {
+		if parts.peek().is_none() && allow_ipv4 && part.contains('.') {
+			let v4 = parse_ipv4(&Token { text: part, quoted: false })?;
+			groups.push(u16::from_be_bytes([v4[0], v4[1]]));
+			groups.push(u16::from_be_bytes([v4[2], v4[3]]));
+		} else {
+			if part.is_empty() || part.len() > 4 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+				return Err(());
+			}
+			groups.push(u16::from_str_radix(part, 16).map_err(|_| ())?);
+		}
+	}
+	Ok(())
+}
+
+fn parse_ipv6(token: &Token) -> Result<[u8; 16], ()> {
+	if token.quoted { return Err(()); }
+	let mut head = Vec::with_capacity(8);
+	let mut tail = Vec::with_capacity(8);
+	if let Some((head_str, tail_str)) = token.text.split_once("::") {
+		parse_ipv6_groups(head_str, false, &mut head)?;
+		parse_ipv6_groups(tail_str, true, &mut tail)?;
+		if head.len() + tail.len() > 7 { return Err(()); }
+	} else {
+		parse_ipv6_groups(token.text, true, &mut head)?;
+		if head.len() != 8 { return Err(()); }
+	}
+	let mut res = [0; 16];
+	for (idx, group) in head.iter().enumerate() {
+		res[idx * 2..idx * 2 + 2].copy_from_slice(&group.to_be_bytes());
+	}
+	for (idx, group) in tail.iter().enumerate() {
+		let pos = 8 - tail.len() + idx;
+		res[pos * 2..pos * 2 + 2].copy_from_slice(&group.to_be_bytes());
+	}
+	Ok(res)
+}
+
+fn write_char_string(data: &mut Vec<u8>, token: &Token) -> Result<(), ()> {
+	let bytes = unescape(token.text)?;
+	data.push(bytes.len().try_into().map_err(|_| ())?);
+	data.extend_from_slice(&bytes);
+	Ok(())
+}
+
+fn write_types_bitmap(data: &mut Vec<u8>, tokens: &[Token]) -> Result<(), ()> {
+	let mut types = Vec::with_capacity(tokens.len());
+	for token in tokens.iter() {
+		if token.quoted { return Err(()); }
+		types.push(type_from_mnemonic(token.text).ok_or(())?);
+	}
+	write_nsec_types_bitmap(data, &NSecTypeMask::from_types(&types));
+	Ok(())
+}
+
+/// Converts the presentation-format RDATA of a record of type `ty` into its wire form.
+fn parse_rdata_tokens(ty: u16, tokens: &[Token], origin: Option<&Name>) -> Result<Vec<u8>, ()> {
+	if tokens.first().map(|t| !t.quoted && t.text == "\\#").unwrap_or(false) {
+		// RFC 3597 generic RDATA
+		let len: u16 = parse_int(tokens.get(1).ok_or(())?)?;
+		let data = if len == 0 && tokens.len() == 2 { Vec::new() } else { parse_hex(&tokens[2..])? };
+		if data.len() != len as usize { return Err(()); }
+		return Ok(data);
+	}
+
+	let mut data = Vec::new();
+	let mut toks = tokens.iter();
+	match ty {
+		A::TYPE => data.extend_from_slice(&parse_ipv4(next_token(&mut toks)?)?),
+		AAAA::TYPE => data.extend_from_slice(&parse_ipv6(next_token(&mut toks)?)?),
+		NS::TYPE|CName::TYPE|DName::TYPE|PTR::TYPE => {
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+		},
+		SOA::TYPE => {
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+			data.extend_from_slice(&parse_int::<u32>(next_token(&mut toks)?)?.to_be_bytes());
+			for _ in 0..4 {
+				data.extend_from_slice(&parse_ttl(next_token(&mut toks)?)?.to_be_bytes());
+			}
+		},
+		Txt::TYPE => {
+			if tokens.is_empty() { return Err(()); }
+			for token in &mut toks { write_char_string(&mut data, token)?; }
+		},
+		TLSA::TYPE|SMIMEA::TYPE => {
+			for _ in 0..3 { data.push(parse_int(next_token(&mut toks)?)?); }
+			data.extend_from_slice(&parse_hex(toks.as_slice())?);
+			toks = [].iter();
+		},
+		OpenPGPKey::TYPE => {
+			data.extend_from_slice(&crate::base64::decode(&concat_tokens(toks.as_slice())?)?);
+			toks = [].iter();
+		},
+		URI::TYPE => {
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&unescape(next_token(&mut toks)?.text)?);
+		},
+		NAPTR::TYPE => {
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			for _ in 0..3 { write_char_string(&mut data, next_token(&mut toks)?)?; }
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+		},
+		CERT::TYPE => {
+			let cert_type = next_token(&mut toks)?;
+			let cert_type = match cert_type.text {
+				"PKIX" => 1, "SPKI" => 2, "PGP" => 3, "IPKIX" => 4, "ISPKI" => 5, "IPGP" => 6,
+				"ACPKIX" => 7, "IACPKIX" => 8, "URI" => 253, "OID" => 254,
+				_ => parse_int::<u16>(cert_type)?,
+			};
+			data.extend_from_slice(&u16::to_be_bytes(cert_type));
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&crate::base64::decode(&concat_tokens(toks.as_slice())?)?);
+			toks = [].iter();
+		},
+		DnsKey::TYPE|CDnsKey::TYPE => {
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&crate::base64::decode(&concat_tokens(toks.as_slice())?)?);
+			toks = [].iter();
+		},
+		DS::TYPE|CDS::TYPE => {
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&parse_hex(toks.as_slice())?);
+			toks = [].iter();
+		},
+		ZoneMD::TYPE => {
+			data.extend_from_slice(&parse_int::<u32>(next_token(&mut toks)?)?.to_be_bytes());
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&parse_hex(toks.as_slice())?);
+			toks = [].iter();
+		},
+		RRSig::TYPE => {
+			let covered = next_token(&mut toks)?;
+			if covered.quoted { return Err(()); }
+			data.extend_from_slice(&type_from_mnemonic(covered.text).ok_or(())?.to_be_bytes());
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&parse_int::<u32>(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&parse_sig_time(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&parse_sig_time(next_token(&mut toks)?)?.to_be_bytes());
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+			data.extend_from_slice(&crate::base64::decode(&concat_tokens(toks.as_slice())?)?);
+			toks = [].iter();
+		},
+		NSec::TYPE => {
+			write_name(&mut data, &parse_name(next_token(&mut toks)?, origin)?);
+			write_types_bitmap(&mut data, toks.as_slice())?;
+			toks = [].iter();
+		},
+		NSec3::TYPE => {
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.push(parse_int(next_token(&mut toks)?)?);
+			data.extend_from_slice(&parse_int::<u16>(next_token(&mut toks)?)?.to_be_bytes());
+			let salt_token = next_token(&mut toks)?;
+			let salt = if salt_token.text == "-" && !salt_token.quoted {
+				Vec::new()
+			} else {
+				parse_hex(core::slice::from_ref(salt_token))?
+			};
+			data.push(salt.len().try_into().map_err(|_| ())?);
+			data.extend_from_slice(&salt);
+			let next_hash_token = next_token(&mut toks)?;
+			if next_hash_token.quoted { return Err(()); }
+			let next_hash = crate::base32::decode(&next_hash_token.text.to_ascii_uppercase())?;
+			data.push(next_hash.len().try_into().map_err(|_| ())?);
+			data.extend_from_slice(&next_hash);
+			write_types_bitmap(&mut data, toks.as_slice())?;
+			toks = [].iter();
+		},
+		_ => return Err(()),
+	}
+	if toks.next().is_some() { return Err(()); }
+	Ok(data)
+}
+
+/// Parses the given zone file text into the records it contains.
+///
+/// The `$ORIGIN` and `$TTL` directives, relative names (including `@`), parentheses, comments
+/// and quoted strings are supported, as is RFC 3597 generic (`\# len hex`) RDATA. Only the `IN`
+/// class and record types which have an [`RR`] variant are supported.
+///
+/// As [`RR`]s do not carry a TTL, the records' TTLs are dropped. Use
+/// [`parse_zone_file_with_ttls`] to get them as well.
+pub fn parse_zone_file(zone: &str) -> Result<Vec<RR>, ()> {
+	Ok(parse_zone(zone)?.into_iter().map(|(rr, _)| rr).collect())
+}
+
+/// Parses the given zone file text into the records it contains, along with each record's TTL.
+///
+/// Records without an explicit TTL get the one set by the last `$TTL` directive or, if there
+/// was none, the TTL of the previous record (RFC 2308 section 4). If neither exists the zone
+/// file is rejected.
+///
+/// See [`parse_zone_file`] for the supported syntax.
+pub fn parse_zone_file_with_ttls(zone: &str) -> Result<Vec<(RR, u32)>, ()> {
+	parse_zone(zone)?.into_iter().map(|(rr, ttl)| Ok((rr, ttl.ok_or(())?))).collect()
+}
+
+/// Parses the given zone file text, returning each record with its TTL, if one was available.
+fn parse_zone(zone: &str) -> Result<Vec<(RR, Option<u32>)>, ()> {
+	let mut origin = None;
+	let mut default_ttl = None;
+	let mut last_ttl = None;
+	let mut last_owner: Option<Name> = None;
+	let mut res = Vec::new();
+	for (reuses_owner, tokens) in tokenize(zone)? {
+		let mut toks = tokens.iter();
+		if !reuses_owner && !tokens[0].quoted && tokens[0].text.starts_with('$') {
+			toks.next();
+			if tokens[0].text.eq_ignore_ascii_case("$ORIGIN") {
+				origin = Some(parse_name(next_token(&mut toks)?, origin.as_ref())?);
+			} else if tokens[0].text.eq_ignore_ascii_case("$TTL") {
+				default_ttl = Some(parse_ttl(next_token(&mut toks)?)?);
+			} else {
+				// $INCLUDE and $GENERATE are not supported
+				return Err(());
+			}
+			if toks.next().is_some() { return Err(()); }
+			continue;
+		}
+
+		let owner = if reuses_owner {
+			last_owner.clone().ok_or(())?
+		} else {
+			parse_name(next_token(&mut toks)?, origin.as_ref())?
+		};
+
+		// The TTL and class are both optional and may appear in either order
+		let (mut ttl, mut have_class) = (None, false);
+		let ty_token = loop {
+			let token = next_token(&mut toks)?;
+			if token.quoted { return Err(()); }
+			if ttl.is_none() && token.text.as_bytes()[0].is_ascii_digit() {
+				ttl = Some(parse_ttl(token)?);
+			} else if !have_class && token.text.eq_ignore_ascii_case("IN") {
+				have_class = true;
+			} else {
+				break token;
+			}
+		};
+		let ty = type_from_mnemonic(ty_token.text).ok_or(())?;
+
+		let data = parse_rdata_tokens(ty, toks.as_slice(), origin.as_ref())?;
+		let ttl = ttl.or(default_ttl).or(last_ttl);
+		res.push((parse_rdata(owner.clone(), ty, &data, &[])?, ttl));
+		last_owner = Some(owner);
+		last_ttl = ttl;
+	}
+	Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use alloc::string::ToString;
+	use alloc::vec;
+
+	#[test]
+	fn parse_zone() {
+		let zone = "$ORIGIN example.com.\n\
+			$TTL 1h ; default TTL\n\
+			@ 3600 IN SOA ns1 hostmaster.example.com. (\n\
+				2024010101 ; serial\n\
+				1d 2h 4w 300 )\n\
+			\tIN NS ns1\n\
+			ns1 IN 300 A 192.0.2.1\n\
+			\tAAAA 2001:db8::1\n\
+			txt TXT \"a \\\"quoted\\\" string\" unquoted \"\\255\\\\\"\n\
+			_443._tcp.www TLSA 3 1 1 ( 0123456789ABCDEF\n\
+				0123456789abcdef )\n\
+			unknown IN TYPE1 \\# 4 C0000202\n\
+			a\\.b.c. CNAME @\n";
+		let ttls = parse_zone_file_with_ttls(zone).unwrap().into_iter().map(|(_, ttl)| ttl).collect::<Vec<_>>();
+		assert_eq!(ttls, vec![3600, 3600, 300, 3600, 3600, 3600, 3600, 3600]);
+		let mut rrs = parse_zone_file(zone).unwrap();
+		// Each character-string is kept as a separate chunk in the TXT record
+		if let RR::Txt(txt) = rrs.remove(4) {
+			assert_eq!(txt.name.as_str(), "txt.example.com.");
+			assert_eq!(txt.data.as_vec(), b"a \"quoted\" stringunquoted\xff\\");
+			assert_eq!(txt.data.serialized_len(), 30);
+		} else { panic!(); }
+		let example: Name = "example.com.".try_into().unwrap();
+		let ns1: Name = "ns1.example.com.".try_into().unwrap();
+		assert_eq!(rrs, vec![
+			SOA {
+				name: example.clone(), primary_ns: ns1.clone(),
+				mailbox: "hostmaster.example.com.".try_into().unwrap(), serial: 2024010101,
+				refresh: 86400, retry: 7200, expire: 2419200, minimum: 300,
+			}.into(),
+			NS { name: example.clone(), name_server: ns1.clone() }.into(),
+			A { name: ns1.clone(), address: [192, 0, 2, 1] }.into(),
+			AAAA { name: ns1, address: [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1] }.into(),
+			TLSA {
+				name: "_443._tcp.www.example.com.".try_into().unwrap(), cert_usage: 3, selector: 1,
+				data_ty: 1, data: vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+			}.into(),
+			A { name: "unknown.example.com.".try_into().unwrap(), address: [192, 0, 2, 2] }.into(),
+			CName { name: "a\\.b.c.".try_into().unwrap(), canonical_name: example }.into(),
+		]);
+
+		assert_eq!(parse_zone_file("a. A 1.2.3.4").unwrap().len(), 1);
+		assert!(parse_zone_file_with_ttls("a. A 1.2.3.4").is_err());
+		assert_eq!(parse_zone_file_with_ttls("a. 60 A 1.2.3.4\nb. A 1.2.3.4").unwrap()[1].1, 60);
+		for invalid in ["a A 1.2.3.4", "$ORIGIN . ( \n a. A 1.2.3.4", " A 1.2.3.4",
+			"a. CH A 1.2.3.4", "a. A 1.2.3.4 5", "a. IN IN A 1.2.3.4", "$INCLUDE foo", "a. A \"1.2.3.4\"",
+			"a. TXT \"unterminated", "a. TXT \"\\256\"", "a. DS 1 2 3 ABC", "a. TYPE1 \\# 4 C00002",
+			"a. RRSIG A 13 1 3600 202\u{e9}101200000 20240101000000 1 a. AAAA",
+			// Generic RDATA can't contain compression pointers, as there's no packet to point into
+			"a. SOA \\# 25 016100C000 0000000000000000000000000000000000000000"]
+		{
+			assert!(parse_zone_file(invalid).is_err(), "{}", invalid);
+		}
+	}
+
+	#[test]
+	fn presentation_round_trip() {
+		let rrs: Vec<RR> = vec![
+			AAAA { name: "a.".try_into().unwrap(), address: [0; 16] }.into(),
+			AAAA { name: "a.".try_into().unwrap(), address: [0x20, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1] }.into(),
+			Txt { name: "a.".try_into().unwrap(), data: TxtBytes::new(&[b'x'; 300]).unwrap() }.into(),
+			Txt { name: "a.".try_into().unwrap(), data: TxtBytes::new(&[]).unwrap() }.into(),
+			URI {
+				name: "_http._tcp.a.".try_into().unwrap(), priority: 1, weight: 2,
+				target: "https://example.com/\"".into(),
+			}.into(),
+			NAPTR {
+				name: "a.".try_into().unwrap(), order: 1, preference: 2, flags: b"u".to_vec(),
+				services: b"E2U+sip".to_vec(), regexp: b"!^.*$!sip:a@b!".to_vec(),
+				replacement: ".".try_into().unwrap(),
+			}.into(),
+			CERT { name: "a.".try_into().unwrap(), cert_type: 1, key_tag: 2, alg: 3, cert: vec![4; 20] }.into(),
+			DnsKey { name: ".".try_into().unwrap(), flags: 257, protocol: 3, alg: 8, pubkey: vec![1, 2, 3, 4] }.into(),
+			DS { name: "com.".try_into().unwrap(), key_tag: 19718, alg: 13, digest_type: 2, digest: vec![0xab; 32] }.into(),
+			RRSig {
+				name: "a.".try_into().unwrap(), ty: Txt::TYPE, alg: 13, labels: 1, orig_ttl: 3600,
+				expiration: 1710201600, inception: 0, key_tag: 1234, key_name: "a.".try_into().unwrap(),
+				signature: vec![42; 64],
+			}.into(),
+			NSec {
+				name: "a.".try_into().unwrap(), next_name: "b.a.".try_into().unwrap(),
+				types: NSecTypeMask::from_types(&[A::TYPE, 15, RRSig::TYPE, NSec::TYPE, 1234]),
+			}.into(),
+			NSec3 {
+				name: "2vptu5timamqttgl4luu9kg21e0aor3s.a.".try_into().unwrap(), hash_algo: 1, flags: 0,
+				hash_iterations: 0, salt: Vec::new(), next_name_hash: vec![0x42; 20],
+				types: NSecTypeMask::from_types(&[Txt::TYPE, RRSig::TYPE]),
+			}.into(),
+			OpenPGPKey { name: "a.".try_into().unwrap(), pubkey: vec![0x99; 10] }.into(),
+			ZoneMD { name: "a.".try_into().unwrap(), serial: 1, scheme: 1, hash_alg: 1, digest: vec![0x11; 48] }.into(),
+		];
+		for rr in rrs {
+			assert_eq!(parse_zone_file(&rr.to_string()).unwrap(), vec![rr.clone()], "{}", rr);
+			let with_ttl = rr.display_with_ttl(3600).to_string();
+			assert_eq!(parse_zone_file_with_ttls(&with_ttl).unwrap(), vec![(rr.clone(), 3600)], "{}", with_ttl);
+		}
+
+		let rrsig = RRSig {
+			name: "a.".try_into().unwrap(), ty: Txt::TYPE, alg: 13, labels: 1, orig_ttl: 3600,
+			expiration: 1710201600, inception: 1708387200, key_tag: 1234,
+			key_name: "a.".try_into().unwrap(), signature: vec![42; 3],
+		};
+		assert_eq!(rrsig.to_string(), "a. 0 IN RRSIG TXT 13 1 3600 20240312000000 20240220000000 1234 a. Kioq");
+		let nsec = NSec {
+			name: "a.".try_into().unwrap(), next_name: "b.a.".try_into().unwrap(),
+			types: NSecTypeMask::from_types(&[A::TYPE, 15, 1234]),
+		};
+		assert_eq!(nsec.to_string(), "a. 0 IN NSEC b.a. A MX TYPE1234");
+		assert_eq!(RR::from(nsec).display_with_ttl(86400).to_string(), "a. 86400 IN NSEC b.a. A MX TYPE1234");
+
+		for (addr, presentation) in [
+			("2001:db8::1", "2001:db8::1"), ("2001:DB8:0:0:1:0:0:1", "2001:db8::1:0:0:1"),
+			("2001:db8:0:1:1:1:1:1", "2001:db8:0:1:1:1:1:1"), ("::", "::"), ("::1.2.3.4", "::102:304"),
+			("1::", "1::"),
+		] {
+			let rrs = parse_zone_file(&alloc::format!("a. AAAA {}", addr)).unwrap();
+			assert_eq!(rrs[0].to_string(), alloc::format!("a. 0 IN AAAA {}", presentation));
+		}
+		for invalid in ["1::2::3", "1:2:3:4:5:6:7", "1:2:3:4:5:6:7:8:9", "1::2:3:4:5:6:7:8", "+1::", "12345::", "1.2.3.4::"] {
+			assert!(parse_zone_file(&alloc::format!("a. AAAA {}", invalid)).is_err(), "{}", invalid);
+		}
+	}
+}
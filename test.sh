@@ -11,6 +11,8 @@ cargo $RUST_VERSION test --no-default-features --features validation
 cargo $RUST_VERSION test --features std,tokio,validation
 cargo $RUST_VERSION test --features std,tokio,validation,slower_smaller_binary
 cargo $RUST_VERSION test --no-default-features --features build_server
+cargo $RUST_VERSION test --no-default-features --features serde
+cargo $RUST_VERSION test --features std,tokio,validation,serde
 cargo $RUST_VERSION build --lib
 cargo $RUST_VERSION build --lib --features std
 cargo $RUST_VERSION build --lib --features tokio
@@ -18,7 +20,16 @@ cargo $RUST_VERSION build --lib --features validation
 cargo $RUST_VERSION build --lib --features std,tokio,validation
 cargo $RUST_VERSION build --lib --features std,tokio,validation --release
 cargo $RUST_VERSION build --bin http_proof_gen --features build_server
-cargo $RUST_VERSION doc --features std,tokio,validation
+cargo $RUST_VERSION doc --features std,tokio,validation,serde
+
+# rustls requires a newer rustc than our MSRV
+if [ "$RUST_VERSION" = "" ]; then
+	cargo test --no-default-features --features doh
+	cargo test --features tokio,tokio-rustls,validation,doh
+	cargo test --no-default-features --features dot
+	cargo test --features tokio,tokio-rustls,validation,dot
+	cargo doc --features std,tokio,tokio-rustls,validation,serde,doh,dot
+fi
 cd fuzz
 RUSTFLAGS="--cfg=fuzzing --cfg=dnssec_prover_fuzzing" RUSTC_BOOTSTRAP=1 cargo build --features stdin_fuzz
 RUSTFLAGS="--cfg=fuzzing --cfg=dnssec_prover_fuzzing" RUSTC_BOOTSTRAP=1 cargo test
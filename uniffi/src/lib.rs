@@ -4,7 +4,7 @@ uniffi::include_scaffolding!("interface");
 
 use dnssec_prover::ser::parse_rr_stream;
 use dnssec_prover::validation::{verify_rr_stream, ValidationError};
-use dnssec_prover::rr::Name;
+use dnssec_prover::rr::{Name, RR};
 use dnssec_prover::query::ProofBuilder as NativeProofBuilder;
 pub use dnssec_prover::query::ProofBuildingError;
 use dnssec_prover::query::{QueryBuf};
@@ -31,6 +31,12 @@ pub fn init_proof_builder(mut name: String, ty: u16) -> Option<Arc<ProofBuilder>
 	}
 }
 
+/// Gets the record type for the given mnemonic (e.g. `"TXT"`, ignoring case), if it is one of the
+/// types supported by this library, for use with [`init_proof_builder`].
+pub fn type_from_name(name: String) -> Option<u16> {
+	RR::ty_from_name(&name)
+}
+
 impl ProofBuilder {
 	/// Processes a response to a query previously fetched from [`get_next_query`].
 	///
@@ -9,14 +9,8 @@ export async function lookup_doh(domain, ty, doh_endpoint) {
 	await init();
 
 	if (!domain.endsWith(".")) domain += ".";
-	if (ty.toLowerCase() == "txt") {
-		ty = 16;
-	} else if (ty.toLowerCase() == "tlsa") {
-		ty = 52;
-	} else if (ty.toLowerCase() == "a") {
-		ty = 1;
-	} else if (ty.toLowerCase() == "aaaa") {
-		ty = 28;
+	if (typeof(ty) == "string") {
+		ty = wasm.type_from_name(ty);
 	}
 	if (typeof(ty) == "number") {
 		var builder = wasm.init_proof_builder(domain, ty);